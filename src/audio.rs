@@ -0,0 +1,178 @@
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Every sound used by the games, embedded in the executable
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Sound {
+    Apple,
+    Ball,
+    Bonus,
+    Death,
+    Golden,
+    Paddle,
+    Shrink,
+    Slow,
+    Wall,
+}
+
+impl Sound {
+    fn bytes(&self) -> &'static [u8] {
+        match self {
+            Sound::Apple => include_bytes!("../assets/apple.wav"),
+            Sound::Ball => include_bytes!("../assets/ball.wav"),
+            Sound::Bonus => include_bytes!("../assets/bonus.wav"),
+            Sound::Death => include_bytes!("../assets/death.wav"),
+            Sound::Golden => include_bytes!("../assets/golden.wav"),
+            Sound::Paddle => include_bytes!("../assets/paddle.wav"),
+            Sound::Shrink => include_bytes!("../assets/shrink.wav"),
+            Sound::Slow => include_bytes!("../assets/slow.wav"),
+            Sound::Wall => include_bytes!("../assets/wall.wav"),
+        }
+    }
+
+    /// Length in seconds, the sound can't be played again until it's finished
+    fn duration(&self) -> f64 {
+        match self {
+            Sound::Apple => 0.25,
+            Sound::Ball => 0.4,
+            Sound::Bonus | Sound::Slow => 0.35,
+            Sound::Death => 3.2,
+            Sound::Golden | Sound::Shrink => 0.3,
+            Sound::Paddle | Sound::Wall => 0.2,
+        }
+    }
+
+    fn channel(&self) -> Channel {
+        Channel::Sfx
+    }
+}
+
+/// Volumes are multiplied by [Channel::Master]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Channel {
+    Master,
+    /// No game has music yet
+    Music,
+    Sfx,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Music, Channel::Sfx];
+}
+
+/// Plays sounds for every game, owned by [GameHost][crate::GameHost] and passed to [Game::update][crate::Game::update]
+///
+/// Sounds are decoded the first time they're played and then reused,
+/// if there's no audio device nothing is played
+pub struct Audio {
+    engine: Option<AudioEngine>,
+    effects: HashMap<Sound, SoundEffect>,
+    /// 0.0 to 1.0, indexed by [Channel]
+    volumes: [f32; Channel::ALL.len()],
+    muted: bool,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        match AudioEngine::new() {
+            Ok(engine) => Self {
+                engine: Some(engine),
+                ..Self::null()
+            },
+            Err(e) => {
+                error!("Unable to init audio: {e}");
+                Self::null()
+            }
+        }
+    }
+
+    /// Silent
+    pub fn null() -> Self {
+        Self {
+            engine: None,
+            effects: HashMap::new(),
+            volumes: [1.0; Channel::ALL.len()],
+            muted: false,
+        }
+    }
+}
+
+impl Audio {
+    /// Does nothing if `sound` is already playing
+    pub fn play(&mut self, sound: Sound) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let volume = self.effective_volume(sound.channel());
+        let effect = match self.effects.entry(sound) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match engine.load_from_bytes(sound.bytes(), sound.duration()) {
+                Ok(effect) => entry.insert(effect),
+                Err(e) => {
+                    error!("Unable to load {sound:?}: {e:?}");
+                    return;
+                }
+            },
+        };
+        effect.set_volume(volume);
+        effect.play();
+    }
+
+    /// Called by the host every update so sounds can finish and be played again
+    pub fn update(&mut self, timing: &Timing) {
+        for effect in self.effects.values_mut() {
+            effect.update(timing);
+        }
+    }
+
+    pub fn volume(&self, channel: Channel) -> f32 {
+        self.volumes[channel as usize]
+    }
+
+    /// `volume` is clamped to 0.0 to 1.0
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.volumes[channel as usize] = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    fn effective_volume(&self, channel: Channel) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        match channel {
+            Channel::Master => self.volume(Channel::Master),
+            _ => self.volume(Channel::Master) * self.volume(channel),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channels_are_scaled_by_master() {
+        let mut audio = Audio::null();
+        audio.set_volume(Channel::Master, 0.5);
+        audio.set_volume(Channel::Sfx, 0.5);
+        audio.set_volume(Channel::Music, 2.0);
+        assert_eq!(audio.volume(Channel::Music), 1.0);
+        assert_eq!(audio.effective_volume(Channel::Sfx), 0.25);
+        assert_eq!(audio.effective_volume(Channel::Music), 0.5);
+        assert_eq!(audio.effective_volume(Channel::Master), 0.5);
+        audio.set_muted(true);
+        assert_eq!(audio.effective_volume(Channel::Sfx), 0.0);
+    }
+
+    #[test]
+    fn null_backend_is_silent() {
+        let mut audio = Audio::null();
+        audio.play(Sound::Death);
+        assert!(audio.effects.is_empty());
+    }
+}
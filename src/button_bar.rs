@@ -1,5 +1,8 @@
+use crate::input::{bindings, key_label, Action, Bindings, PadButton, Player};
+use crate::CLR_3;
 use log::error;
-use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::Center;
+use pixels_graphics_lib::prelude::PixelFont::{Limited3x5, Standard4x5};
 use pixels_graphics_lib::prelude::*;
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -13,10 +16,14 @@ static ICONS: OnceLock<HashMap<ButtonDef, HashMap<Option<Controller>, IndexedIma
     OnceLock::new();
 
 fn get_icon(def: ButtonDef, controller: Option<Controller>) -> &'static IndexedImage {
+    let def = match def {
+        ButtonDef::P2Vert => ButtonDef::Vert,
+        _ => def,
+    };
     &ICONS.get_or_init(|| {
         HashMap::from([
             (
-                ButtonDef::Space,
+                ButtonDef::Confirm,
                 HashMap::from([
                     (
                         Some(Controller::Playstation),
@@ -53,7 +60,7 @@ fn get_icon(def: ButtonDef, controller: Option<Controller>) -> &'static IndexedI
                 ]),
             ),
             (
-                ButtonDef::Escape,
+                ButtonDef::Back,
                 HashMap::from([
                     (
                         Some(Controller::Playstation),
@@ -204,6 +211,75 @@ fn get_icon(def: ButtonDef, controller: Option<Controller>) -> &'static IndexedI
     })[&def][&controller]
 }
 
+enum Icon {
+    Image(&'static IndexedImage),
+    /// Used when the button has been rebound to something without an image
+    Label(String),
+}
+
+/// Image for `def` if its current binding matches the image, otherwise a text label
+fn resolve_icon(def: ButtonDef, controller: Option<Controller>, bindings: &Bindings) -> Icon {
+    let actions = def.actions();
+    let labels: Vec<String> = if controller.is_none() {
+        let keys: Vec<Option<KeyCode>> = actions
+            .iter()
+            .map(|action| bindings.primary_key(def.player(), *action))
+            .collect();
+        if keys
+            .iter()
+            .copied()
+            .eq(def.image_keys().iter().copied().map(Some))
+        {
+            return Icon::Image(get_icon(def, controller));
+        }
+        keys.iter()
+            .map(|key| key.map(key_label).unwrap_or(String::from("-")))
+            .collect()
+    } else {
+        let buttons: Vec<Option<PadButton>> =
+            actions.iter().map(|action| bindings.pad(*action)).collect();
+        if buttons
+            .iter()
+            .copied()
+            .eq(def.image_buttons().iter().copied().map(Some))
+        {
+            return Icon::Image(get_icon(def, controller));
+        }
+        buttons
+            .iter()
+            .map(|button| button.map(|b| b.label(controller)).unwrap_or("-"))
+            .map(String::from)
+            .collect()
+    };
+    if labels.len() == 1 {
+        Icon::Label(labels[0].clone())
+    } else {
+        Icon::Label(
+            labels
+                .iter()
+                .filter_map(|label| label.chars().next())
+                .collect(),
+        )
+    }
+}
+
+fn draw_label_icon(graphics: &mut Graphics, pos: Coord, label: &str) {
+    graphics.draw_rect(
+        Rect::new_with_size(pos + (1, 1), ICON_SIZE.0 - 3, ICON_SIZE.1 - 3),
+        stroke(CLR_3),
+    );
+    let font = if label.len() > 3 {
+        Limited3x5
+    } else {
+        Standard4x5
+    };
+    graphics.draw_text(
+        label,
+        TextPos::px(pos + (ICON_SIZE.0 / 2, ICON_SIZE.1 / 2)),
+        (CLR_3, font, Center),
+    );
+}
+
 pub struct ButtonBar {
     width: usize,
     position: Coord,
@@ -239,23 +315,37 @@ impl ButtonBar {
             return;
         }
         let section_width = self.width / buttons.len();
-        for (i, (label, def)) in buttons.iter().enumerate() {
-            let padding = (section_width - widths[i]) / 2;
-            let x = section_width * i;
-            self.buttons.push((label, *def, coord!(x + padding, 0)));
+        if widths.iter().all(|width| *width <= section_width) {
+            for (i, (label, def)) in buttons.iter().enumerate() {
+                let padding = (section_width - widths[i]) / 2;
+                let x = section_width * i;
+                self.buttons.push((label, *def, coord!(x + padding, 0)));
+            }
+        } else {
+            //too wide for equal sections so spread the spare space between them instead
+            let gap = (self.width - widths.iter().sum::<usize>()) / (buttons.len() + 1);
+            let mut x = gap;
+            for (i, (label, def)) in buttons.iter().enumerate() {
+                self.buttons.push((label, *def, coord!(x, 0)));
+                x += widths[i] + gap;
+            }
         }
     }
 }
 
 impl ButtonBar {
     pub fn render(&self, graphics: &mut Graphics, active_controller: Option<Controller>) {
+        let bindings = bindings();
         graphics.with_translate(self.position, |g| {
             for (name, def, pos) in &self.buttons {
-                g.draw_indexed_image(pos, get_icon(*def, active_controller));
+                match resolve_icon(*def, active_controller, &bindings) {
+                    Icon::Image(image) => g.draw_indexed_image(pos, image),
+                    Icon::Label(label) => draw_label_icon(g, *pos, &label),
+                }
                 g.draw_text(
                     name,
                     TextPos::px(*pos + (ICON_SIZE.0 + ICON_PADDING, 6)),
-                    (GB_3, Standard4x5),
+                    (CLR_3, Standard4x5),
                 );
             }
         });
@@ -264,9 +354,67 @@ impl ButtonBar {
 
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub enum ButtonDef {
-    Escape,
-    Space,
+    Back,
+    Confirm,
     Horz,
     Vert,
     Cursor,
+    /// Up and down for the second player
+    P2Vert,
+    /// Always shown as a label, there's no image for it
+    Secondary,
+}
+
+impl ButtonDef {
+    fn player(&self) -> Player {
+        match self {
+            ButtonDef::P2Vert => Player::Two,
+            _ => Player::One,
+        }
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        match self {
+            ButtonDef::Back => &[Action::Back],
+            ButtonDef::Confirm => &[Action::Confirm],
+            ButtonDef::Horz => &[Action::Left, Action::Right],
+            ButtonDef::Vert | ButtonDef::P2Vert => &[Action::Up, Action::Down],
+            ButtonDef::Cursor => &[Action::Up, Action::Down, Action::Left, Action::Right],
+            ButtonDef::Secondary => &[Action::Secondary],
+        }
+    }
+
+    /// Keys shown in the keyboard image for this button, see [ButtonDef::actions]
+    fn image_keys(&self) -> &'static [KeyCode] {
+        match self {
+            ButtonDef::Back => &[KeyCode::Escape],
+            ButtonDef::Confirm => &[KeyCode::Space],
+            ButtonDef::Horz => &[KeyCode::ArrowLeft, KeyCode::ArrowRight],
+            ButtonDef::Vert | ButtonDef::P2Vert => &[KeyCode::ArrowUp, KeyCode::ArrowDown],
+            ButtonDef::Cursor => &[
+                KeyCode::ArrowUp,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowRight,
+            ],
+            ButtonDef::Secondary => &[],
+        }
+    }
+
+    /// Buttons shown in the controller images for this button, see [ButtonDef::actions]
+    fn image_buttons(&self) -> &'static [PadButton] {
+        match self {
+            ButtonDef::Back => &[PadButton::East],
+            ButtonDef::Confirm => &[PadButton::South],
+            ButtonDef::Horz => &[PadButton::DPadLeft, PadButton::DPadRight],
+            ButtonDef::Vert | ButtonDef::P2Vert => &[PadButton::DPadUp, PadButton::DPadDown],
+            ButtonDef::Cursor => &[
+                PadButton::DPadUp,
+                PadButton::DPadDown,
+                PadButton::DPadLeft,
+                PadButton::DPadRight,
+            ],
+            ButtonDef::Secondary => &[],
+        }
+    }
 }
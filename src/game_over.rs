@@ -0,0 +1,26 @@
+use crate::{CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::Center;
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+const PANEL: (isize, isize, isize, isize) = (25, 67, 135, 100);
+
+/// Framed GAME OVER box with the seed under it, drawn over the play area once the last life is lost
+pub fn draw_game_over(graphics: &mut Graphics, seed: u64) {
+    let (x1, y1, x2, y2) = PANEL;
+    graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+    graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+    graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+    graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+    graphics.draw_text(
+        "GAME OVER",
+        TextPos::px(coord!(SCREEN_WIDTH / 2, 79)),
+        (CLR_3, Standard8x10, Center),
+    );
+    graphics.draw_text(
+        &format!("SEED {seed}"),
+        TextPos::px(coord!(SCREEN_WIDTH / 2, 93)),
+        (CLR_2, Standard4x5, Center),
+    );
+}
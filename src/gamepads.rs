@@ -0,0 +1,107 @@
+use crate::input::PadButton;
+use gilrs::{Button, Event, EventType, GamepadId, Gilrs};
+use log::error;
+use pixels_graphics_lib::prelude::Controller;
+use std::collections::HashMap;
+
+/// Tracks each connected gamepad separately so that two players can have a controller each
+///
+/// Replaces [GameController][pixels_graphics_lib::prelude::GameController] which merges all pads
+pub struct Gamepads {
+    gilrs: Option<Gilrs>,
+    masks: HashMap<GamepadId, u16>,
+    last_used: Option<GamepadId>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                error!("Unable to init controllers: {e:?}");
+                None
+            }
+        };
+        Self {
+            gilrs,
+            masks: HashMap::new(),
+            last_used: None,
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn update(&mut self) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while let Some(Event { id, event, .. }) = gilrs.next_event() {
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    self.last_used = Some(id);
+                    if let Some(button) = pad_button(button) {
+                        *self.masks.entry(id).or_default() |= button.mask();
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = pad_button(button) {
+                        *self.masks.entry(id).or_default() &= !button.mask();
+                    }
+                }
+                EventType::Disconnected => {
+                    self.masks.remove(&id);
+                    if self.last_used == Some(id) {
+                        self.last_used = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Buttons held on each connected pad, in connection order
+    pub fn masks(&self) -> Vec<u16> {
+        let Some(gilrs) = &self.gilrs else {
+            return vec![];
+        };
+        gilrs
+            .gamepads()
+            .filter(|(_, pad)| pad.is_connected())
+            .map(|(id, _)| self.masks.get(&id).copied().unwrap_or_default())
+            .collect()
+    }
+
+    /// Type of the pad that was last used, if it's recognised
+    // using http://www.linux-usb.org/usb.ids as source
+    pub fn controller_type(&self) -> Option<Controller> {
+        let gilrs = self.gilrs.as_ref()?;
+        let uuid = gilrs.gamepad(self.last_used?).uuid();
+        let vendor = u16::from_le_bytes([uuid[4], uuid[5]]);
+        let product = u16::from_le_bytes([uuid[8], uuid[9]]);
+        match (vendor, product) {
+            (0x54c, 0xdf2 | 0xce6 | 0xcda | 0x9cc | 0x5c4 | 0x268) => Some(Controller::Playstation),
+            (
+                0x45e,
+                0x202 | 0x285 | 0x289 | 0x28e | 0x28f | 0x2d1 | 0x2dd | 0x2e0 | 0x2e3 | 0x2ea
+                | 0x2fd | 0xb12 | 0xb00,
+            ) => Some(Controller::Xbox),
+            (0x57e, 0x2009) => Some(Controller::Switch),
+            _ => None,
+        }
+    }
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+    match button {
+        Button::DPadUp => Some(PadButton::DPadUp),
+        Button::DPadDown => Some(PadButton::DPadDown),
+        Button::DPadLeft => Some(PadButton::DPadLeft),
+        Button::DPadRight => Some(PadButton::DPadRight),
+        Button::South => Some(PadButton::South),
+        Button::East => Some(PadButton::East),
+        Button::North => Some(PadButton::North),
+        Button::West => Some(PadButton::West),
+        Button::Start => Some(PadButton::Start),
+        _ => None,
+    }
+}
@@ -0,0 +1,952 @@
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::game_over::draw_game_over;
+use crate::games::asteroids::State::*;
+use crate::high_scores::submit_score;
+use crate::input::{Action, Input};
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::{LeftTop, RightTop};
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+use std::f32::consts::TAU;
+
+pub const ID: &str = "asteroids";
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+const WIDTH: f32 = SCREEN_WIDTH as f32;
+const HEIGHT: f32 = PLAY_HEIGHT as f32;
+const CENTER: (f32, f32) = (WIDTH / 2.0, HEIGHT / 2.0);
+
+//radians per second
+const SHIP_TURN_SPEED: f32 = 4.5;
+//pixels per second per second
+const SHIP_THRUST: f32 = 100.0;
+const SHIP_MAX_SPEED: f32 = 100.0;
+//fraction of the ship's speed kept after a second without thrust
+const SHIP_DRAG: f32 = 0.5;
+const SHIP_RADIUS: f32 = 4.0;
+const SHIP_SHAPE: [(f32, f32); 4] = [(0.0, -5.0), (3.5, 4.0), (0.0, 2.0), (-3.5, 4.0)];
+const FLAME_SHAPE: [(f32, f32); 3] = [(-2.0, 3.0), (0.0, 7.0), (2.0, 3.0)];
+const RESPAWN_DELAY: f64 = 2.0;
+//the ship only respawns when no asteroids are this close to the center
+const SAFE_DISTANCE: f32 = 30.0;
+const HYPERSPACE_DURATION: f64 = 0.6;
+const STARTING_LIVES: usize = 3;
+const EXTRA_LIFE_SCORE: usize = 10000;
+
+const BULLET_SPEED: f32 = 130.0;
+const BULLET_LIFE: f64 = 0.9;
+const MAX_BULLETS: usize = 4;
+
+const WAVE_START_ASTEROIDS: usize = 4;
+const MAX_WAVE_ASTEROIDS: usize = 8;
+const WAVE_DELAY: f64 = 2.0;
+const ASTEROID_POINTS: usize = 10;
+
+const UFO_DELAY: f64 = 15.0;
+const UFO_SPEED: f32 = 30.0;
+const UFO_TURN_RATE: f64 = 1.0;
+const UFO_FIRE_RATE: f64 = 1.2;
+const UFO_BULLET_SPEED: f32 = 80.0;
+const UFO_BULLET_LIFE: f64 = 1.2;
+//after this score every UFO is small
+const SMALL_UFO_SCORE: usize = 10000;
+const SMALL_UFO_CHANCE: f32 = 0.25;
+//radians either side of the ship that small UFOs miss by
+const SMALL_UFO_AIM_ERROR: f32 = 0.2;
+const UFO_SHAPE: [(f32, f32); 6] = [
+    (-7.0, 0.0),
+    (-3.0, -2.0),
+    (3.0, -2.0),
+    (7.0, 0.0),
+    (3.0, 2.0),
+    (-3.0, 2.0),
+];
+const UFO_DOME: [(f32, f32); 4] = [(-2.0, -2.0), (-1.0, -4.0), (1.0, -4.0), (2.0, -2.0)];
+
+const PARTICLE_LIFE: f64 = 0.6;
+const PARTICLE_SPEED: f32 = 30.0;
+
+/// Wrap `pos` so it's on screen
+fn wrap(pos: (f32, f32)) -> (f32, f32) {
+    (pos.0.rem_euclid(WIDTH), pos.1.rem_euclid(HEIGHT))
+}
+
+/// Shortest offset from `from` to `to`, which may be across an edge
+fn offset(from: (f32, f32), to: (f32, f32)) -> (f32, f32) {
+    let wrap_delta = |delta: f32, size: f32| (delta + size / 2.0).rem_euclid(size) - size / 2.0;
+    (
+        wrap_delta(to.0 - from.0, WIDTH),
+        wrap_delta(to.1 - from.1, HEIGHT),
+    )
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = offset(a, b);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Rotate `point` clockwise by `angle` radians
+fn rotate(point: (f32, f32), angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (point.0 * cos - point.1 * sin, point.0 * sin + point.1 * cos)
+}
+
+/// Unit vector for `angle`, 0 is up and angles are clockwise
+fn heading(angle: f32) -> (f32, f32) {
+    (angle.sin(), -angle.cos())
+}
+
+/// Copies of `pos` needed to draw something of `radius` across the edges of the screen
+fn wrapped_positions(pos: (f32, f32), radius: f32) -> Vec<(f32, f32)> {
+    let mut xs = vec![pos.0];
+    if pos.0 < radius {
+        xs.push(pos.0 + WIDTH);
+    } else if pos.0 > WIDTH - radius {
+        xs.push(pos.0 - WIDTH);
+    }
+    let mut ys = vec![pos.1];
+    if pos.1 < radius {
+        ys.push(pos.1 + HEIGHT);
+    } else if pos.1 > HEIGHT - radius {
+        ys.push(pos.1 - HEIGHT);
+    }
+    xs.iter()
+        .flat_map(|x| ys.iter().map(move |y| (*x, *y)))
+        .collect()
+}
+
+/// Outline joining `points`, which are relative to `pos`
+fn draw_shape(
+    graphics: &mut Graphics,
+    pos: (f32, f32),
+    points: &[(f32, f32)],
+    radius: f32,
+    color: Color,
+) {
+    for pos in wrapped_positions(pos, radius) {
+        let coords: Vec<Coord> = points
+            .iter()
+            .map(|(x, y)| coord!((pos.0 + x).round(), (pos.1 + y).round()))
+            .collect();
+        graphics.draw_polygon(Polygon::new(&coords), stroke(color));
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Playing,
+    /// Ship is gone and will reappear somewhere random
+    Hyperspace,
+    /// Ship was destroyed, it comes back once the center is clear
+    Respawning,
+    Dead,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Size {
+    Large,
+    Medium,
+    Small,
+}
+
+impl Size {
+    fn radius(&self) -> f32 {
+        match self {
+            Size::Large => 12.0,
+            Size::Medium => 7.0,
+            Size::Small => 4.0,
+        }
+    }
+
+    fn score(&self) -> usize {
+        match self {
+            Size::Large => 2 * ASTEROID_POINTS,
+            Size::Medium => 5 * ASTEROID_POINTS,
+            Size::Small => 10 * ASTEROID_POINTS,
+        }
+    }
+
+    /// Pixels per second
+    fn speed(&self) -> (f32, f32) {
+        match self {
+            Size::Large => (12.0, 25.0),
+            Size::Medium => (20.0, 40.0),
+            Size::Small => (30.0, 55.0),
+        }
+    }
+
+    /// Size of the pieces this splits into when hit
+    fn smaller(&self) -> Option<Size> {
+        match self {
+            Size::Large => Some(Size::Medium),
+            Size::Medium => Some(Size::Small),
+            Size::Small => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Asteroid {
+    pos: (f32, f32),
+    velocity: (f32, f32),
+    size: Size,
+    /// Outline relative to the center before rotating
+    shape: Vec<(f32, f32)>,
+    angle: f32,
+    /// Radians per second
+    spin: f32,
+}
+
+impl Asteroid {
+    fn new(pos: (f32, f32), size: Size, rng: &mut Rng) -> Self {
+        let (min, max) = size.speed();
+        let speed = min + rng.f32() * (max - min);
+        let direction = rng.f32() * TAU;
+        let points = 9 + rng.usize(0..3);
+        let shape = (0..points)
+            .map(|i| {
+                let angle = i as f32 / points as f32 * TAU;
+                let radius = size.radius() * (0.75 + rng.f32() * 0.35);
+                (angle.sin() * radius, -angle.cos() * radius)
+            })
+            .collect();
+        Self {
+            pos,
+            velocity: (direction.sin() * speed, -direction.cos() * speed),
+            size,
+            shape,
+            angle: 0.0,
+            spin: (rng.f32() * 2.0 - 1.0) * 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bullet {
+    pos: (f32, f32),
+    velocity: (f32, f32),
+    /// Seconds left
+    life: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Ufo {
+    pos: (f32, f32),
+    velocity: (f32, f32),
+    small: bool,
+    next_turn: Timer,
+    next_fire: Timer,
+}
+
+impl Ufo {
+    fn scale(&self) -> f32 {
+        if self.small {
+            0.6
+        } else {
+            1.0
+        }
+    }
+
+    fn radius(&self) -> f32 {
+        7.0 * self.scale()
+    }
+
+    fn score(&self) -> usize {
+        if self.small {
+            1000
+        } else {
+            200
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Particle {
+    pos: (f32, f32),
+    velocity: (f32, f32),
+    life: f64,
+}
+
+pub struct Asteroids {
+    ship_pos: (f32, f32),
+    ship_velocity: (f32, f32),
+    /// Radians clockwise from up
+    ship_angle: f32,
+    thrusting: bool,
+    asteroids: Vec<Asteroid>,
+    bullets: Vec<Bullet>,
+    ufo: Option<Ufo>,
+    ufo_bullets: Vec<Bullet>,
+    particles: Vec<Particle>,
+    next_ufo: Timer,
+    respawn: Timer,
+    hyperspace: Timer,
+    /// Seconds until the next wave, only counts down once the asteroids are gone
+    next_wave: f64,
+    wave: usize,
+    lives: usize,
+    score: usize,
+    /// Score needed for the next extra life
+    next_extra_life: usize,
+    /// Toggled every update, used to flicker the thrust flame
+    flicker: bool,
+    state: State,
+    result: GameUpdateResult,
+    button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
+}
+
+impl Asteroids {
+    pub fn new(seed: u64) -> Box<Self> {
+        let mut asteroids = Box::new(Self {
+            ship_pos: CENTER,
+            ship_velocity: (0.0, 0.0),
+            ship_angle: 0.0,
+            thrusting: false,
+            asteroids: vec![],
+            bullets: vec![],
+            ufo: None,
+            ufo_bullets: vec![],
+            particles: vec![],
+            next_ufo: Timer::new_with_delay(UFO_DELAY, UFO_DELAY),
+            respawn: Timer::new_once(RESPAWN_DELAY),
+            hyperspace: Timer::new_once(HYPERSPACE_DURATION),
+            next_wave: WAVE_DELAY,
+            wave: 1,
+            lives: STARTING_LIVES,
+            score: 0,
+            next_extra_life: EXTRA_LIFE_SCORE,
+            flicker: false,
+            state: Playing,
+            result: Nothing,
+            button_bar: ButtonBar::new(
+                coord!(0, PLAY_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("PAUSE", ButtonDef::Back),
+                    ("SHIP", ButtonDef::Cursor),
+                    ("FIRE", ButtonDef::Confirm),
+                ],
+            ),
+            seed,
+            rng: Rng::with_seed(seed),
+        });
+        asteroids.start_wave();
+        asteroids
+    }
+}
+
+impl Asteroids {
+    /// Large asteroids around the edges, away from the ship
+    fn start_wave(&mut self) {
+        let count = (WAVE_START_ASTEROIDS + self.wave - 1).min(MAX_WAVE_ASTEROIDS);
+        while self.asteroids.len() < count {
+            let pos = (self.rng.f32() * WIDTH, self.rng.f32() * HEIGHT);
+            if distance(pos, self.ship_pos) > SAFE_DISTANCE * 2.0 {
+                self.asteroids
+                    .push(Asteroid::new(pos, Size::Large, &mut self.rng));
+            }
+        }
+        self.next_wave = WAVE_DELAY;
+    }
+
+    fn add_score(&mut self, points: usize, audio: &mut Audio) {
+        self.score += points;
+        if self.score >= self.next_extra_life {
+            self.next_extra_life += EXTRA_LIFE_SCORE;
+            self.lives += 1;
+            audio.play(Sound::Golden);
+        }
+    }
+
+    fn explode(&mut self, pos: (f32, f32), count: usize) {
+        for _ in 0..count {
+            let direction = self.rng.f32() * TAU;
+            let speed = PARTICLE_SPEED * (0.3 + self.rng.f32());
+            self.particles.push(Particle {
+                pos,
+                velocity: (direction.sin() * speed, -direction.cos() * speed),
+                life: PARTICLE_LIFE * (0.5 + self.rng.f64() * 0.5),
+            });
+        }
+    }
+
+    /// Break asteroid `idx` into smaller pieces, or destroy it if it's already small
+    fn split(&mut self, idx: usize, audio: &mut Audio) {
+        let asteroid = self.asteroids.remove(idx);
+        self.explode(asteroid.pos, 6);
+        audio.play(Sound::Wall);
+        if let Some(size) = asteroid.size.smaller() {
+            for _ in 0..2 {
+                self.asteroids
+                    .push(Asteroid::new(asteroid.pos, size, &mut self.rng));
+            }
+        }
+    }
+
+    fn kill_ship(&mut self, audio: &mut Audio) {
+        self.explode(self.ship_pos, 12);
+        audio.play(Sound::Death);
+        self.lives = self.lives.saturating_sub(1);
+        self.thrusting = false;
+        if self.lives == 0 {
+            self.state = Dead;
+            if self.result == Nothing {
+                self.result = submit_score(ID, self.score);
+            }
+        } else {
+            self.state = Respawning;
+            self.respawn.reset();
+        }
+    }
+
+    fn fire(&mut self, audio: &mut Audio) {
+        if self.bullets.len() >= MAX_BULLETS {
+            return;
+        }
+        let direction = heading(self.ship_angle);
+        let nose = rotate(SHIP_SHAPE[0], self.ship_angle);
+        self.bullets.push(Bullet {
+            pos: wrap((self.ship_pos.0 + nose.0, self.ship_pos.1 + nose.1)),
+            velocity: (
+                self.ship_velocity.0 + direction.0 * BULLET_SPEED,
+                self.ship_velocity.1 + direction.1 * BULLET_SPEED,
+            ),
+            life: BULLET_LIFE,
+        });
+        audio.play(Sound::Paddle);
+    }
+
+    fn update_ship(&mut self, input: &Input, delta: f32, audio: &mut Audio) {
+        if input.held(Action::Left) {
+            self.ship_angle -= SHIP_TURN_SPEED * delta;
+        } else if input.held(Action::Right) {
+            self.ship_angle += SHIP_TURN_SPEED * delta;
+        }
+        self.ship_angle = self.ship_angle.rem_euclid(TAU);
+
+        self.thrusting = input.held(Action::Up);
+        if self.thrusting {
+            let direction = heading(self.ship_angle);
+            self.ship_velocity.0 += direction.0 * SHIP_THRUST * delta;
+            self.ship_velocity.1 += direction.1 * SHIP_THRUST * delta;
+            let speed = self.ship_velocity.0.hypot(self.ship_velocity.1);
+            if speed > SHIP_MAX_SPEED {
+                self.ship_velocity.0 *= SHIP_MAX_SPEED / speed;
+                self.ship_velocity.1 *= SHIP_MAX_SPEED / speed;
+            }
+        } else {
+            let drag = SHIP_DRAG.powf(delta);
+            self.ship_velocity.0 *= drag;
+            self.ship_velocity.1 *= drag;
+        }
+        self.ship_pos = wrap((
+            self.ship_pos.0 + self.ship_velocity.0 * delta,
+            self.ship_pos.1 + self.ship_velocity.1 * delta,
+        ));
+
+        if input.pressed(Action::Confirm) {
+            self.fire(audio);
+        }
+        if input.pressed(Action::Down) {
+            self.state = Hyperspace;
+            self.thrusting = false;
+            self.hyperspace.reset();
+            audio.play(Sound::Shrink);
+        }
+    }
+
+    fn update_ufo(&mut self, timing: &Timing, delta: f32) {
+        let Some(ufo) = &mut self.ufo else {
+            if self.state != Dead && self.next_ufo.update(timing) {
+                let small = self.score >= SMALL_UFO_SCORE || self.rng.f32() < SMALL_UFO_CHANCE;
+                let from_left = self.rng.bool();
+                let speed = if small { UFO_SPEED * 1.3 } else { UFO_SPEED };
+                self.ufo = Some(Ufo {
+                    pos: (if from_left { 0.0 } else { WIDTH }, self.rng.f32() * HEIGHT),
+                    velocity: (if from_left { speed } else { -speed }, 0.0),
+                    small,
+                    next_turn: Timer::new(UFO_TURN_RATE),
+                    next_fire: Timer::new_with_delay(UFO_FIRE_RATE, UFO_FIRE_RATE / 2.0),
+                });
+            }
+            return;
+        };
+
+        if ufo.next_turn.update(timing) {
+            let speed = ufo.velocity.0.abs();
+            ufo.velocity.1 = [-speed, 0.0, speed][self.rng.usize(0..3)];
+        }
+        ufo.pos.0 += ufo.velocity.0 * delta;
+        ufo.pos.1 = (ufo.pos.1 + ufo.velocity.1 * delta).rem_euclid(HEIGHT);
+
+        if ufo.next_fire.update(timing) {
+            let angle = if ufo.small && self.state == Playing {
+                let (dx, dy) = offset(ufo.pos, self.ship_pos);
+                dx.atan2(-dy) + (self.rng.f32() * 2.0 - 1.0) * SMALL_UFO_AIM_ERROR
+            } else {
+                self.rng.f32() * TAU
+            };
+            let direction = heading(angle);
+            self.ufo_bullets.push(Bullet {
+                pos: ufo.pos,
+                velocity: (
+                    direction.0 * UFO_BULLET_SPEED,
+                    direction.1 * UFO_BULLET_SPEED,
+                ),
+                life: UFO_BULLET_LIFE,
+            });
+        }
+
+        //UFOs don't wrap horizontally, they leave
+        if ufo.pos.0 < -ufo.radius() || ufo.pos.0 > WIDTH + ufo.radius() {
+            self.ufo = None;
+            self.next_ufo.reset();
+        }
+    }
+
+    fn destroy_ufo(&mut self, audio: &mut Audio) {
+        if let Some(ufo) = self.ufo.take() {
+            self.explode(ufo.pos, 10);
+            audio.play(Sound::Bonus);
+            self.next_ufo.reset();
+        }
+    }
+
+    /// Moves bullets and particles, and removes them once they've expired
+    fn update_projectiles(&mut self, delta: f32) {
+        for bullet in self.bullets.iter_mut().chain(self.ufo_bullets.iter_mut()) {
+            bullet.pos = wrap((
+                bullet.pos.0 + bullet.velocity.0 * delta,
+                bullet.pos.1 + bullet.velocity.1 * delta,
+            ));
+            bullet.life -= delta as f64;
+        }
+        self.bullets.retain(|bullet| bullet.life > 0.0);
+        self.ufo_bullets.retain(|bullet| bullet.life > 0.0);
+
+        for particle in &mut self.particles {
+            particle.pos.0 += particle.velocity.0 * delta;
+            particle.pos.1 += particle.velocity.1 * delta;
+            particle.life -= delta as f64;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    fn check_collisions(&mut self, audio: &mut Audio) {
+        let mut i = 0;
+        while i < self.bullets.len() {
+            let pos = self.bullets[i].pos;
+            if let Some(idx) = self
+                .asteroids
+                .iter()
+                .position(|asteroid| distance(pos, asteroid.pos) < asteroid.size.radius())
+            {
+                self.bullets.remove(i);
+                self.add_score(self.asteroids[idx].size.score(), audio);
+                self.split(idx, audio);
+            } else if let Some(ufo) = self
+                .ufo
+                .as_ref()
+                .filter(|ufo| distance(pos, ufo.pos) < ufo.radius())
+            {
+                self.bullets.remove(i);
+                self.add_score(ufo.score(), audio);
+                self.destroy_ufo(audio);
+            } else {
+                i += 1;
+            }
+        }
+
+        let mut i = 0;
+        while i < self.ufo_bullets.len() {
+            let pos = self.ufo_bullets[i].pos;
+            if let Some(idx) = self
+                .asteroids
+                .iter()
+                .position(|asteroid| distance(pos, asteroid.pos) < asteroid.size.radius())
+            {
+                self.ufo_bullets.remove(i);
+                self.split(idx, audio);
+            } else if self.state == Playing && distance(pos, self.ship_pos) < SHIP_RADIUS {
+                self.ufo_bullets.remove(i);
+                self.kill_ship(audio);
+            } else {
+                i += 1;
+            }
+        }
+
+        if let Some(ufo_pos) = self.ufo.as_ref().map(|ufo| ufo.pos) {
+            let radius = self
+                .ufo
+                .as_ref()
+                .map(|ufo| ufo.radius())
+                .unwrap_or_default();
+            if let Some(idx) = self.asteroids.iter().position(|asteroid| {
+                distance(ufo_pos, asteroid.pos) < asteroid.size.radius() + radius
+            }) {
+                self.split(idx, audio);
+                self.destroy_ufo(audio);
+            }
+        }
+
+        if self.state == Playing {
+            if let Some(idx) = self.asteroids.iter().position(|asteroid| {
+                distance(self.ship_pos, asteroid.pos) < asteroid.size.radius() + SHIP_RADIUS
+            }) {
+                self.add_score(self.asteroids[idx].size.score(), audio);
+                self.split(idx, audio);
+                self.kill_ship(audio);
+            } else if let Some(ufo) = self
+                .ufo
+                .as_ref()
+                .filter(|ufo| distance(self.ship_pos, ufo.pos) < ufo.radius() + SHIP_RADIUS)
+            {
+                self.add_score(ufo.score(), audio);
+                self.destroy_ufo(audio);
+                self.kill_ship(audio);
+            }
+        }
+    }
+
+    fn center_is_clear(&self) -> bool {
+        self.asteroids
+            .iter()
+            .all(|asteroid| distance(CENTER, asteroid.pos) > asteroid.size.radius() + SAFE_DISTANCE)
+            && self.ufo.is_none()
+    }
+
+    fn render_ship(&self, graphics: &mut Graphics, pos: (f32, f32), angle: f32, color: Color) {
+        let points: Vec<(f32, f32)> = SHIP_SHAPE
+            .iter()
+            .map(|point| rotate(*point, angle))
+            .collect();
+        draw_shape(graphics, pos, &points, SHIP_RADIUS + 1.0, color);
+    }
+
+    fn render_hud(&self, graphics: &mut Graphics) {
+        graphics.draw_text(
+            &format!("SCORE: {: >6}", self.score),
+            TextPos::Px(3, 3),
+            (CLR_2, Standard8x10, LeftTop),
+        );
+        graphics.draw_text(
+            &format!("WAVE {}", self.wave),
+            TextPos::Px(SCREEN_WIDTH as isize - 3, 3),
+            (CLR_2, Standard4x5, RightTop),
+        );
+        for i in 0..self.lives.saturating_sub(1) {
+            let pos = (WIDTH - 7.0 - i as f32 * 9.0, 16.0);
+            self.render_ship(graphics, pos, 0.0, CLR_2);
+        }
+    }
+}
+
+impl Game for Asteroids {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        for particle in &self.particles {
+            graphics.set_pixel(
+                particle.pos.0.round() as isize,
+                particle.pos.1.round() as isize,
+                CLR_1,
+            );
+        }
+
+        for asteroid in &self.asteroids {
+            let points: Vec<(f32, f32)> = asteroid
+                .shape
+                .iter()
+                .map(|point| rotate(*point, asteroid.angle))
+                .collect();
+            draw_shape(
+                graphics,
+                asteroid.pos,
+                &points,
+                asteroid.size.radius(),
+                CLR_2,
+            );
+        }
+
+        if let Some(ufo) = &self.ufo {
+            let scale = |points: &[(f32, f32)]| -> Vec<(f32, f32)> {
+                points
+                    .iter()
+                    .map(|(x, y)| (x * ufo.scale(), y * ufo.scale()))
+                    .collect()
+            };
+            draw_shape(graphics, ufo.pos, &scale(&UFO_SHAPE), ufo.radius(), CLR_3);
+            draw_shape(graphics, ufo.pos, &scale(&UFO_DOME), ufo.radius(), CLR_3);
+        }
+
+        if self.state == Playing {
+            self.render_ship(graphics, self.ship_pos, self.ship_angle, CLR_3);
+            if self.thrusting && self.flicker {
+                let points: Vec<(f32, f32)> = FLAME_SHAPE
+                    .iter()
+                    .map(|point| rotate(*point, self.ship_angle))
+                    .collect();
+                draw_shape(graphics, self.ship_pos, &points, SHIP_RADIUS + 3.0, CLR_2);
+            }
+        }
+
+        for bullet in &self.bullets {
+            graphics.draw_rect(
+                Rect::new_with_size(
+                    (bullet.pos.0.round() as isize, bullet.pos.1.round() as isize),
+                    1,
+                    1,
+                ),
+                fill(CLR_3),
+            );
+        }
+        for bullet in &self.ufo_bullets {
+            graphics.draw_rect(
+                Rect::new_with_size(
+                    (bullet.pos.0.round() as isize, bullet.pos.1.round() as isize),
+                    1,
+                    1,
+                ),
+                fill(CLR_2),
+            );
+        }
+
+        self.render_hud(graphics);
+
+        //shapes aren't clipped to the play area, so clear under the bar
+        graphics.draw_rect(
+            Rect::new((0, PLAY_HEIGHT), (SCREEN_WIDTH, SCREEN_HEIGHT)),
+            fill(CLR_0),
+        );
+        graphics.draw_line(
+            (0, PLAY_HEIGHT as isize),
+            (SCREEN_WIDTH as isize, PLAY_HEIGHT as isize),
+            CLR_1,
+        );
+        self.button_bar.render(graphics, controller);
+
+        if self.state == Dead {
+            draw_game_over(graphics, self.seed);
+        }
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        let delta = timing.fixed_time_step as f32;
+        self.flicker = !self.flicker;
+
+        match self.state {
+            Playing => self.update_ship(input, delta, audio),
+            Hyperspace => {
+                if self.hyperspace.update(timing) {
+                    self.ship_pos = (self.rng.f32() * WIDTH, self.rng.f32() * HEIGHT);
+                    self.ship_velocity = (0.0, 0.0);
+                    self.state = Playing;
+                }
+            }
+            Respawning => {
+                if self.respawn.update(timing) && self.center_is_clear() {
+                    self.ship_pos = CENTER;
+                    self.ship_velocity = (0.0, 0.0);
+                    self.ship_angle = 0.0;
+                    self.state = Playing;
+                }
+            }
+            Dead => {}
+        }
+
+        for asteroid in &mut self.asteroids {
+            asteroid.pos = wrap((
+                asteroid.pos.0 + asteroid.velocity.0 * delta,
+                asteroid.pos.1 + asteroid.velocity.1 * delta,
+            ));
+            asteroid.angle = (asteroid.angle + asteroid.spin * delta).rem_euclid(TAU);
+        }
+        self.update_ufo(timing, delta);
+        self.update_projectiles(delta);
+        self.check_collisions(audio);
+
+        if self.asteroids.is_empty() && self.state != Dead {
+            self.next_wave -= timing.fixed_time_step;
+            if self.next_wave <= 0.0 {
+                self.wave += 1;
+                self.start_wave();
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+    use std::f32::consts::PI;
+
+    /// Game with a single large asteroid that won't hit anything
+    fn asteroids() -> Harness<Asteroids> {
+        let mut harness = Harness::new(Asteroids::new(1));
+        harness.game.asteroids.truncate(1);
+        harness.game.asteroids[0].pos = (10.0, 10.0);
+        harness.game.asteroids[0].velocity = (0.0, 0.0);
+        harness
+    }
+
+    #[test]
+    fn ship_keeps_moving_after_thrust() {
+        let mut harness = asteroids();
+        harness.hold(Action::Up);
+        harness.run(0.5);
+        harness.release(Action::Up);
+        assert!(harness.game.ship_velocity.1 < -20.0);
+        let (pos, velocity) = (harness.game.ship_pos, harness.game.ship_velocity);
+        harness.run(0.2);
+        assert!(harness.game.ship_pos.1 < pos.1 - 2.0);
+        //drag slows it down
+        assert!(harness.game.ship_velocity.1 > velocity.1);
+    }
+
+    #[test]
+    fn ship_turns() {
+        let mut harness = asteroids();
+        harness.hold(Action::Right);
+        harness.run(PI as f64 / 2.0 / SHIP_TURN_SPEED as f64);
+        assert!((harness.game.ship_angle - PI / 2.0).abs() < 0.1);
+        harness.release(Action::Right);
+        harness.hold(Action::Left);
+        harness.run(PI as f64 / SHIP_TURN_SPEED as f64);
+        assert!((harness.game.ship_angle - PI * 1.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn wraps_around_edges() {
+        let mut harness = asteroids();
+        harness.game.ship_pos = (WIDTH - 1.0, 60.0);
+        harness.game.ship_velocity = (50.0, 0.0);
+        harness.run(0.1);
+        assert!(harness.game.ship_pos.0 < 5.0);
+        assert_eq!(offset((WIDTH - 1.0, 1.0), (1.0, HEIGHT - 1.0)), (2.0, -2.0));
+    }
+
+    #[test]
+    fn shooting_splits_asteroids() {
+        let mut harness = asteroids();
+        harness.game.asteroids[0].pos = (CENTER.0, CENTER.1 - 30.0);
+        harness.press_and_release(Action::Confirm);
+        harness.run_until(1.0, |game| game.asteroids.len() == 2);
+        assert!(harness
+            .game
+            .asteroids
+            .iter()
+            .all(|asteroid| asteroid.size == Size::Medium));
+        assert_eq!(harness.game.score, Size::Large.score());
+        assert!(harness.game.bullets.is_empty());
+    }
+
+    #[test]
+    fn small_asteroids_are_destroyed() {
+        let mut harness = asteroids();
+        let mut rng = Rng::with_seed(1);
+        harness.game.asteroids = vec![Asteroid::new(
+            (CENTER.0, CENTER.1 - 30.0),
+            Size::Small,
+            &mut rng,
+        )];
+        harness.game.asteroids[0].velocity = (0.0, 0.0);
+        harness.press_and_release(Action::Confirm);
+        harness.run_until(1.0, |game| game.asteroids.is_empty());
+        harness.run(WAVE_DELAY + 0.1);
+        assert_eq!(harness.game.wave, 2);
+        assert_eq!(harness.game.asteroids.len(), WAVE_START_ASTEROIDS + 1);
+    }
+
+    #[test]
+    fn collision_costs_a_life() {
+        let mut harness = asteroids();
+        harness.game.asteroids[0].pos = (CENTER.0 + 5.0, CENTER.1);
+        harness.tick();
+        assert_eq!(harness.game.state, Respawning);
+        assert_eq!(harness.game.lives, STARTING_LIVES - 1);
+        //the asteroid pieces are still too close to respawn
+        for asteroid in &mut harness.game.asteroids {
+            asteroid.velocity = (0.0, 0.0);
+        }
+        harness.run(RESPAWN_DELAY + 0.1);
+        assert_eq!(harness.game.state, Respawning);
+        harness.game.asteroids.clear();
+        harness.tick();
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.ship_pos, CENTER);
+    }
+
+    #[test]
+    fn extra_lives() {
+        let mut harness = asteroids();
+        let mut audio = Audio::null();
+        harness.game.add_score(EXTRA_LIFE_SCORE - 1, &mut audio);
+        assert_eq!(harness.game.lives, STARTING_LIVES);
+        harness.game.add_score(1, &mut audio);
+        assert_eq!(harness.game.lives, STARTING_LIVES + 1);
+        harness.game.add_score(EXTRA_LIFE_SCORE, &mut audio);
+        assert_eq!(harness.game.lives, STARTING_LIVES + 2);
+    }
+
+    #[test]
+    fn hyperspace_moves_ship() {
+        let mut harness = asteroids();
+        harness.press_and_release(Action::Down);
+        assert_eq!(harness.game.state, Hyperspace);
+        harness.run(HYPERSPACE_DURATION + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert_ne!(harness.game.ship_pos, CENTER);
+    }
+
+    #[test]
+    fn shooting_ufo() {
+        let mut harness = asteroids();
+        harness.game.ufo = Some(Ufo {
+            pos: (CENTER.0, CENTER.1 - 30.0),
+            velocity: (0.0, 0.0),
+            small: true,
+            next_turn: Timer::new_once(100.0),
+            next_fire: Timer::new_once(100.0),
+        });
+        harness.press_and_release(Action::Confirm);
+        harness.run_until(1.0, |game| game.ufo.is_none());
+        assert_eq!(harness.game.score, 1000);
+    }
+
+    #[test]
+    fn ufo_appears_and_fires() {
+        let mut harness = asteroids();
+        harness.run_until(UFO_DELAY + 1.0, |game| game.ufo.is_some());
+        harness.run_until(UFO_FIRE_RATE, |game| !game.ufo_bullets.is_empty());
+    }
+
+    #[test]
+    fn start() {
+        let harness = Harness::new(Asteroids::new(1));
+        harness.assert_snapshot("asteroids_start");
+    }
+
+    #[test]
+    fn game_over() {
+        let mut harness = asteroids();
+        harness.game.lives = 1;
+        harness.game.asteroids[0].pos = CENTER;
+        harness.tick();
+        assert_eq!(harness.game.state, Dead);
+        harness.assert_snapshot("asteroids_game_over");
+    }
+}
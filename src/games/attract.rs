@@ -0,0 +1,111 @@
+use crate::audio::Audio;
+use crate::button_bar::BAR_HEIGHT;
+use crate::input::Input;
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_0, CLR_2, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::Center;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
+use pixels_graphics_lib::prelude::*;
+
+/// Seconds the demo plays for before going back to the menu
+const DEMO_LENGTH: f64 = 60.0;
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+
+/// Plays a game's [demo][crate::games::GameDescriptor::demo], started by the menu after it's been
+/// left alone
+///
+/// The demo is silent and gets no input, any input goes back to the menu once it's released
+/// (so it isn't also used by the menu)
+pub struct AttractMode {
+    game: Box<dyn Game>,
+    time_left: f64,
+    /// Set when there's been input, closes once it's released
+    leaving: bool,
+    audio: Audio,
+    result: GameUpdateResult,
+}
+
+impl AttractMode {
+    pub fn new(game: Box<dyn Game>) -> Box<Self> {
+        Box::new(Self {
+            game,
+            time_left: DEMO_LENGTH,
+            leaving: false,
+            audio: Audio::null(),
+            result: Nothing,
+        })
+    }
+}
+
+impl Game for AttractMode {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        self.game.render(graphics, controller);
+
+        graphics.draw_rect(
+            Rect::new((0, PLAY_HEIGHT), (SCREEN_WIDTH, SCREEN_HEIGHT)),
+            fill(CLR_0),
+        );
+        graphics.draw_text(
+            "DEMO - PRESS ANY BUTTON",
+            Px(
+                SCREEN_WIDTH as isize / 2,
+                (PLAY_HEIGHT + BAR_HEIGHT / 2) as isize,
+            ),
+            (CLR_2, Standard4x5, Center),
+        );
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {
+        self.leaving = true;
+    }
+
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if input.any_held() {
+            self.leaving = true;
+        } else if self.leaving {
+            self.result = Pop;
+        }
+
+        self.time_left -= timing.fixed_time_step;
+        if self.time_left <= 0.0 {
+            self.result = Pop;
+        }
+
+        if !self.leaving {
+            self.game
+                .update(timing, &Input::from_masks([0; 2], [0; 2]), &mut self.audio);
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::snake::Snake;
+    use crate::harness::Harness;
+    use crate::input::Action;
+
+    #[test]
+    fn closes_once_input_is_released() {
+        let mut harness = Harness::new(AttractMode::new(Snake::demo(1)));
+        assert_eq!(harness.run(1.0), Nothing);
+        harness.hold(Action::Confirm);
+        assert_eq!(harness.run(0.5), Nothing);
+        harness.release(Action::Confirm);
+        assert_eq!(harness.tick(), Pop);
+    }
+
+    #[test]
+    fn closes_after_demo_length() {
+        let mut harness = Harness::new(AttractMode::new(Snake::demo(1)));
+        assert_eq!(harness.run(DEMO_LENGTH - 1.0), Nothing);
+        assert_eq!(harness.run(1.1), Pop);
+    }
+}
@@ -0,0 +1,213 @@
+//! Ball movement and collisions shared by [Pong][crate::games::pong] and
+//! [Breakout][crate::games::breakout]
+
+use crate::CLR_3;
+use pixels_graphics_lib::prelude::*;
+
+//angle the ball leaves a paddle at when hitting the very end of it, in radians
+const MAX_BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+//fraction of the paddle's speed given to the ball
+const PADDLE_SPIN: f32 = 0.25;
+
+/// Way the ball is sent by a paddle
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Towards {
+    Left,
+    Right,
+    Up,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ball {
+    /// Center
+    pub pos: (f32, f32),
+    /// Pixels per second
+    pub velocity: (f32, f32),
+    pub speed: f32,
+    pub radius: f32,
+}
+
+impl Ball {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            pos: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            speed: 0.0,
+            radius,
+        }
+    }
+}
+
+impl Ball {
+    pub fn center(&self) -> Coord {
+        coord!(self.pos.0.round(), self.pos.1.round())
+    }
+
+    /// Start moving at `speed` in the direction of `angle`, in radians clockwise from right
+    pub fn launch(&mut self, speed: f32, angle: f32) {
+        self.speed = speed;
+        self.velocity = (angle.cos() * speed, angle.sin() * speed);
+    }
+
+    /// Multiply the speed by `factor`, up to `max`, the direction is kept
+    pub fn speed_up(&mut self, factor: f32, max: f32) {
+        let speed = (self.speed * factor).min(max);
+        if self.speed > 0.0 {
+            let scale = speed / self.speed;
+            self.velocity = (self.velocity.0 * scale, self.velocity.1 * scale);
+        }
+        self.speed = speed;
+    }
+
+    /// Number of updates needed to move the ball `delta` seconds in steps of at most a pixel,
+    /// so it can't pass through anything, and the seconds per step
+    pub fn steps(&self, delta: f32) -> (usize, f32) {
+        let steps = (self.speed * delta).ceil().max(1.0) as usize;
+        (steps, delta / steps as f32)
+    }
+
+    pub fn advance(&mut self, step: f32) {
+        self.pos.0 += self.velocity.0 * step;
+        self.pos.1 += self.velocity.1 * step;
+    }
+
+    /// True if the ball overlaps `rect`
+    pub fn touches(&self, rect: &Rect) -> bool {
+        let nearest_x = self
+            .pos
+            .0
+            .clamp(rect.left() as f32, rect.right() as f32 + 1.0);
+        let nearest_y = self
+            .pos
+            .1
+            .clamp(rect.top() as f32, rect.bottom() as f32 + 1.0);
+        let dx = self.pos.0 - nearest_x;
+        let dy = self.pos.1 - nearest_y;
+        dx * dx + dy * dy < self.radius * self.radius
+    }
+
+    /// Send the ball `towards` from `paddle`, the angle depends on where it hit the paddle
+    /// and how the paddle was moving (`paddle_velocity` is along the paddle in pixels per second)
+    pub fn bounce(&mut self, paddle: &Rect, paddle_velocity: f32, towards: Towards) {
+        let (along, start, length) = match towards {
+            Towards::Left | Towards::Right => (self.pos.1, paddle.top(), paddle.height()),
+            Towards::Up => (self.pos.0, paddle.left(), paddle.width()),
+        };
+        //rects include their right and bottom edges
+        let half = (length + 1) as f32 / 2.0;
+        let offset = ((along - (start as f32 + half)) / (half + self.radius)).clamp(-1.0, 1.0);
+        let max_side = self.speed * MAX_BOUNCE_ANGLE.sin();
+        let side = (self.speed * (offset * MAX_BOUNCE_ANGLE).sin() + paddle_velocity * PADDLE_SPIN)
+            .clamp(-max_side, max_side);
+        let forward = (self.speed * self.speed - side * side).sqrt();
+        self.velocity = match towards {
+            Towards::Right => {
+                self.pos.0 = paddle.right() as f32 + 1.0 + self.radius;
+                (forward, side)
+            }
+            Towards::Left => {
+                self.pos.0 = paddle.left() as f32 - self.radius;
+                (-forward, side)
+            }
+            Towards::Up => {
+                self.pos.1 = paddle.top() as f32 - self.radius;
+                (side, -forward)
+            }
+        };
+    }
+
+    /// Bounce off `rect` like a wall, from whichever side or corner of it the ball hit
+    pub fn reflect(&mut self, rect: &Rect) {
+        let (x, y) = self.pos;
+        let mut reflected = false;
+        if (x < rect.left() as f32 && self.velocity.0 > 0.0)
+            || (x > rect.right() as f32 + 1.0 && self.velocity.0 < 0.0)
+        {
+            self.velocity.0 = -self.velocity.0;
+            reflected = true;
+        }
+        if (y < rect.top() as f32 && self.velocity.1 > 0.0)
+            || (y > rect.bottom() as f32 + 1.0 && self.velocity.1 < 0.0)
+        {
+            self.velocity.1 = -self.velocity.1;
+            reflected = true;
+        }
+        if !reflected {
+            //center is inside the rect, it should have been caught a step earlier
+            self.velocity.1 = -self.velocity.1;
+        }
+    }
+
+    pub fn render(&self, graphics: &mut Graphics) {
+        graphics.draw_circle(
+            Circle::new(self.center(), self.radius as usize),
+            fill(CLR_3),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ball_at(pos: (f32, f32), velocity: (f32, f32)) -> Ball {
+        let mut ball = Ball::new(2.0);
+        ball.pos = pos;
+        ball.velocity = velocity;
+        ball.speed = (velocity.0 * velocity.0 + velocity.1 * velocity.1).sqrt();
+        ball
+    }
+
+    #[test]
+    fn touches_corners_by_radius() {
+        let rect = Rect::new((10, 10), (19, 14));
+        assert!(ball_at((9.0, 12.0), (0.0, 0.0)).touches(&rect));
+        assert!(ball_at((21.0, 16.0), (0.0, 0.0)).touches(&rect));
+        assert!(!ball_at((22.0, 17.0), (0.0, 0.0)).touches(&rect));
+        assert!(!ball_at((15.0, 7.0), (0.0, 0.0)).touches(&rect));
+    }
+
+    #[test]
+    fn bounce_angle_depends_on_hit_position() {
+        let paddle = Rect::new((40, 100), (63, 102));
+        let middle = paddle.left() as f32 + (paddle.width() + 1) as f32 / 2.0;
+        let mut center = ball_at((middle, 99.0), (0.0, 50.0));
+        center.bounce(&paddle, 0.0, Towards::Up);
+        assert!(center.velocity.0.abs() < 0.01);
+        assert!((center.velocity.1 + 50.0).abs() < 0.01);
+        assert_eq!(center.pos.1, 98.0);
+
+        let mut edge = ball_at((63.0, 99.0), (0.0, 50.0));
+        edge.bounce(&paddle, 0.0, Towards::Up);
+        assert!(edge.velocity.0 > 30.0);
+        assert!(edge.velocity.1 < 0.0);
+
+        let mut spun = ball_at((middle, 99.0), (0.0, 50.0));
+        spun.bounce(&paddle, -60.0, Towards::Up);
+        assert!(spun.velocity.0 < 0.0);
+    }
+
+    #[test]
+    fn reflects_off_sides_and_corners() {
+        let rect = Rect::new((10, 10), (19, 14));
+        let mut below = ball_at((15.0, 16.0), (10.0, -10.0));
+        below.reflect(&rect);
+        assert_eq!(below.velocity, (10.0, 10.0));
+
+        let mut left = ball_at((9.0, 12.0), (10.0, -10.0));
+        left.reflect(&rect);
+        assert_eq!(left.velocity, (-10.0, -10.0));
+
+        let mut corner = ball_at((9.0, 9.0), (10.0, 10.0));
+        corner.reflect(&rect);
+        assert_eq!(corner.velocity, (-10.0, -10.0));
+    }
+
+    #[test]
+    fn speed_up_keeps_direction() {
+        let mut ball = ball_at((0.0, 0.0), (30.0, 40.0));
+        ball.speed_up(2.0, 80.0);
+        assert_eq!(ball.speed, 80.0);
+        assert_eq!(ball.velocity, (48.0, 64.0));
+    }
+}
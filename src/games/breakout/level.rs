@@ -0,0 +1,133 @@
+use crate::games::breakout::{BRICK_COLS, BRICK_ROWS};
+use pixels_graphics_lib::prelude::*;
+use std::sync::OnceLock;
+
+/// Most hits a brick can take
+pub const MAX_HITS: usize = 3;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Brick {
+    /// Column and row in the brick grid
+    pub pos: Coord,
+    /// Hits left before it breaks, None if it can't be broken
+    pub hits: Option<usize>,
+}
+
+/// Brick layout, see `assets/breakout_levels.txt` for the format
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Level {
+    pub bricks: Vec<Brick>,
+}
+
+pub fn levels() -> &'static [Level] {
+    static LEVELS: OnceLock<Vec<Level>> = OnceLock::new();
+    LEVELS
+        .get_or_init(|| parse_levels(include_str!("../../../assets/breakout_levels.txt")).unwrap())
+}
+
+/// Parse every level in `text`, the error includes the line number
+pub fn parse_levels(text: &str) -> Result<Vec<Level>, String> {
+    let mut levels = vec![];
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    while let Some((num, header)) = lines.next() {
+        //comments and blank lines are only allowed between levels as rows can start with a brick
+        if header.is_empty() || header.starts_with('#') {
+            continue;
+        }
+        if header != "LEVEL" {
+            return Err(format!("Line {num}: expected LEVEL"));
+        }
+        let mut bricks = vec![];
+        for y in 0..BRICK_ROWS {
+            let (num, row) = lines
+                .next()
+                .ok_or(format!("Line {num}: level needs {BRICK_ROWS} rows"))?;
+            if row.chars().count() != BRICK_COLS {
+                return Err(format!("Line {num}: rows must be {BRICK_COLS} bricks"));
+            }
+            for (x, chr) in row.chars().enumerate() {
+                let hits = match chr {
+                    '.' => continue,
+                    '#' => None,
+                    '1'..='9' => {
+                        let hits = chr as usize - '0' as usize;
+                        if hits > MAX_HITS {
+                            return Err(format!(
+                                "Line {num}: bricks can take at most {MAX_HITS} hits"
+                            ));
+                        }
+                        Some(hits)
+                    }
+                    _ => return Err(format!("Line {num}: unknown brick '{chr}'")),
+                };
+                bricks.push(Brick {
+                    pos: Coord::from((x, y)),
+                    hits,
+                });
+            }
+        }
+        if bricks.iter().all(|brick| brick.hits.is_none()) {
+            return Err(format!("Line {num}: level has no breakable bricks"));
+        }
+        levels.push(Level { bricks });
+    }
+    if levels.is_empty() {
+        return Err(String::from("No levels"));
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn map(rows: &[&str]) -> String {
+        let mut text = String::from("LEVEL\n");
+        for y in 0..BRICK_ROWS {
+            text.push_str(rows.get(y).unwrap_or(&".........."));
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn built_in_levels_are_valid() {
+        assert!(levels().len() > 1);
+    }
+
+    #[test]
+    fn parses_bricks() {
+        let levels = parse_levels(&map(&["1.........", "..........", ".......3#."])).unwrap();
+        assert_eq!(
+            levels[0].bricks,
+            vec![
+                Brick {
+                    pos: Coord::new(0, 0),
+                    hits: Some(1)
+                },
+                Brick {
+                    pos: Coord::new(7, 2),
+                    hits: Some(3)
+                },
+                Brick {
+                    pos: Coord::new(8, 2),
+                    hits: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        assert!(parse_levels("").is_err());
+        assert!(parse_levels(&map(&[])).is_err());
+        assert!(parse_levels(&map(&["#########"])).is_err());
+        assert!(parse_levels(&map(&["##########"])).is_err());
+        assert!(parse_levels(&map(&["1....4...."])).is_err());
+        assert!(parse_levels(&map(&["1....x...."])).is_err());
+        assert!(parse_levels(&map(&["1........."]).replace("LEVEL", "LEVEL 2")).is_err());
+    }
+}
@@ -0,0 +1,696 @@
+mod level;
+
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::game_over::draw_game_over;
+use crate::games::ball::{Ball, Towards};
+use crate::games::breakout::level::{levels, Brick, MAX_HITS};
+use crate::games::breakout::State::*;
+use crate::high_scores::submit_score;
+use crate::input::{Action, Input};
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::{
+    Center, CenterTop, LeftTop, RightTop,
+};
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
+use pixels_graphics_lib::prelude::*;
+
+pub const ID: &str = "breakout";
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+
+pub const BRICK_COLS: usize = 10;
+pub const BRICK_ROWS: usize = 8;
+const BRICK_SIZE: (usize, usize) = (14, 5);
+const BRICK_SPACING: (usize, usize) = (15, 7);
+const BRICKS_TOP: isize = 24;
+//inside edges of the walls, the bottom is open
+const FIELD_LEFT: isize = 5;
+const FIELD_RIGHT: isize = FIELD_LEFT + (BRICK_COLS * BRICK_SPACING.0) as isize;
+const FIELD_TOP: isize = 12;
+
+const PADDLE_Y: isize = PLAY_HEIGHT as isize - 8;
+const PADDLE_HEIGHT: usize = 3;
+const PADDLE_WIDTH: usize = 24;
+const WIDE_PADDLE_WIDTH: usize = 36;
+//pixels moved per update
+const PADDLE_MOVE_DISTANCE: isize = 2;
+
+const BALL_RADIUS: f32 = 2.0;
+//pixels per second
+const BALL_START_SPEED: f32 = 80.0;
+const BALL_MAX_SPEED: f32 = 170.0;
+//multiplier applied on every paddle hit
+const BALL_SPEED_UP: f32 = 1.02;
+//extra starting speed for each level, including repeats
+const LEVEL_SPEED_UP: f32 = 0.05;
+const MAX_SERVE_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+const STARTING_LIVES: usize = 3;
+const BRICK_SCORE: usize = 10;
+const LEVEL_CLEAR_DELAY: f64 = 2.0;
+
+const POWER_UP_CHANCE: f32 = 0.12;
+const POWER_UP_SIZE: (usize, usize) = (11, 7);
+const POWER_UP_FALL_SPEED: f32 = 35.0;
+const POWER_UP_SCORE: usize = 50;
+const WIDE_DURATION: f64 = 15.0;
+//fraction of the starting speed balls are slowed to
+const SLOW_SPEED: f32 = 0.7;
+const MULTI_BALL_SPREAD: f32 = 0.4;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Ball is on the paddle waiting to be launched
+    Serving,
+    Playing,
+    /// Next level starts after [LEVEL_CLEAR_DELAY]
+    LevelClear,
+    Dead,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum PowerUpKind {
+    /// Paddle is wider for [WIDE_DURATION]
+    Wide,
+    /// Two more balls split off from the first
+    Multi,
+    /// Every ball goes back to [SLOW_SPEED]
+    Slow,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 3] = [PowerUpKind::Wide, PowerUpKind::Multi, PowerUpKind::Slow];
+
+    fn letter(&self) -> &'static str {
+        match self {
+            PowerUpKind::Wide => "W",
+            PowerUpKind::Multi => "M",
+            PowerUpKind::Slow => "S",
+        }
+    }
+}
+
+/// Dropped by a broken brick, caught with the paddle
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PowerUp {
+    kind: PowerUpKind,
+    /// Top left
+    pos: (f32, f32),
+}
+
+impl PowerUp {
+    fn rect(&self) -> Rect {
+        Rect::new_with_size(
+            (self.pos.0.round() as isize, self.pos.1.round() as isize),
+            POWER_UP_SIZE.0 - 1,
+            POWER_UP_SIZE.1 - 1,
+        )
+    }
+}
+
+fn brick_rect(brick: &Brick) -> Rect {
+    Rect::new_with_size(
+        (
+            FIELD_LEFT + brick.pos.x * BRICK_SPACING.0 as isize,
+            BRICKS_TOP + brick.pos.y * BRICK_SPACING.1 as isize,
+        ),
+        BRICK_SIZE.0 - 1,
+        BRICK_SIZE.1 - 1,
+    )
+}
+
+pub struct Breakout {
+    bricks: Vec<Brick>,
+    balls: Vec<Ball>,
+    power_ups: Vec<PowerUp>,
+    paddle_x: isize,
+    paddle_width: usize,
+    /// Pixels per second, positive is right
+    paddle_velocity: f32,
+    wide_time: f64,
+    /// Index into [levels], keeps counting after the last level
+    level: usize,
+    next_level: Timer,
+    lives: usize,
+    score: usize,
+    state: State,
+    result: GameUpdateResult,
+    button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
+}
+
+impl Breakout {
+    pub fn new(seed: u64) -> Box<Self> {
+        let mut breakout = Box::new(Self {
+            bricks: vec![],
+            balls: vec![],
+            power_ups: vec![],
+            paddle_x: 0,
+            paddle_width: PADDLE_WIDTH,
+            paddle_velocity: 0.0,
+            wide_time: 0.0,
+            level: 0,
+            next_level: Timer::new_once(LEVEL_CLEAR_DELAY),
+            lives: STARTING_LIVES,
+            score: 0,
+            state: Serving,
+            result: Nothing,
+            button_bar: ButtonBar::new(
+                coord!(0, PLAY_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("PAUSE", ButtonDef::Back),
+                    ("MOVE", ButtonDef::Horz),
+                    ("LAUNCH", ButtonDef::Confirm),
+                ],
+            ),
+            seed,
+            rng: Rng::with_seed(seed),
+        });
+        breakout.start_level(0);
+        breakout
+    }
+}
+
+impl Breakout {
+    fn start_level(&mut self, level: usize) {
+        self.level = level;
+        let levels = levels();
+        self.bricks = levels[level % levels.len()].bricks.clone();
+        self.reset_serve();
+    }
+
+    /// Single ball on a centered normal paddle, power ups are lost
+    fn reset_serve(&mut self) {
+        self.state = Serving;
+        self.power_ups.clear();
+        self.paddle_width = PADDLE_WIDTH;
+        self.wide_time = 0.0;
+        self.paddle_x = (FIELD_LEFT + FIELD_RIGHT - PADDLE_WIDTH as isize) / 2;
+        self.balls = vec![Ball::new(BALL_RADIUS)];
+        self.place_ball_on_paddle();
+    }
+
+    fn place_ball_on_paddle(&mut self) {
+        self.balls[0].pos = (
+            self.paddle_x as f32 + self.paddle_width as f32 / 2.0,
+            PADDLE_Y as f32 - BALL_RADIUS,
+        );
+    }
+
+    fn start_speed(&self) -> f32 {
+        (BALL_START_SPEED * (1.0 + self.level as f32 * LEVEL_SPEED_UP)).min(BALL_MAX_SPEED)
+    }
+
+    fn paddle_rect(&self) -> Rect {
+        Rect::new_with_size(
+            (self.paddle_x, PADDLE_Y),
+            self.paddle_width - 1,
+            PADDLE_HEIGHT - 1,
+        )
+    }
+
+    fn move_paddle(&mut self, timing: &Timing, input: &Input) {
+        let last_x = self.paddle_x;
+        if input.held(Action::Left) {
+            self.paddle_x = (self.paddle_x - PADDLE_MOVE_DISTANCE).max(FIELD_LEFT);
+        } else if input.held(Action::Right) {
+            self.paddle_x = (self.paddle_x + PADDLE_MOVE_DISTANCE)
+                .min(FIELD_RIGHT - self.paddle_width as isize);
+        }
+        self.paddle_velocity = (self.paddle_x - last_x) as f32 / timing.fixed_time_step as f32;
+    }
+
+    fn set_paddle_width(&mut self, width: usize) {
+        let center = self.paddle_x + self.paddle_width as isize / 2;
+        self.paddle_width = width;
+        self.paddle_x =
+            (center - width as isize / 2).clamp(FIELD_LEFT, FIELD_RIGHT - width as isize);
+    }
+
+    fn launch(&mut self) {
+        let angle = -std::f32::consts::FRAC_PI_2 + (self.rng.f32() * 2.0 - 1.0) * MAX_SERVE_ANGLE;
+        let speed = self.start_speed();
+        self.balls[0].launch(speed, angle);
+        self.state = Playing;
+    }
+
+    /// Move ball `idx` in steps of at most a pixel so it can't pass through bricks,
+    /// returns false if it went off the bottom
+    fn move_ball(&mut self, idx: usize, timing: &Timing, audio: &mut Audio) -> bool {
+        let mut ball = self.balls[idx].clone();
+        let paddle = self.paddle_rect();
+        let (steps, step) = ball.steps(timing.fixed_time_step as f32);
+        for _ in 0..steps {
+            ball.advance(step);
+
+            if ball.pos.0 - ball.radius < FIELD_LEFT as f32 {
+                ball.pos.0 = FIELD_LEFT as f32 + ball.radius;
+                ball.velocity.0 = ball.velocity.0.abs();
+                audio.play(Sound::Wall);
+            } else if ball.pos.0 + ball.radius > FIELD_RIGHT as f32 {
+                ball.pos.0 = FIELD_RIGHT as f32 - ball.radius;
+                ball.velocity.0 = -ball.velocity.0.abs();
+                audio.play(Sound::Wall);
+            }
+            if ball.pos.1 - ball.radius < FIELD_TOP as f32 {
+                ball.pos.1 = FIELD_TOP as f32 + ball.radius;
+                ball.velocity.1 = ball.velocity.1.abs();
+                audio.play(Sound::Wall);
+            }
+
+            //only the top of the paddle returns the ball, once past it's lost
+            if ball.velocity.1 > 0.0 && ball.pos.1 < paddle.top() as f32 && ball.touches(&paddle) {
+                ball.speed_up(BALL_SPEED_UP, BALL_MAX_SPEED);
+                ball.bounce(&paddle, self.paddle_velocity, Towards::Up);
+                audio.play(Sound::Paddle);
+            }
+
+            if let Some(i) = self
+                .bricks
+                .iter()
+                .position(|brick| ball.touches(&brick_rect(brick)))
+            {
+                ball.reflect(&brick_rect(&self.bricks[i]));
+                self.hit_brick(i, audio);
+            }
+
+            if ball.pos.1 - ball.radius > PLAY_HEIGHT as f32 {
+                return false;
+            }
+        }
+        self.balls[idx] = ball;
+        true
+    }
+
+    fn hit_brick(&mut self, idx: usize, audio: &mut Audio) {
+        let brick = &mut self.bricks[idx];
+        let Some(hits) = &mut brick.hits else {
+            audio.play(Sound::Wall);
+            return;
+        };
+        *hits -= 1;
+        self.score += BRICK_SCORE;
+        if *hits > 0 {
+            audio.play(Sound::Ball);
+            return;
+        }
+        audio.play(Sound::Apple);
+        let rect = brick_rect(brick);
+        self.bricks.remove(idx);
+        if self.rng.f32() < POWER_UP_CHANCE {
+            let center = rect.center();
+            self.power_ups.push(PowerUp {
+                kind: PowerUpKind::ALL[self.rng.usize(0..PowerUpKind::ALL.len())],
+                pos: (
+                    (center.x - POWER_UP_SIZE.0 as isize / 2) as f32,
+                    rect.top() as f32,
+                ),
+            });
+        }
+    }
+
+    fn update_power_ups(&mut self, timing: &Timing, audio: &mut Audio) {
+        let paddle = self.paddle_rect();
+        let mut caught = vec![];
+        self.power_ups.retain_mut(|power_up| {
+            power_up.pos.1 += POWER_UP_FALL_SPEED * timing.fixed_time_step as f32;
+            if power_up.rect().intersects_rect(&paddle) {
+                caught.push(power_up.kind);
+                false
+            } else {
+                power_up.pos.1 < PLAY_HEIGHT as f32
+            }
+        });
+        for kind in caught {
+            self.apply_power_up(kind, audio);
+        }
+
+        if self.wide_time > 0.0 {
+            self.wide_time -= timing.fixed_time_step;
+            if self.wide_time <= 0.0 {
+                self.set_paddle_width(PADDLE_WIDTH);
+            }
+        }
+    }
+
+    fn apply_power_up(&mut self, kind: PowerUpKind, audio: &mut Audio) {
+        self.score += POWER_UP_SCORE;
+        match kind {
+            PowerUpKind::Wide => {
+                self.set_paddle_width(WIDE_PADDLE_WIDTH);
+                self.wide_time = WIDE_DURATION;
+                audio.play(Sound::Bonus);
+            }
+            PowerUpKind::Multi => {
+                if let Some(ball) = self.balls.first().cloned() {
+                    let angle = ball.velocity.1.atan2(ball.velocity.0);
+                    for spread in [-MULTI_BALL_SPREAD, MULTI_BALL_SPREAD] {
+                        let mut split = ball.clone();
+                        split.launch(ball.speed, angle + spread);
+                        self.balls.push(split);
+                    }
+                }
+                audio.play(Sound::Bonus);
+            }
+            PowerUpKind::Slow => {
+                let slow = BALL_START_SPEED * SLOW_SPEED;
+                for ball in &mut self.balls {
+                    if ball.speed > slow {
+                        ball.speed_up(slow / ball.speed, slow);
+                    }
+                }
+                audio.play(Sound::Slow);
+            }
+        }
+    }
+
+    fn lose_life(&mut self, audio: &mut Audio) {
+        self.lives = self.lives.saturating_sub(1);
+        audio.play(Sound::Death);
+        if self.lives == 0 {
+            self.state = Dead;
+            if self.result == Nothing {
+                self.result = submit_score(ID, self.score);
+            }
+        } else {
+            self.reset_serve();
+        }
+    }
+
+    fn render_hud(&self, graphics: &mut Graphics) {
+        graphics.draw_text(
+            &format!("SCORE: {: >6}", self.score),
+            TextPos::Px(FIELD_LEFT, 3),
+            (CLR_2, Standard4x5, LeftTop),
+        );
+        graphics.draw_text(
+            &format!("LEVEL {}", self.level + 1),
+            TextPos::Px(SCREEN_WIDTH as isize / 2, 3),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+        graphics.draw_text(
+            &format!("LIVES {}", self.lives),
+            TextPos::Px(FIELD_RIGHT, 3),
+            (CLR_2, Standard4x5, RightTop),
+        );
+    }
+}
+
+impl Game for Breakout {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        self.render_hud(graphics);
+
+        graphics.draw_line(
+            (FIELD_LEFT - 1, FIELD_TOP - 1),
+            (FIELD_RIGHT, FIELD_TOP - 1),
+            CLR_1,
+        );
+        graphics.draw_line(
+            (FIELD_LEFT - 1, FIELD_TOP - 1),
+            (FIELD_LEFT - 1, PLAY_HEIGHT as isize - 1),
+            CLR_1,
+        );
+        graphics.draw_line(
+            (FIELD_RIGHT, FIELD_TOP - 1),
+            (FIELD_RIGHT, PLAY_HEIGHT as isize - 1),
+            CLR_1,
+        );
+
+        for brick in &self.bricks {
+            let rect = brick_rect(brick);
+            match brick.hits {
+                None => {
+                    graphics.draw_rect(rect.clone(), fill(CLR_1));
+                    graphics.draw_rect(rect, stroke(CLR_2));
+                }
+                Some(1) => graphics.draw_rect(rect, fill(CLR_2)),
+                Some(hits) => {
+                    graphics.draw_rect(rect.clone(), fill(CLR_3));
+                    //notches for each extra hit needed
+                    for i in 1..hits.min(MAX_HITS) {
+                        let x = rect.left() + i as isize * 4;
+                        graphics.draw_line((x, rect.top() + 1), (x, rect.bottom() - 1), CLR_0);
+                    }
+                }
+            }
+        }
+
+        for power_up in &self.power_ups {
+            let rect = power_up.rect();
+            graphics.draw_rect(rect.clone(), stroke(CLR_3));
+            graphics.draw_text(
+                power_up.kind.letter(),
+                TextPos::px(rect.top_left() + (3, 1)),
+                (CLR_3, Standard4x5, LeftTop),
+            );
+        }
+
+        if self.state != Dead {
+            graphics.draw_rect(self.paddle_rect(), fill(CLR_3));
+            if self.state != LevelClear {
+                for ball in &self.balls {
+                    ball.render(graphics);
+                }
+            }
+        }
+
+        if self.state == LevelClear {
+            graphics.draw_text(
+                &format!("LEVEL {} CLEAR", self.level + 1),
+                TextPos::px(coord!(SCREEN_WIDTH / 2, 90)),
+                (CLR_3, Standard4x5, Center),
+            );
+        }
+
+        self.button_bar.render(graphics, controller);
+
+        if self.state == Dead {
+            draw_game_over(graphics, self.seed);
+        }
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        match self.state {
+            Serving => {
+                self.move_paddle(timing, input);
+                self.place_ball_on_paddle();
+                if input.pressed(Action::Confirm) {
+                    self.launch();
+                }
+            }
+            Playing => {
+                self.move_paddle(timing, input);
+                let mut i = 0;
+                while i < self.balls.len() {
+                    if self.move_ball(i, timing, audio) {
+                        i += 1;
+                    } else {
+                        self.balls.remove(i);
+                    }
+                }
+                self.update_power_ups(timing, audio);
+
+                if self.balls.is_empty() {
+                    self.lose_life(audio);
+                } else if self.bricks.iter().all(|brick| brick.hits.is_none()) {
+                    audio.play(Sound::Golden);
+                    self.state = LevelClear;
+                    self.next_level.reset();
+                }
+            }
+            LevelClear => {
+                if self.next_level.update(timing) {
+                    self.start_level(self.level + 1);
+                }
+            }
+            Dead => {}
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn playing() -> Harness<Breakout> {
+        let mut harness = Harness::new(Breakout::new(1));
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        harness
+    }
+
+    /// Put a single ball at `pos` moving with `velocity`
+    fn launch(harness: &mut Harness<Breakout>, pos: (f32, f32), velocity: (f32, f32)) {
+        let ball = &mut harness.game.balls[0];
+        ball.pos = pos;
+        ball.velocity = velocity;
+        ball.speed = (velocity.0 * velocity.0 + velocity.1 * velocity.1).sqrt();
+    }
+
+    #[test]
+    fn ball_follows_paddle_until_launched() {
+        let mut harness = Harness::new(Breakout::new(1));
+        let start = harness.game.balls[0].pos;
+        harness.hold(Action::Left);
+        harness.run(0.2);
+        harness.release(Action::Left);
+        assert_eq!(harness.game.balls[0].pos, (start.0 - 24.0, start.1));
+        let paddle = harness.game.paddle_rect();
+        harness.press(Action::Confirm);
+        harness.run(0.5);
+        assert!(harness.game.balls[0].pos.1 < paddle.top() as f32 - 20.0);
+    }
+
+    #[test]
+    fn paddle_returns_ball() {
+        let mut harness = playing();
+        let paddle = harness.game.paddle_rect();
+        launch(
+            &mut harness,
+            (paddle.center().x as f32, 100.0),
+            (0.0, BALL_START_SPEED),
+        );
+        harness.run_until(2.0, |game| game.balls[0].velocity.1 < 0.0);
+        assert!(harness.game.balls[0].speed > BALL_START_SPEED);
+        assert_eq!(harness.game.lives, STARTING_LIVES);
+    }
+
+    #[test]
+    fn brick_takes_hits_then_breaks() {
+        let mut harness = playing();
+        harness.game.bricks = vec![Brick {
+            pos: Coord::new(4, 6),
+            hits: Some(2),
+        }];
+        harness.game.bricks.push(Brick {
+            pos: Coord::new(0, 0),
+            hits: Some(1),
+        });
+        let rect = brick_rect(&harness.game.bricks[0]);
+        let below = (rect.center().x as f32, rect.bottom() as f32 + 10.0);
+        launch(&mut harness, below, (0.0, -BALL_START_SPEED));
+        harness.run_until(1.0, |game| game.balls[0].velocity.1 > 0.0);
+        assert_eq!(harness.game.bricks[0].hits, Some(1));
+        assert_eq!(harness.game.score, BRICK_SCORE);
+
+        launch(&mut harness, below, (0.0, -BALL_START_SPEED));
+        harness.run_until(1.0, |game| game.balls[0].velocity.1 > 0.0);
+        assert_eq!(harness.game.bricks.len(), 1);
+        assert_eq!(harness.game.score, BRICK_SCORE * 2);
+    }
+
+    #[test]
+    fn clearing_bricks_starts_next_level() {
+        let mut harness = playing();
+        harness.game.bricks = vec![
+            Brick {
+                pos: Coord::new(4, 6),
+                hits: Some(1),
+            },
+            Brick {
+                pos: Coord::new(0, 0),
+                hits: None,
+            },
+        ];
+        let rect = brick_rect(&harness.game.bricks[0]);
+        launch(
+            &mut harness,
+            (rect.center().x as f32, rect.bottom() as f32 + 10.0),
+            (0.0, -BALL_START_SPEED),
+        );
+        harness.run_until(1.0, |game| game.state == LevelClear);
+        harness.run(LEVEL_CLEAR_DELAY + 0.1);
+        assert_eq!(harness.game.state, Serving);
+        assert_eq!(harness.game.level, 1);
+        assert_eq!(harness.game.bricks, levels()[1].bricks);
+    }
+
+    #[test]
+    fn losing_every_ball_costs_a_life() {
+        let mut harness = playing();
+        launch(&mut harness, (20.0, 130.0), (0.0, BALL_START_SPEED));
+        harness.run_until(2.0, |game| game.state == Serving);
+        assert_eq!(harness.game.lives, STARTING_LIVES - 1);
+        assert_eq!(harness.game.balls.len(), 1);
+    }
+
+    #[test]
+    fn power_ups() {
+        let mut harness = playing();
+        let mut audio = Audio::null();
+        launch(&mut harness, (80.0, 60.0), (0.0, -BALL_START_SPEED * 2.0));
+
+        harness.game.apply_power_up(PowerUpKind::Multi, &mut audio);
+        assert_eq!(harness.game.balls.len(), 3);
+        assert!(harness.game.balls.iter().all(|ball| ball.velocity.1 < 0.0));
+
+        harness.game.apply_power_up(PowerUpKind::Slow, &mut audio);
+        assert!(harness
+            .game
+            .balls
+            .iter()
+            .all(|ball| (ball.speed - BALL_START_SPEED * SLOW_SPEED).abs() < 0.01));
+
+        harness.game.apply_power_up(PowerUpKind::Wide, &mut audio);
+        assert_eq!(harness.game.paddle_width, WIDE_PADDLE_WIDTH);
+        //keep a ball bouncing straight up and down until the power up runs out
+        harness.game.balls.truncate(1);
+        harness.game.bricks = vec![Brick {
+            pos: Coord::new(0, 0),
+            hits: Some(1),
+        }];
+        let paddle = harness.game.paddle_rect();
+        launch(
+            &mut harness,
+            (paddle.left() as f32 + WIDE_PADDLE_WIDTH as f32 / 2.0, 40.0),
+            (0.0, BALL_START_SPEED),
+        );
+        harness.run(WIDE_DURATION + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.paddle_width, PADDLE_WIDTH);
+    }
+
+    #[test]
+    fn catches_power_up() {
+        let mut harness = playing();
+        launch(&mut harness, (80.0, 18.0), (BALL_START_SPEED, 0.0));
+        let paddle = harness.game.paddle_rect();
+        harness.game.power_ups.push(PowerUp {
+            kind: PowerUpKind::Wide,
+            pos: (paddle.left() as f32, paddle.top() as f32 - 20.0),
+        });
+        harness.run_until(2.0, |game| game.power_ups.is_empty());
+        assert_eq!(harness.game.paddle_width, WIDE_PADDLE_WIDTH);
+        assert_eq!(harness.game.score, POWER_UP_SCORE);
+    }
+
+    #[test]
+    fn game_over_after_last_life() {
+        let mut harness = playing();
+        harness.game.lives = 1;
+        launch(&mut harness, (20.0, 130.0), (0.0, BALL_START_SPEED));
+        harness.run_until(2.0, |game| game.state == Dead);
+        harness.assert_snapshot("breakout_game_over");
+    }
+
+    #[test]
+    fn start() {
+        let harness = Harness::new(Breakout::new(1));
+        harness.assert_snapshot("breakout_start");
+    }
+}
@@ -0,0 +1,380 @@
+use crate::audio::Audio;
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::input::{
+    bindings, key_label, set_bindings, Action, Bindings, Input, Player, DEFAULT_BINDINGS, KEY_SLOTS,
+};
+use crate::settings::input_delay;
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{Center, CenterTop, LeftTop};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+const TITLE: &str = "CONTROLS";
+const TITLE_POS: TextPos = Px(8, 8);
+const HEADER_Y: isize = 20;
+const ROWS_Y: isize = 27;
+const ROW_STEP: isize = 8;
+const NAME_X: isize = 8;
+const COLUMN_X: [isize; COLUMNS] = [80, 108, 136];
+const COLUMN_NAMES: [&str; COLUMNS] = ["KEY", "ALT", "PAD"];
+const CELL_WIDTH: usize = 26;
+const RESET: &str = "RESET DEFAULTS";
+const MESSAGE_Y: isize = (SCREEN_HEIGHT - BAR_HEIGHT) as isize - 7;
+//key slots and then the controller
+const COLUMNS: usize = KEY_SLOTS + 1;
+const BINDING_ROWS: [(Player, Action); 13] = [
+    (Player::One, Action::Up),
+    (Player::One, Action::Down),
+    (Player::One, Action::Left),
+    (Player::One, Action::Right),
+    (Player::One, Action::Confirm),
+    (Player::One, Action::Back),
+    (Player::One, Action::Pause),
+    (Player::One, Action::Secondary),
+    (Player::Two, Action::Up),
+    (Player::Two, Action::Down),
+    (Player::Two, Action::Left),
+    (Player::Two, Action::Right),
+    (Player::Two, Action::Confirm),
+];
+//bindings and then reset
+const ROWS: usize = BINDING_ROWS.len() + 1;
+const CAPTURE_TIMEOUT: f64 = 5.0;
+
+/// Lets the player change the keys and controller buttons for each [Action]
+///
+/// The second player's controller uses the first player's buttons so only their keys can be changed
+///
+/// Changes are saved immediately
+pub struct Controls {
+    bindings: Bindings,
+    /// Called with the bindings after every change
+    save: fn(Bindings),
+    row: usize,
+    column: usize,
+    /// Seconds left to press a key or button for the selected cell
+    capture: Option<f64>,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+}
+
+impl Controls {
+    pub fn new() -> Box<Self> {
+        Self::with_bindings(bindings(), set_bindings)
+    }
+
+    pub fn with_bindings(bindings: Bindings, save: fn(Bindings)) -> Box<Self> {
+        Box::new(Self {
+            bindings,
+            save,
+            row: 0,
+            column: 0,
+            capture: None,
+            result: Nothing,
+            input_timer: Timer::new(input_delay()),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("BACK", ButtonDef::Back),
+                    ("CURSOR", ButtonDef::Cursor),
+                    ("CHANGE", ButtonDef::Confirm),
+                ],
+            ),
+        })
+    }
+}
+
+impl Controls {
+    fn binding(&self) -> Option<(Player, Action)> {
+        BINDING_ROWS.get(self.row).copied()
+    }
+
+    fn capturing_key(&self) -> bool {
+        self.capture.is_some() && self.column < KEY_SLOTS
+    }
+
+    fn capturing_pad(&self) -> bool {
+        self.capture.is_some() && self.column == KEY_SLOTS
+    }
+
+    fn apply(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+        (self.save)(bindings);
+    }
+
+    fn finish_capture(&mut self) {
+        self.capture = None;
+        self.input_timer.reset();
+    }
+
+    fn cell_text(
+        &self,
+        player: Player,
+        action: Action,
+        column: usize,
+        controller: Option<Controller>,
+    ) -> String {
+        if column < KEY_SLOTS {
+            self.bindings
+                .key(player, action, column)
+                .map(key_label)
+                .unwrap_or(String::from("-"))
+        } else {
+            self.bindings
+                .pad(action)
+                .map(|button| button.label(controller))
+                .unwrap_or("-")
+                .to_string()
+        }
+    }
+}
+
+impl Game for Controls {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.draw_text(TITLE, TITLE_POS, (CLR_3, Standard8x10, LeftTop));
+        for (x, name) in COLUMN_X.iter().zip(COLUMN_NAMES) {
+            graphics.draw_text(name, Px(*x, HEADER_Y), (CLR_1, Standard4x5, CenterTop));
+        }
+
+        for (i, (player, action)) in BINDING_ROWS.iter().enumerate() {
+            let y = ROWS_Y + i as isize * ROW_STEP;
+            let color = if self.row == i { CLR_3 } else { CLR_2 };
+            let name = match player {
+                Player::One => action.name().to_string(),
+                Player::Two => format!("P2 {}", action.name()),
+            };
+            graphics.draw_text(&name, Px(NAME_X, y + 1), (color, Standard4x5, LeftTop));
+            for (column, x) in COLUMN_X.iter().enumerate() {
+                let selected = self.row == i && self.column == column;
+                let text = if selected && self.capture.is_some() {
+                    String::from("...")
+                } else {
+                    self.cell_text(*player, *action, column, controller)
+                };
+                let color = if *player == Player::Two && column == KEY_SLOTS {
+                    CLR_1
+                } else {
+                    color
+                };
+                graphics.draw_text(&text, Px(*x, y + 3), (color, Standard4x5, Center));
+                if selected {
+                    graphics.draw_rect(
+                        Rect::new_with_size((x - CELL_WIDTH as isize / 2, y - 1), CELL_WIDTH, 7),
+                        stroke(CLR_3),
+                    );
+                }
+            }
+        }
+
+        let reset_color = if self.binding().is_none() {
+            CLR_3
+        } else {
+            CLR_2
+        };
+        graphics.draw_text(
+            RESET,
+            Px(NAME_X, ROWS_Y + BINDING_ROWS.len() as isize * ROW_STEP + 2),
+            (reset_color, Standard4x5, LeftTop),
+        );
+
+        if let Some(remaining) = self.capture {
+            let message = if self.capturing_pad() {
+                "PRESS A BUTTON"
+            } else {
+                "PRESS A KEY"
+            };
+            graphics.draw_text(
+                &format!("{message} ({:.0})", remaining.ceil()),
+                Px(SCREEN_WIDTH as isize / 2, MESSAGE_Y),
+                (CLR_3, Standard4x5, CenterTop),
+            );
+        }
+
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, key: KeyCode) {
+        if self.capturing_key() {
+            if let Some((player, action)) = self.binding() {
+                let mut bindings = self.bindings;
+                bindings.set_key(player, action, self.column, key);
+                self.apply(bindings);
+            }
+            self.finish_capture();
+        } else if self.capturing_pad() {
+            //any key cancels waiting for a controller button
+            self.finish_capture();
+        }
+    }
+
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if let Some(remaining) = &mut self.capture {
+            *remaining -= timing.fixed_time_step;
+            if *remaining <= 0.0 {
+                self.finish_capture();
+            } else if let Some(button) = input.pad_pressed() {
+                if self.capturing_pad() {
+                    if let Some((_, action)) = self.binding() {
+                        let mut bindings = self.bindings;
+                        bindings.set_pad(action, button);
+                        self.apply(bindings);
+                    }
+                }
+                //any button cancels waiting for a key
+                self.finish_capture();
+            }
+            return self.result;
+        }
+
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                self.row = (self.row + ROWS - 1) % ROWS;
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                self.row = (self.row + 1) % ROWS;
+            } else if input.held(Action::Left) {
+                self.input_timer.reset();
+                self.column = (self.column + COLUMNS - 1) % COLUMNS;
+            } else if input.held(Action::Right) {
+                self.input_timer.reset();
+                self.column = (self.column + 1) % COLUMNS;
+            } else if input.pressed(Action::Confirm) {
+                self.input_timer.reset();
+                match self.binding() {
+                    //second player uses the first player's controller buttons
+                    Some((Player::Two, _)) if self.column == KEY_SLOTS => {}
+                    Some(_) => self.capture = Some(CAPTURE_TIMEOUT),
+                    None => self.apply(DEFAULT_BINDINGS),
+                }
+            } else if input.pressed(Action::Back) {
+                self.result = Pop;
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.input_timer.reset();
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn controls() -> Harness<Controls> {
+        Harness::new(Controls::with_bindings(DEFAULT_BINDINGS, |_| {}))
+    }
+
+    /// Press `action` and wait for the menu to accept input again
+    fn tap(harness: &mut Harness<Controls>, action: Action) {
+        harness.press(action);
+        harness.run(input_delay());
+    }
+
+    #[test]
+    fn rebinds_key() {
+        let mut harness = controls();
+        tap(&mut harness, Action::Down);
+        tap(&mut harness, Action::Right);
+        tap(&mut harness, Action::Confirm);
+        assert!(harness.game.capturing_key());
+        harness.game.on_key_press(KeyCode::KeyQ);
+        assert_eq!(harness.game.capture, None);
+        assert_eq!(
+            harness.game.bindings.key(Player::One, Action::Down, 1),
+            Some(KeyCode::KeyQ)
+        );
+        assert_eq!(
+            harness.game.bindings.key(Player::One, Action::Down, 0),
+            Some(KeyCode::ArrowDown)
+        );
+    }
+
+    #[test]
+    fn rebinds_second_player() {
+        let mut harness = controls();
+        //wraps around to reset, then up through the second player's rows
+        for _ in 0..5 {
+            tap(&mut harness, Action::Up);
+        }
+        assert_eq!(harness.game.binding(), Some((Player::Two, Action::Down)));
+        tap(&mut harness, Action::Confirm);
+        harness.game.on_key_press(KeyCode::KeyK);
+        assert_eq!(
+            harness.game.bindings.key(Player::Two, Action::Down, 0),
+            Some(KeyCode::KeyK)
+        );
+        assert_eq!(
+            harness.game.bindings.key(Player::One, Action::Down, 1),
+            Some(KeyCode::KeyS)
+        );
+    }
+
+    #[test]
+    fn second_player_pad_cant_change() {
+        let mut harness = controls();
+        tap(&mut harness, Action::Up);
+        tap(&mut harness, Action::Left);
+        assert_eq!(harness.game.binding(), None);
+        tap(&mut harness, Action::Up);
+        assert_eq!(harness.game.binding(), Some((Player::Two, Action::Confirm)));
+        assert_eq!(harness.game.column, KEY_SLOTS);
+        tap(&mut harness, Action::Confirm);
+        assert_eq!(harness.game.capture, None);
+    }
+
+    #[test]
+    fn key_cancels_pad_capture() {
+        let mut harness = controls();
+        tap(&mut harness, Action::Left);
+        tap(&mut harness, Action::Confirm);
+        assert!(harness.game.capturing_pad());
+        harness.game.on_key_press(KeyCode::KeyQ);
+        assert_eq!(harness.game.capture, None);
+        assert_eq!(harness.game.bindings, DEFAULT_BINDINGS);
+    }
+
+    #[test]
+    fn capture_times_out() {
+        let mut harness = controls();
+        tap(&mut harness, Action::Confirm);
+        assert!(harness.game.capturing_key());
+        harness.run(CAPTURE_TIMEOUT + 0.1);
+        assert_eq!(harness.game.capture, None);
+        assert_eq!(harness.game.bindings, DEFAULT_BINDINGS);
+    }
+
+    #[test]
+    fn resets_defaults() {
+        let mut bindings = DEFAULT_BINDINGS;
+        bindings.set_key(Player::One, Action::Up, 0, KeyCode::KeyI);
+        let mut harness = Harness::new(Controls::with_bindings(bindings, |_| {}));
+        assert_eq!(
+            harness.game.cell_text(Player::One, Action::Up, 0, None),
+            "I"
+        );
+        tap(&mut harness, Action::Up);
+        assert_eq!(harness.game.binding(), None);
+        tap(&mut harness, Action::Confirm);
+        assert_eq!(harness.game.bindings, DEFAULT_BINDINGS);
+        assert_eq!(
+            harness.game.cell_text(Player::One, Action::Up, 0, None),
+            "UP"
+        );
+    }
+
+    #[test]
+    fn back_closes() {
+        let mut harness = controls();
+        assert_eq!(harness.press(Action::Back), Pop);
+    }
+}
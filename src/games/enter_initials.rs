@@ -0,0 +1,272 @@
+use crate::audio::Audio;
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::games::find_score_table;
+use crate::games::high_score_table::render_table;
+use crate::high_scores::{HighScores, Ranking, ScoreEntry, INITIALS_LEN};
+use crate::input::{Action, Input};
+use crate::settings::input_delay;
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{Center, CenterTop};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+const LETTER_STEP: isize = 16;
+const LETTERS_Y: isize = 90;
+//stops the button used to play the game from also changing the initials
+const START_DELAY: f64 = 0.6;
+
+/// Arcade style three letter initials entry, shown when a score makes the high score table
+///
+/// Once confirmed the entry is saved and the table is shown with the new entry highlighted
+pub struct EnterInitials {
+    scores: HighScores,
+    game: &'static str,
+    score: usize,
+    letters: [usize; INITIALS_LEN],
+    slot: usize,
+    saved: Option<(Vec<ScoreEntry>, Option<usize>)>,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+}
+
+impl EnterInitials {
+    /// `score` is saved into `scores`, the caller should check it qualifies
+    pub fn new(game: &'static str, score: usize, scores: HighScores) -> Box<Self> {
+        Box::new(Self {
+            scores,
+            game,
+            score,
+            letters: [0; INITIALS_LEN],
+            slot: 0,
+            saved: None,
+            result: Nothing,
+            input_timer: Timer::new_with_delay(START_DELAY, input_delay()),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("SKIP", ButtonDef::Back),
+                    ("LETTER", ButtonDef::Vert),
+                    ("NEXT", ButtonDef::Confirm),
+                ],
+            ),
+        })
+    }
+}
+
+impl EnterInitials {
+    fn initials(&self) -> String {
+        self.letters
+            .iter()
+            .map(|i| LETTERS[*i] as char)
+            .collect::<String>()
+    }
+
+    fn save(&mut self) {
+        let idx = self
+            .scores
+            .insert(self.game, ScoreEntry::new(self.score, self.initials()));
+        self.saved = Some((self.scores.table(self.game).to_vec(), idx));
+        self.button_bar.set_buttons(&[("DONE", ButtonDef::Confirm)]);
+    }
+}
+
+impl Game for EnterInitials {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        let (name, ranking) = find_score_table(self.game)
+            .map(|table| (table.name, table.ranking))
+            .unwrap_or(("", Ranking::Points));
+        let title = match ranking {
+            Ranking::Points => "NEW HIGH SCORE!",
+            Ranking::Time => "NEW BEST TIME!",
+        };
+        graphics.draw_text(
+            title,
+            Px(SCREEN_WIDTH as isize / 2, 8),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+        graphics.draw_text(
+            &header(name, ranking, self.score),
+            Px(SCREEN_WIDTH as isize / 2, 24),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+
+        if let Some((table, idx)) = &self.saved {
+            render_table(graphics, table, ranking, *idx);
+        } else {
+            graphics.draw_text(
+                "ENTER YOUR INITIALS",
+                Px(SCREEN_WIDTH as isize / 2, 60),
+                (CLR_2, Standard4x5, CenterTop),
+            );
+            let start_x = SCREEN_WIDTH as isize / 2 - LETTER_STEP;
+            for (i, letter) in self.letters.iter().enumerate() {
+                let x = start_x + i as isize * LETTER_STEP;
+                let color = if i == self.slot { CLR_3 } else { CLR_2 };
+                graphics.draw_text(
+                    &(LETTERS[*letter] as char).to_string(),
+                    Px(x, LETTERS_Y),
+                    (color, Standard8x10, Center),
+                );
+                graphics.draw_line((x - 4, LETTERS_Y + 7), (x + 3, LETTERS_Y + 7), color);
+                if i == self.slot {
+                    graphics.draw_triangle(
+                        Triangle::equilateral((x, LETTERS_Y - 11), 6, FlatSide::Bottom),
+                        fill(CLR_3),
+                    );
+                    graphics.draw_triangle(
+                        Triangle::equilateral((x, LETTERS_Y + 12), 6, FlatSide::Top),
+                        fill(CLR_3),
+                    );
+                }
+            }
+        }
+
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if !self.input_timer.update(timing) {
+            return self.result;
+        }
+        let confirm = input.held(Action::Confirm);
+        if self.saved.is_some() {
+            if confirm {
+                self.result = Pop;
+            }
+            return self.result;
+        }
+
+        let letter = &mut self.letters[self.slot];
+        if input.held(Action::Up) {
+            self.input_timer.reset();
+            *letter = (*letter + LETTERS.len() - 1) % LETTERS.len();
+        } else if input.held(Action::Down) {
+            self.input_timer.reset();
+            *letter = (*letter + 1) % LETTERS.len();
+        } else if input.held(Action::Left) {
+            self.input_timer.reset();
+            self.slot = self.slot.saturating_sub(1);
+        } else if input.held(Action::Right) {
+            self.input_timer.reset();
+            self.slot = (self.slot + 1).min(INITIALS_LEN - 1);
+        } else if confirm {
+            self.input_timer.reset();
+            if self.slot == INITIALS_LEN - 1 {
+                self.save();
+            } else {
+                self.slot += 1;
+            }
+        } else if input.held(Action::Back) {
+            self.result = Pop;
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {}
+}
+
+/// Table name and the score being entered
+fn header(name: &str, ranking: Ranking, score: usize) -> String {
+    format!("{name}: {}", ranking.format(score))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::{minesweeper, pong, score_tables};
+    use crate::harness::Harness;
+
+    fn entering(game: &'static str, score: usize, scores: HighScores) -> Harness<EnterInitials> {
+        let mut harness = Harness::new(EnterInitials::new(game, score, scores));
+        harness.run(START_DELAY);
+        harness
+    }
+
+    /// Hold `action` until it changes something
+    fn tap(harness: &mut Harness<EnterInitials>, action: Action) {
+        let state =
+            |game: &EnterInitials| (game.letters, game.slot, game.saved.is_some(), game.result);
+        let before = state(&harness.game);
+        harness.hold(action);
+        harness.run_until(1.0, |game| state(game) != before);
+        harness.release(action);
+    }
+
+    #[test]
+    fn headers_fit_screen() {
+        for table in score_tables() {
+            let largest = match table.ranking {
+                Ranking::Points => 999_999,
+                Ranking::Time => minesweeper::MAX_SECONDS,
+            };
+            let text = header(table.name, table.ranking, largest);
+            let width = Standard4x5.measure(&text).0;
+            assert!(width <= SCREEN_WIDTH, "{text} is {width}px wide");
+        }
+    }
+
+    #[test]
+    fn enters_and_saves_initials() {
+        let mut scores = HighScores::in_memory();
+        scores.insert(pong::ID, ScoreEntry::new(9, String::from("TOP")));
+        let mut harness = entering(pong::ID, 5, scores);
+        harness.assert_snapshot("enter_initials");
+
+        tap(&mut harness, Action::Down);
+        tap(&mut harness, Action::Confirm);
+        tap(&mut harness, Action::Up);
+        tap(&mut harness, Action::Right);
+        tap(&mut harness, Action::Down);
+        tap(&mut harness, Action::Down);
+        tap(&mut harness, Action::Left);
+        assert_eq!(harness.game.slot, 1);
+        assert_eq!(harness.game.initials(), "B C");
+        tap(&mut harness, Action::Confirm);
+        tap(&mut harness, Action::Confirm);
+
+        let (table, idx) = harness.game.saved.clone().unwrap();
+        assert_eq!(idx, Some(1));
+        assert_eq!(table[1].initials, "B C");
+        assert_eq!(table[1].score, 5);
+        assert_eq!(harness.game.scores.table(pong::ID), table);
+        tap(&mut harness, Action::Confirm);
+        assert_eq!(harness.game.result, Pop);
+    }
+
+    #[test]
+    fn saves_best_times() {
+        let mut harness = entering(minesweeper::EASY_ID, 75, HighScores::in_memory());
+        for _ in 0..INITIALS_LEN {
+            tap(&mut harness, Action::Confirm);
+        }
+        let (table, idx) = harness.game.saved.clone().unwrap();
+        assert_eq!(idx, Some(0));
+        assert_eq!(table[0].initials, "AAA");
+        assert_eq!(Ranking::Time.format(table[0].score), "1:15");
+    }
+
+    #[test]
+    fn skip_doesnt_save() {
+        let mut harness = entering(pong::ID, 5, HighScores::in_memory());
+        tap(&mut harness, Action::Back);
+        assert_eq!(harness.game.result, Pop);
+        assert!(harness.game.saved.is_none());
+        assert!(harness.game.scores.table(pong::ID).is_empty());
+    }
+
+    #[test]
+    fn ignores_input_at_start() {
+        let mut harness = Harness::new(EnterInitials::new(pong::ID, 5, HighScores::in_memory()));
+        harness.hold(Action::Confirm);
+        harness.run(START_DELAY / 2.0);
+        assert_eq!(harness.game.slot, 0);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::audio::Audio;
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::games::score_tables;
+use crate::high_scores::{HighScores, Ranking, ScoreEntry, ScoreTable, MAX_ENTRIES};
+use crate::input::{Action, Input};
+use crate::settings::input_delay;
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{CenterTop, LeftTop};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+const TITLE: &str = "HIGH SCORES";
+const TITLE_POS: TextPos = Px(8, 8);
+const GAME_NAME_Y: isize = 24;
+const TABLE_X: isize = 12;
+const TABLE_Y: isize = 40;
+const ROW_STEP: isize = 10;
+
+/// Draw `entries` as a [MAX_ENTRIES] row table starting at [TABLE_Y]
+///
+/// Row `highlight` is drawn brighter, scores are shown using `ranking`
+pub fn render_table(
+    graphics: &mut Graphics,
+    entries: &[ScoreEntry],
+    ranking: Ranking,
+    highlight: Option<usize>,
+) {
+    for i in 0..MAX_ENTRIES {
+        let text = match entries.get(i) {
+            Some(entry) => format!(
+                "{: >2}. {: <3} {: >8} {}",
+                i + 1,
+                entry.initials,
+                ranking.format(entry.score),
+                entry.date
+            ),
+            None => format!("{: >2}. --- {: >8} ----------", i + 1, "-"),
+        };
+        let color = if highlight == Some(i) {
+            CLR_3
+        } else if i < entries.len() {
+            CLR_2
+        } else {
+            CLR_1
+        };
+        graphics.draw_text(
+            &text,
+            Px(TABLE_X, TABLE_Y + i as isize * ROW_STEP),
+            (color, Standard4x5, LeftTop),
+        );
+    }
+}
+
+/// Shows every [ScoreTable], one at a time
+pub struct HighScoreTable {
+    scores: HighScores,
+    tables: Vec<&'static ScoreTable>,
+    table_idx: usize,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+}
+
+impl HighScoreTable {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            scores: HighScores::load(),
+            tables: score_tables(),
+            table_idx: 0,
+            result: Nothing,
+            input_timer: Timer::new(input_delay()),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[("BACK", ButtonDef::Back), ("TABLE", ButtonDef::Horz)],
+            ),
+        })
+    }
+}
+
+impl Game for HighScoreTable {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.draw_text(TITLE, TITLE_POS, (CLR_3, Standard8x10, LeftTop));
+        let table = self.tables[self.table_idx];
+        graphics.draw_text(
+            &format!("< {} >", table.name),
+            Px(SCREEN_WIDTH as isize / 2, GAME_NAME_Y),
+            (CLR_2, Standard8x10, CenterTop),
+        );
+        render_table(graphics, self.scores.table(table.id), table.ranking, None);
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Left) {
+                self.input_timer.reset();
+                if self.table_idx == 0 {
+                    self.table_idx = self.tables.len() - 1;
+                } else {
+                    self.table_idx -= 1;
+                }
+            } else if input.held(Action::Right) {
+                self.input_timer.reset();
+                if self.table_idx == self.tables.len() - 1 {
+                    self.table_idx = 0;
+                } else {
+                    self.table_idx += 1;
+                }
+            } else if input.held(Action::Back) {
+                self.result = Pop;
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {}
+}
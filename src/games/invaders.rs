@@ -0,0 +1,842 @@
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::game_over::draw_game_over;
+use crate::games::invaders::State::*;
+use crate::high_scores::submit_score;
+use crate::input::{Action, Input};
+use crate::sprite::draw_sprite;
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::{LeftTop, RightTop};
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+use std::collections::BTreeSet;
+
+pub const ID: &str = "invaders";
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+
+const ALIEN_COLS: usize = 8;
+const ALIEN_ROWS: usize = 5;
+const ALIEN_SIZE: (usize, usize) = (8, 8);
+const ALIEN_SPACING: (usize, usize) = (12, 11);
+const ALIEN_STEP_X: isize = 2;
+const ALIEN_STEP_Y: isize = 4;
+const ALIEN_MARGIN: isize = 2;
+const FORMATION_START: Coord = Coord::new(10, 28);
+const FORMATION_WAVE_DROP: isize = 4;
+const MAX_WAVE_DROP: usize = 4;
+//seconds between formation steps with a full grid, shrinks as aliens are killed
+const MARCH_RATE: f64 = 0.5;
+const MIN_MARCH_RATE: f64 = 0.02;
+const MARCH_SPEED_UP_PER_WAVE: f64 = 0.9;
+
+const TANK_SIZE: (usize, usize) = (11, 6);
+const TANK_Y: isize = 132;
+const TANK_MOVE_RATE: f64 = 0.016;
+const RESPAWN_DELAY: f64 = 1.5;
+const STARTING_LIVES: usize = 3;
+
+const BULLET_HEIGHT: isize = 4;
+//pixels moved per update
+const PLAYER_BULLET_SPEED: usize = 3;
+const ALIEN_BULLET_MOVE_RATE: f64 = 0.02;
+const MAX_ALIEN_BULLETS: usize = 3;
+const ALIEN_FIRE_RATE: f64 = 0.8;
+
+const BUNKER_COUNT: usize = 4;
+const BUNKER_Y: isize = 110;
+const BUNKER_SPLASH: isize = 2;
+
+const UFO_WIDTH: usize = 16;
+const UFO_Y: isize = 17;
+const UFO_MOVE_RATE: f64 = 0.03;
+const UFO_DELAY: f64 = 20.0;
+const UFO_SCORES: [usize; 4] = [50, 100, 150, 300];
+
+const EXPLOSION_DURATION: f64 = 0.15;
+
+const SQUID: [[u16; 8]; 2] = [
+    [
+        0b00011000, 0b00111100, 0b01111110, 0b11011011, 0b11111111, 0b00100100, 0b01011010,
+        0b10100101,
+    ],
+    [
+        0b00011000, 0b00111100, 0b01111110, 0b11011011, 0b11111111, 0b01011010, 0b10000001,
+        0b01000010,
+    ],
+];
+const CRAB: [[u16; 8]; 2] = [
+    [
+        0b01000010, 0b00100100, 0b01111110, 0b11011011, 0b11111111, 0b10111101, 0b10100101,
+        0b00100100,
+    ],
+    [
+        0b01000010, 0b00100100, 0b01111110, 0b11011011, 0b11111111, 0b01111110, 0b00100100,
+        0b01000010,
+    ],
+];
+const OCTOPUS: [[u16; 8]; 2] = [
+    [
+        0b00111100, 0b01111110, 0b11111111, 0b11011011, 0b11111111, 0b00100100, 0b01011010,
+        0b10000001,
+    ],
+    [
+        0b00111100, 0b01111110, 0b11111111, 0b11011011, 0b11111111, 0b01100110, 0b11000011,
+        0b01100110,
+    ],
+];
+const EXPLOSION: [u16; 8] = [
+    0b10010010, 0b01000100, 0b00101000, 0b11000011, 0b00101000, 0b01000100, 0b10010010, 0,
+];
+const UFO: [u16; 7] = [
+    0b0000011111100000,
+    0b0001111111111000,
+    0b0011111111111100,
+    0b0110110110110110,
+    0b1111111111111111,
+    0b0011100110011100,
+    0b0001000000001000,
+];
+const TANK: [u16; 6] = [
+    0b00000100000,
+    0b00001110000,
+    0b01111111110,
+    0b11111111111,
+    0b11111111111,
+    0b11111111111,
+];
+const BUNKER: [u32; 12] = [
+    0b000111111111111000,
+    0b001111111111111100,
+    0b011111111111111110,
+    0b111111111111111111,
+    0b111111111111111111,
+    0b111111111111111111,
+    0b111111111111111111,
+    0b111111111111111111,
+    0b111111111111111111,
+    0b111110000000011111,
+    0b111100000000001111,
+    0b111000000000000111,
+];
+const BUNKER_WIDTH: usize = 18;
+
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.left() <= b.right() && b.left() <= a.right() && a.top() <= b.bottom() && b.top() <= a.bottom()
+}
+
+fn bullet_rect(bullet: Coord) -> Rect {
+    Rect::new(bullet, bullet + (0, BULLET_HEIGHT - 1))
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Playing,
+    Respawning,
+    Dead,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Alien {
+    col: usize,
+    row: usize,
+}
+
+impl Alien {
+    fn sprite(&self, frame: usize) -> &'static [u16; 8] {
+        match self.row {
+            0 => &SQUID[frame],
+            1 | 2 => &CRAB[frame],
+            _ => &OCTOPUS[frame],
+        }
+    }
+
+    fn score(&self) -> usize {
+        match self.row {
+            0 => 30,
+            1 | 2 => 20,
+            _ => 10,
+        }
+    }
+
+    fn offset(&self) -> Coord {
+        Coord::from((self.col * ALIEN_SPACING.0, self.row * ALIEN_SPACING.1))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Ufo {
+    x: isize,
+    direction: isize,
+}
+
+impl Ufo {
+    fn rect(&self) -> Rect {
+        Rect::new_with_size((self.x, UFO_Y), UFO_WIDTH - 1, UFO.len() - 1)
+    }
+}
+
+pub struct Invaders {
+    aliens: Vec<Alien>,
+    formation: Coord,
+    formation_direction: isize,
+    anim_frame: usize,
+    next_march: f64,
+    tank_x: isize,
+    next_tank_move: Timer,
+    player_bullet: Option<Coord>,
+    alien_bullets: Vec<Coord>,
+    next_alien_bullet_move: Timer,
+    next_alien_fire: Timer,
+    /// Remaining bunker pixels as (x, y), ordered so damage is the same for every run of a seed
+    bunkers: BTreeSet<(isize, isize)>,
+    ufo: Option<Ufo>,
+    next_ufo: Timer,
+    next_ufo_move: Timer,
+    explosions: Vec<(Coord, f64)>,
+    respawn: Timer,
+    lives: usize,
+    wave: usize,
+    score: usize,
+    state: State,
+    result: GameUpdateResult,
+    button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
+}
+
+impl Invaders {
+    pub fn new(seed: u64) -> Box<Self> {
+        let mut invaders = Box::new(Self {
+            aliens: vec![],
+            formation: FORMATION_START,
+            formation_direction: 1,
+            anim_frame: 0,
+            next_march: MARCH_RATE,
+            tank_x: (SCREEN_WIDTH - TANK_SIZE.0) as isize / 2,
+            next_tank_move: Timer::new(TANK_MOVE_RATE),
+            player_bullet: None,
+            alien_bullets: vec![],
+            next_alien_bullet_move: Timer::new(ALIEN_BULLET_MOVE_RATE),
+            next_alien_fire: Timer::new_with_delay(ALIEN_FIRE_RATE * 2.0, ALIEN_FIRE_RATE),
+            bunkers: BTreeSet::new(),
+            ufo: None,
+            next_ufo: Timer::new_with_delay(UFO_DELAY, UFO_DELAY),
+            next_ufo_move: Timer::new(UFO_MOVE_RATE),
+            explosions: vec![],
+            respawn: Timer::new_once(RESPAWN_DELAY),
+            lives: STARTING_LIVES,
+            wave: 1,
+            score: 0,
+            state: Playing,
+            result: Nothing,
+            button_bar: ButtonBar::new(
+                coord!(0, PLAY_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("PAUSE", ButtonDef::Back),
+                    ("MOVE", ButtonDef::Horz),
+                    ("FIRE", ButtonDef::Confirm),
+                ],
+            ),
+            seed,
+            rng: Rng::with_seed(seed),
+        });
+        invaders.start_wave();
+        invaders.build_bunkers();
+        invaders
+    }
+}
+
+impl Invaders {
+    fn start_wave(&mut self) {
+        self.aliens = (0..ALIEN_ROWS)
+            .flat_map(|row| (0..ALIEN_COLS).map(move |col| Alien { col, row }))
+            .collect();
+        let drop = (self.wave - 1).min(MAX_WAVE_DROP) as isize * FORMATION_WAVE_DROP;
+        self.formation = FORMATION_START + (0, drop);
+        self.formation_direction = 1;
+        self.alien_bullets.clear();
+        self.player_bullet = None;
+        self.next_march = self.march_rate();
+    }
+
+    fn build_bunkers(&mut self) {
+        self.bunkers.clear();
+        let section = SCREEN_WIDTH / BUNKER_COUNT;
+        for i in 0..BUNKER_COUNT {
+            let x = (section * i + (section - BUNKER_WIDTH) / 2) as isize;
+            for (y, row) in BUNKER.iter().enumerate() {
+                for bx in 0..BUNKER_WIDTH {
+                    if (row >> (BUNKER_WIDTH - 1 - bx)) & 1 == 1 {
+                        self.bunkers
+                            .insert((x + bx as isize, BUNKER_Y + y as isize));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Delay between formation steps, the formation speeds up as it shrinks and on later waves
+    fn march_rate(&self) -> f64 {
+        let remaining = self.aliens.len() as f64 / (ALIEN_COLS * ALIEN_ROWS) as f64;
+        let wave_mod = MARCH_SPEED_UP_PER_WAVE.powi(self.wave as i32 - 1);
+        (MARCH_RATE * remaining * wave_mod).max(MIN_MARCH_RATE)
+    }
+
+    fn alien_rect(&self, alien: &Alien) -> Rect {
+        Rect::new_with_size(
+            self.formation + alien.offset(),
+            ALIEN_SIZE.0 - 1,
+            ALIEN_SIZE.1 - 1,
+        )
+    }
+
+    fn tank_rect(&self) -> Rect {
+        Rect::new_with_size((self.tank_x, TANK_Y), TANK_SIZE.0 - 1, TANK_SIZE.1 - 1)
+    }
+
+    fn march(&mut self, audio: &mut Audio) {
+        self.anim_frame = 1 - self.anim_frame;
+        let (left, right) = self
+            .aliens
+            .iter()
+            .map(|alien| self.alien_rect(alien))
+            .fold((isize::MAX, isize::MIN), |(l, r), rect| {
+                (l.min(rect.left()), r.max(rect.right()))
+            });
+        let next_left = left + ALIEN_STEP_X * self.formation_direction;
+        let next_right = right + ALIEN_STEP_X * self.formation_direction;
+        if next_left < ALIEN_MARGIN || next_right >= SCREEN_WIDTH as isize - ALIEN_MARGIN {
+            self.formation_direction = -self.formation_direction;
+            self.formation = self.formation + (0, ALIEN_STEP_Y);
+        } else {
+            self.formation = self.formation + (ALIEN_STEP_X * self.formation_direction, 0);
+        }
+
+        let rects: Vec<Rect> = self.aliens.iter().map(|a| self.alien_rect(a)).collect();
+        self.bunkers
+            .retain(|px| !rects.iter().any(|rect| rect.contains(Coord::from(*px))));
+        if rects.iter().any(|rect| rect.bottom() >= TANK_Y) {
+            self.lives = 0;
+            self.kill_player(audio);
+        }
+    }
+
+    fn fire_alien_bullet(&mut self) {
+        if self.alien_bullets.len() >= MAX_ALIEN_BULLETS || self.aliens.is_empty() {
+            return;
+        }
+        let mut cols: Vec<usize> = self.aliens.iter().map(|a| a.col).collect();
+        cols.sort_unstable();
+        cols.dedup();
+        let col = cols[self.rng.usize(0..cols.len())];
+        if let Some(shooter) = self
+            .aliens
+            .iter()
+            .filter(|a| a.col == col)
+            .max_by_key(|a| a.row)
+        {
+            let rect = self.alien_rect(shooter);
+            self.alien_bullets
+                .push(Coord::new(rect.center().x, rect.bottom() + 1));
+        }
+    }
+
+    /// Remove bunker pixels around where `bullet` hits, returns true if anything was hit
+    fn damage_bunker(&mut self, bullet: &Rect) -> bool {
+        let hit = self
+            .bunkers
+            .iter()
+            .find(|px| bullet.contains(Coord::from(**px)))
+            .copied();
+        if let Some(hit) = hit {
+            self.bunkers.retain(|px| {
+                let diff = Coord::from(*px) - Coord::from(hit);
+                diff.x.abs() + diff.y.abs() > BUNKER_SPLASH
+                    || (diff.x.abs() == BUNKER_SPLASH && self.rng.bool())
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    fn kill_player(&mut self, audio: &mut Audio) {
+        self.lives = self.lives.saturating_sub(1);
+        audio.play(Sound::Death);
+        self.alien_bullets.clear();
+        self.player_bullet = None;
+        self.explosions
+            .push((self.tank_rect().top_left(), RESPAWN_DELAY));
+        if self.lives == 0 {
+            self.state = Dead;
+            if self.result == Nothing {
+                self.result = submit_score(ID, self.score);
+            }
+        } else {
+            self.state = Respawning;
+            self.respawn.reset();
+        }
+    }
+
+    fn update_player_bullet(&mut self, audio: &mut Audio) {
+        if let Some(bullet) = self.player_bullet {
+            let bullet = bullet + (0, -1);
+            self.player_bullet = Some(bullet);
+            let rect = bullet_rect(bullet);
+            if bullet.y < 0 {
+                self.player_bullet = None;
+            } else if let Some(i) = self
+                .aliens
+                .iter()
+                .position(|alien| overlaps(&self.alien_rect(alien), &rect))
+            {
+                let alien = self.aliens.remove(i);
+                self.score += alien.score();
+                self.explosions
+                    .push((self.alien_rect(&alien).top_left(), EXPLOSION_DURATION));
+                audio.play(Sound::Wall);
+                self.player_bullet = None;
+                if self.aliens.is_empty() {
+                    self.wave += 1;
+                    self.start_wave();
+                }
+            } else if let Some(ufo) = self.ufo.filter(|ufo| overlaps(&ufo.rect(), &rect)) {
+                self.score += UFO_SCORES[self.rng.usize(0..UFO_SCORES.len())];
+                self.explosions
+                    .push((ufo.rect().top_left() + (4, 0), EXPLOSION_DURATION * 3.0));
+                self.ufo = None;
+                audio.play(Sound::Apple);
+                self.player_bullet = None;
+            } else if let Some(i) = self
+                .alien_bullets
+                .iter()
+                .position(|b| overlaps(&bullet_rect(*b), &rect))
+            {
+                self.alien_bullets.remove(i);
+                self.player_bullet = None;
+            } else if self.damage_bunker(&rect) {
+                self.player_bullet = None;
+            }
+        }
+    }
+
+    fn update_alien_bullets(&mut self, audio: &mut Audio) {
+        let tank = self.tank_rect();
+        let mut i = 0;
+        while i < self.alien_bullets.len() {
+            let bullet = self.alien_bullets[i] + (0, 1);
+            self.alien_bullets[i] = bullet;
+            let rect = bullet_rect(bullet);
+            if overlaps(&tank, &rect) {
+                self.kill_player(audio);
+                return;
+            } else if bullet.y >= PLAY_HEIGHT as isize || self.damage_bunker(&rect) {
+                self.alien_bullets.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn update_ufo(&mut self, timing: &Timing) {
+        if let Some(ufo) = &mut self.ufo {
+            if self.next_ufo_move.update(timing) {
+                ufo.x += ufo.direction;
+                if ufo.x < -(UFO_WIDTH as isize) || ufo.x > SCREEN_WIDTH as isize {
+                    self.ufo = None;
+                }
+            }
+        } else if self.next_ufo.update(timing) {
+            self.ufo = Some(if self.rng.bool() {
+                Ufo {
+                    x: -(UFO_WIDTH as isize),
+                    direction: 1,
+                }
+            } else {
+                Ufo {
+                    x: SCREEN_WIDTH as isize,
+                    direction: -1,
+                }
+            });
+        }
+    }
+}
+
+impl Game for Invaders {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.draw_text(
+            &format!("SCORE: {: >6}", self.score),
+            TextPos::Px(3, 3),
+            (CLR_2, Standard8x10, LeftTop),
+        );
+        graphics.draw_text(
+            &format!("WAVE {}", self.wave),
+            TextPos::Px(SCREEN_WIDTH as isize - 3, 3),
+            (CLR_2, Standard4x5, RightTop),
+        );
+        for i in 0..self.lives.saturating_sub(1) {
+            draw_sprite(
+                graphics,
+                Coord::new(
+                    SCREEN_WIDTH as isize - 3 - ((i + 1) * (TANK_SIZE.0 + 2)) as isize,
+                    9,
+                ),
+                &TANK,
+                TANK_SIZE.0,
+                CLR_2,
+            );
+        }
+
+        for (x, y) in &self.bunkers {
+            graphics.set_pixel(*x, *y, CLR_1);
+        }
+
+        for alien in &self.aliens {
+            draw_sprite(
+                graphics,
+                self.alien_rect(alien).top_left(),
+                alien.sprite(self.anim_frame),
+                ALIEN_SIZE.0,
+                CLR_3,
+            );
+        }
+
+        if let Some(ufo) = self.ufo {
+            draw_sprite(graphics, ufo.rect().top_left(), &UFO, UFO_WIDTH, CLR_2);
+        }
+
+        for (pos, _) in &self.explosions {
+            draw_sprite(graphics, *pos, &EXPLOSION, ALIEN_SIZE.0, CLR_2);
+        }
+
+        if self.state == Playing {
+            draw_sprite(
+                graphics,
+                self.tank_rect().top_left(),
+                &TANK,
+                TANK_SIZE.0,
+                CLR_3,
+            );
+        }
+
+        if let Some(bullet) = self.player_bullet {
+            graphics.draw_line(bullet, bullet + (0, BULLET_HEIGHT - 1), CLR_3);
+        }
+        for bullet in &self.alien_bullets {
+            graphics.draw_line(*bullet, *bullet + (0, BULLET_HEIGHT - 1), CLR_2);
+        }
+
+        graphics.draw_line(
+            (0, PLAY_HEIGHT as isize - 1),
+            (SCREEN_WIDTH as isize, PLAY_HEIGHT as isize - 1),
+            CLR_1,
+        );
+
+        self.button_bar.render(graphics, controller);
+
+        if self.state == Dead {
+            draw_game_over(graphics, self.seed);
+        }
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    #[allow(clippy::collapsible_if)] //for readability
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        self.explosions
+            .iter_mut()
+            .for_each(|(_, remaining)| *remaining -= timing.fixed_time_step);
+        self.explosions.retain(|(_, remaining)| *remaining > 0.0);
+
+        match self.state {
+            Playing => {
+                if self.next_tank_move.update(timing) {
+                    if input.held(Action::Left) {
+                        self.tank_x = (self.tank_x - 1).max(0);
+                    } else if input.held(Action::Right) {
+                        self.tank_x = (self.tank_x + 1).min((SCREEN_WIDTH - TANK_SIZE.0) as isize);
+                    }
+                }
+
+                if self.player_bullet.is_none() && input.held(Action::Confirm) {
+                    let tank = self.tank_rect();
+                    self.player_bullet = Some(Coord::new(tank.center().x, tank.top() - 1));
+                    audio.play(Sound::Paddle);
+                }
+
+                for _ in 0..PLAYER_BULLET_SPEED {
+                    self.update_player_bullet(audio);
+                }
+                if self.state == Playing && self.next_alien_bullet_move.update(timing) {
+                    self.update_alien_bullets(audio);
+                }
+                if self.state == Playing && self.next_alien_fire.update(timing) {
+                    if self.rng.f64() < 0.7 {
+                        self.fire_alien_bullet();
+                    }
+                }
+
+                self.update_ufo(timing);
+
+                self.next_march -= timing.fixed_time_step;
+                if self.state == Playing && self.next_march <= 0.0 {
+                    self.march(audio);
+                    self.next_march = self.march_rate();
+                }
+            }
+            Respawning => {
+                self.update_ufo(timing);
+                if self.respawn.update(timing) {
+                    self.tank_x = (SCREEN_WIDTH - TANK_SIZE.0) as isize / 2;
+                    self.state = Playing;
+                }
+            }
+            Dead => {}
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+    use crate::GameUpdateResult::Push;
+    use crate::{Screen, UPDATES_PER_SECOND};
+
+    /// Game where the aliens don't move or shoot and the UFO doesn't appear
+    fn quiet() -> Harness<Invaders> {
+        let mut harness = Harness::new(Invaders::new(1));
+        harness.game.next_march = 1000.0;
+        harness.game.next_alien_fire = Timer::new_once(1000.0);
+        harness.game.next_ufo = Timer::new_once(1000.0);
+        harness
+    }
+
+    /// Move the tank so its bullets go up through `x`
+    fn aim(harness: &mut Harness<Invaders>, x: isize) {
+        harness.game.tank_x = x - TANK_SIZE.0 as isize / 2;
+        assert_eq!(harness.game.tank_rect().center().x, x);
+    }
+
+    fn fire(harness: &mut Harness<Invaders>) {
+        harness.hold(Action::Confirm);
+        harness.tick();
+        harness.release(Action::Confirm);
+        assert!(harness.game.player_bullet.is_some());
+    }
+
+    #[test]
+    fn start() {
+        let harness = Harness::new(Invaders::new(1));
+        assert_eq!(harness.game.aliens.len(), ALIEN_COLS * ALIEN_ROWS);
+        assert_eq!(harness.game.lives, STARTING_LIVES);
+        harness.assert_snapshot("invaders_start");
+    }
+
+    #[test]
+    fn moves_tank() {
+        let mut harness = quiet();
+        let x = harness.game.tank_x;
+        harness.hold(Action::Left);
+        harness.run(0.5);
+        harness.release(Action::Left);
+        assert!(harness.game.tank_x < x);
+        harness.game.tank_x = 1;
+        harness.hold(Action::Left);
+        harness.run(0.5);
+        assert_eq!(harness.game.tank_x, 0);
+    }
+
+    #[test]
+    fn march_speeds_up() {
+        let mut harness = Harness::new(Invaders::new(1));
+        harness.game.next_alien_fire = Timer::new_once(1000.0);
+        let count_steps = |harness: &mut Harness<Invaders>| {
+            let mut steps = 0;
+            for _ in 0..UPDATES_PER_SECOND * 2 {
+                let formation = harness.game.formation;
+                harness.tick();
+                if harness.game.formation != formation {
+                    steps += 1;
+                }
+            }
+            steps
+        };
+        let full = count_steps(&mut harness);
+        assert_eq!(harness.game.march_rate(), MARCH_RATE);
+
+        harness.game.aliens.retain(|alien| alien.row == 0);
+        let fewer = count_steps(&mut harness);
+        assert!(
+            fewer > full * 3,
+            "{full} steps full, {fewer} with a row left"
+        );
+
+        harness.game.aliens.truncate(1);
+        assert_eq!(harness.game.march_rate(), MIN_MARCH_RATE);
+        harness.game.wave = 2;
+        harness.game.aliens = vec![Alien { col: 0, row: 0 }; ALIEN_COLS * ALIEN_ROWS];
+        assert!(harness.game.march_rate() < MARCH_RATE);
+    }
+
+    #[test]
+    fn formation_drops_at_edges() {
+        let mut harness = quiet();
+        harness.game.formation_direction = -1;
+        harness.game.formation = Coord::new(ALIEN_MARGIN + 1, FORMATION_START.y);
+        harness.game.march(&mut Audio::null());
+        assert_eq!(harness.game.formation_direction, 1);
+        assert_eq!(
+            harness.game.formation,
+            Coord::new(ALIEN_MARGIN + 1, FORMATION_START.y + ALIEN_STEP_Y)
+        );
+    }
+
+    #[test]
+    fn shooting_aliens_scores() {
+        let mut harness = quiet();
+        harness.game.bunkers.clear();
+        let alien = Alien { col: 2, row: 4 };
+        let x = harness.game.alien_rect(&alien).center().x;
+        aim(&mut harness, x);
+        fire(&mut harness);
+        harness.run_until(1.0, |game| game.player_bullet.is_none());
+        assert!(!harness.game.aliens.contains(&alien));
+        assert_eq!(harness.game.aliens.len(), ALIEN_COLS * ALIEN_ROWS - 1);
+        assert_eq!(harness.game.score, alien.score());
+    }
+
+    #[test]
+    fn bullets_erode_bunkers() {
+        let mut harness = quiet();
+        let pixels = harness.game.bunkers.len();
+        let x = (SCREEN_WIDTH / BUNKER_COUNT / 2) as isize;
+        aim(&mut harness, x);
+        fire(&mut harness);
+        harness.run_until(1.0, |game| game.player_bullet.is_none());
+        let after_player = harness.game.bunkers.len();
+        assert!(after_player < pixels);
+        assert_eq!(harness.game.aliens.len(), ALIEN_COLS * ALIEN_ROWS);
+
+        harness.game.alien_bullets = vec![Coord::new(x + 4, BUNKER_Y - BULLET_HEIGHT)];
+        harness.run_until(1.0, |game| game.alien_bullets.is_empty());
+        assert!(harness.game.bunkers.len() < after_player);
+        assert_eq!(harness.game.lives, STARTING_LIVES);
+    }
+
+    #[test]
+    fn same_seed_erodes_bunkers_the_same() {
+        let play = || {
+            let mut harness = Harness::new(Invaders::new(7));
+            harness.hold(Action::Confirm);
+            harness.run(20.0);
+            harness.game.bunkers.clone()
+        };
+        let bunkers = play();
+        assert!(bunkers.len() < Invaders::new(7).bunkers.len());
+        assert_eq!(play(), bunkers);
+    }
+
+    #[test]
+    fn alien_bullets_cost_a_life() {
+        let mut harness = quiet();
+        let tank = harness.game.tank_rect();
+        harness.game.alien_bullets = vec![Coord::new(tank.center().x, tank.top() - 10)];
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Respawning);
+        assert_eq!(harness.game.lives, STARTING_LIVES - 1);
+        assert!(harness.game.alien_bullets.is_empty());
+
+        harness.hold(Action::Left);
+        harness.run(RESPAWN_DELAY + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert!(harness.game.tank_x < tank.left());
+    }
+
+    #[test]
+    fn game_over() {
+        let mut harness = quiet();
+        harness.game.lives = 1;
+        harness.game.score = 120;
+        let tank = harness.game.tank_rect();
+        harness.game.alien_bullets = vec![Coord::new(tank.center().x, tank.top() - 10)];
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Dead);
+        assert_eq!(
+            harness.game.result,
+            Push(Screen::EnterInitials {
+                game: ID,
+                score: 120
+            })
+        );
+        harness.run(EXPLOSION_DURATION + RESPAWN_DELAY);
+        harness.assert_snapshot("invaders_game_over");
+    }
+
+    #[test]
+    fn aliens_landing_ends_game() {
+        let mut harness = quiet();
+        harness.game.formation = Coord::new(FORMATION_START.x, TANK_Y - 50);
+        harness.game.next_march = 0.0;
+        harness.tick();
+        assert_eq!(harness.game.state, Dead);
+        assert_eq!(harness.game.lives, 0);
+    }
+
+    #[test]
+    fn clearing_wave_starts_next() {
+        let mut harness = quiet();
+        harness.game.bunkers.clear();
+        let alien = Alien { col: 5, row: 1 };
+        harness.game.aliens = vec![alien];
+        let x = harness.game.alien_rect(&alien).center().x;
+        aim(&mut harness, x);
+        fire(&mut harness);
+        harness.run_until(1.0, |game| game.wave > 1);
+        assert_eq!(harness.game.score, alien.score());
+        assert_eq!(harness.game.aliens.len(), ALIEN_COLS * ALIEN_ROWS);
+        assert_eq!(
+            harness.game.formation,
+            FORMATION_START + (0, FORMATION_WAVE_DROP)
+        );
+        assert!(harness.game.march_rate() < MARCH_RATE);
+    }
+
+    #[test]
+    fn shooting_ufo_scores() {
+        let mut harness = quiet();
+        harness.game.bunkers.clear();
+        harness.game.next_ufo_move = Timer::new_once(1000.0);
+        harness.game.ufo = Some(Ufo {
+            x: 140,
+            direction: -1,
+        });
+        aim(&mut harness, 147);
+        fire(&mut harness);
+        harness.run_until(1.0, |game| game.player_bullet.is_none());
+        assert_eq!(harness.game.ufo, None);
+        assert!(UFO_SCORES.contains(&harness.game.score));
+        assert_eq!(harness.game.aliens.len(), ALIEN_COLS * ALIEN_ROWS);
+    }
+
+    #[test]
+    fn ufo_crosses_screen() {
+        let mut harness = quiet();
+        harness.game.next_ufo = Timer::new_once(0.1);
+        harness.run_until(1.0, |game| game.ufo.is_some());
+        harness.game.next_ufo = Timer::new_once(1000.0);
+        harness.run_until(10.0, |game| game.ufo.is_none());
+    }
+}
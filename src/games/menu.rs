@@ -1,30 +1,116 @@
+use crate::audio::Audio;
 use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
-use crate::GameName::{Pong, Snake};
+use crate::games::{daily_challenge, GameDescriptor, GAMES, ICON_SIZE};
+use crate::input::{Action, Input};
+use crate::settings::input_delay;
+use crate::sprite::draw_sprite;
 use crate::GameUpdateResult::{Nothing, Pop, Push};
-use crate::{Game, GameUpdateResult, CLR_2, CLR_3, INPUT_DELAY, SCREEN_HEIGHT, SCREEN_WIDTH};
-use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::LeftTop;
+use crate::{Game, GameUpdateResult, Screen, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{CenterBottom, LeftTop};
 use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
 use pixels_graphics_lib::prelude::font::standard_8x10;
-use pixels_graphics_lib::prelude::PixelFont::Standard8x10;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
 use pixels_graphics_lib::prelude::*;
 
 const TITLE: &str = "GAMES";
-const OPTIONS: [&str; 2] = ["PONG", "SNAKE"];
 const TITLE_POS: TextPos = Px(8, 8);
 const CURSOR_X: isize = 8;
-const MENU_X: isize = 20;
+const ICON_X: isize = 20;
+const MENU_X: isize = ICON_X + ICON_SIZE as isize + 4;
 const MENU_START_Y: isize = 30;
 const MENU_STEP: usize = standard_8x10::CHAR_HEIGHT + 4;
+const DESCRIPTION_Y: isize = (SCREEN_HEIGHT - BAR_HEIGHT) as isize - 4;
+const VISIBLE_OPTIONS: usize = (DESCRIPTION_Y as usize - 10 - MENU_START_Y as usize) / MENU_STEP;
+const SCROLL_ARROW_X: isize = SCREEN_WIDTH as isize - 12;
+/// Seconds without input before a random game's demo is played
+const ATTRACT_DELAY: f64 = 30.0;
+
+const CONTROLLER_ICON: [u8; ICON_SIZE] = [
+    0b00000000, 0b01111110, 0b11111111, 0b10111011, 0b00010101, 0b10111011, 0b11111111, 0b01100110,
+];
+const CALENDAR_ICON: [u8; ICON_SIZE] = [
+    0b01000010, 0b11111111, 0b10000001, 0b11111111, 0b10101011, 0b10000001, 0b10101011, 0b11111111,
+];
+const REPLAY_ICON: [u8; ICON_SIZE] = [
+    0b00000000, 0b01000000, 0b01110000, 0b01111100, 0b01111111, 0b01111100, 0b01110000, 0b01000000,
+];
+const GEAR_ICON: [u8; ICON_SIZE] = [
+    0b00011000, 0b01011010, 0b00111100, 0b11100111, 0b11100111, 0b00111100, 0b01011010, 0b00011000,
+];
+const TROPHY_ICON: [u8; ICON_SIZE] = [
+    0b01111110, 0b11111111, 0b10111101, 0b01111110, 0b00111100, 0b00011000, 0b00011000, 0b00111100,
+];
+
+struct MenuItem {
+    name: &'static str,
+    description: String,
+    icon: &'static [u8; ICON_SIZE],
+    screen: Screen,
+}
+
+/// All games from the registry followed by the other screens
+fn menu_items() -> Vec<MenuItem> {
+    let mut items: Vec<MenuItem> = GAMES
+        .iter()
+        .map(|game| MenuItem {
+            name: game.name,
+            description: game.description.to_string(),
+            icon: &game.icon,
+            screen: Screen::Game {
+                id: game.id,
+                seed: None,
+            },
+        })
+        .collect();
+    let (daily_game, daily_seed) = daily_challenge();
+    items.push(MenuItem {
+        name: "DAILY GAME",
+        description: format!("TODAY: {} SEED {daily_seed}", daily_game.name),
+        icon: &CALENDAR_ICON,
+        screen: Screen::Game {
+            id: daily_game.id,
+            seed: Some(daily_seed),
+        },
+    });
+    items.push(MenuItem {
+        name: "LAST REPLAY",
+        description: String::from("WATCH YOUR LAST GAME AGAIN"),
+        icon: &REPLAY_ICON,
+        screen: Screen::Replay,
+    });
+    items.push(MenuItem {
+        name: "HIGH SCORES",
+        description: String::from("BEST SCORES FOR EVERY GAME"),
+        icon: &TROPHY_ICON,
+        screen: Screen::HighScores,
+    });
+    items.push(MenuItem {
+        name: "CONTROLS",
+        description: String::from("CHANGE KEYS AND BUTTONS"),
+        icon: &CONTROLLER_ICON,
+        screen: Screen::Controls,
+    });
+    items.push(MenuItem {
+        name: "SETTINGS",
+        description: String::from("SOUND, DISPLAY AND INPUT"),
+        icon: &GEAR_ICON,
+        screen: Screen::Settings,
+    });
+    items
+}
 
 pub struct GameMenu {
     title: Text,
     cursor_idx: usize,
+    scroll: usize,
     cursor: Drawable<Triangle>,
-    options: Vec<Text>,
+    options: Vec<MenuItem>,
     frame: ShapeCollection,
     result: GameUpdateResult,
     input_timer: Timer,
     button_bar: ButtonBar,
+    /// Seconds since there was last any input
+    idle: f64,
 }
 
 impl GameMenu {
@@ -35,97 +121,141 @@ impl GameMenu {
             fill(CLR_3),
         );
         let frame = ShapeCollection::default();
-        let options = OPTIONS
-            .iter()
-            .enumerate()
-            .map(|(i, text)| {
-                Text::new(
-                    text,
-                    Px(MENU_X, MENU_START_Y + (i * MENU_STEP) as isize),
-                    (CLR_3, Standard8x10, LeftTop),
-                )
-            })
-            .collect();
 
         Self {
             title,
             cursor_idx: 0,
+            scroll: 0,
             cursor,
             frame,
-            options,
+            options: menu_items(),
             result: Nothing,
-            input_timer: Timer::new(INPUT_DELAY),
+            input_timer: Timer::new(input_delay()),
+            idle: 0.0,
             button_bar: ButtonBar::new(
                 coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
                 SCREEN_WIDTH,
                 &[
-                    ("EXIT", ButtonDef::Escape),
+                    ("EXIT", ButtonDef::Back),
                     ("CURSOR", ButtonDef::Vert),
-                    ("PLAY", ButtonDef::Space),
+                    ("PLAY", ButtonDef::Confirm),
                 ],
             ),
         }
     }
 }
 
+impl GameMenu {
+    /// Keep the cursor within the visible part of the list
+    fn update_scroll(&mut self) {
+        if self.cursor_idx < self.scroll {
+            self.scroll = self.cursor_idx;
+        } else if self.cursor_idx >= self.scroll + VISIBLE_OPTIONS {
+            self.scroll = self.cursor_idx + 1 - VISIBLE_OPTIONS;
+        }
+    }
+}
+
 impl Game for GameMenu {
     fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
         graphics.draw(&self.frame);
         graphics.draw(&self.title);
         graphics.draw(&self.cursor);
-        for (i, option) in self.options.iter().enumerate() {
+        let visible = self
+            .options
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(VISIBLE_OPTIONS);
+        for (row, (i, option)) in visible.enumerate() {
             let color = if self.cursor_idx == i { CLR_3 } else { CLR_2 };
-            graphics.draw(&option.with_color(color));
+            let y = MENU_START_Y + (row * MENU_STEP) as isize;
+            draw_sprite(
+                graphics,
+                coord!(ICON_X, y + 1),
+                option.icon,
+                ICON_SIZE,
+                color,
+            );
+            graphics.draw_text(option.name, Px(MENU_X, y), (color, Standard8x10, LeftTop));
+        }
+        if self.scroll > 0 {
+            graphics.draw_triangle(
+                Triangle::equilateral((SCROLL_ARROW_X, MENU_START_Y + 3), 6, FlatSide::Bottom),
+                fill(CLR_2),
+            );
         }
+        if self.scroll + VISIBLE_OPTIONS < self.options.len() {
+            let y = MENU_START_Y + ((VISIBLE_OPTIONS - 1) * MENU_STEP) as isize;
+            graphics.draw_triangle(
+                Triangle::equilateral((SCROLL_ARROW_X, y + 5), 6, FlatSide::Top),
+                fill(CLR_2),
+            );
+        }
+        graphics.draw_text(
+            &self.options[self.cursor_idx].description,
+            Px(SCREEN_WIDTH as isize / 2, DESCRIPTION_Y),
+            (CLR_1, Standard4x5, CenterBottom),
+        );
         self.button_bar.render(graphics, controller);
     }
 
-    fn on_key_press(&mut self, _: KeyCode) {}
+    fn on_key_press(&mut self, _: KeyCode) {
+        self.idle = 0.0;
+    }
 
-    fn update(
-        &mut self,
-        timing: &Timing,
-        held_keys: &Vec<&KeyCode>,
-        controller: &GameController,
-    ) -> GameUpdateResult {
-        self.cursor = self.cursor.with_move((
-            CURSOR_X,
-            MENU_START_Y + 1 + (self.cursor_idx * MENU_STEP) as isize,
-        ));
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if input.any_held() {
+            self.idle = 0.0;
+        } else {
+            self.idle += timing.fixed_time_step;
+        }
+        if self.idle >= ATTRACT_DELAY {
+            self.idle = 0.0;
+            let demos: Vec<&GameDescriptor> =
+                GAMES.iter().filter(|game| game.demo.is_some()).collect();
+            if !demos.is_empty() {
+                let game = demos[fastrand::usize(..demos.len())];
+                self.result = Push(Screen::Attract { id: game.id });
+                return self.result;
+            }
+        }
 
         if self.input_timer.update(timing) {
-            if held_keys.contains(&&KeyCode::ArrowUp) || controller.direction.up {
+            if input.held(Action::Up) {
                 self.input_timer.reset();
                 if self.cursor_idx == 0 {
                     self.cursor_idx = self.options.len() - 1;
                 } else {
                     self.cursor_idx -= 1;
                 }
-            } else if held_keys.contains(&&KeyCode::ArrowDown) || controller.direction.down {
+            } else if input.held(Action::Down) {
                 self.input_timer.reset();
                 if self.cursor_idx == self.options.len() - 1 {
                     self.cursor_idx = 0;
                 } else {
                     self.cursor_idx += 1;
                 }
-            } else if held_keys.contains(&&KeyCode::Space) || controller.action.south {
+            } else if input.held(Action::Confirm) {
                 self.input_timer.reset();
-                match self.cursor_idx {
-                    0 => self.result = Push(Pong),
-                    1 => self.result = Push(Snake),
-                    // 2 => self.result = Push(Invaders),
-                    _ => {}
-                }
-            } else if held_keys.contains(&&KeyCode::Escape) || controller.action.east {
+                self.result = Push(self.options[self.cursor_idx].screen);
+            } else if input.held(Action::Back) {
                 self.result = Pop
             }
         }
 
+        self.update_scroll();
+        self.cursor = self.cursor.with_move((
+            CURSOR_X,
+            MENU_START_Y + 1 + ((self.cursor_idx - self.scroll) * MENU_STEP) as isize,
+        ));
+
         self.result
     }
 
     fn resuming(&mut self) {
         self.input_timer.reset();
+        self.idle = 0.0;
         self.result = Nothing;
     }
 }
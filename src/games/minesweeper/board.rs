@@ -0,0 +1,301 @@
+use fastrand::Rng;
+use pixels_graphics_lib::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Cover {
+    Hidden,
+    Flagged,
+    Revealed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cell {
+    pub mine: bool,
+    pub cover: Cover,
+    /// Mines in the surrounding eight cells
+    pub adjacent: usize,
+}
+
+/// What happened when cells were revealed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    /// No cells were revealed
+    Nothing,
+    Safe,
+    /// A mine was revealed at this position
+    Exploded(Coord),
+}
+
+/// Minefield, the mines aren't placed until the first reveal so it's always safe
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    cells: Vec<Cell>,
+    placed: bool,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize, mines: usize) -> Self {
+        Self {
+            width,
+            height,
+            mines,
+            cells: vec![
+                Cell {
+                    mine: false,
+                    cover: Cover::Hidden,
+                    adjacent: 0,
+                };
+                width * height
+            ],
+            placed: false,
+        }
+    }
+}
+
+impl Board {
+    pub fn cell(&self, xy: Coord) -> &Cell {
+        &self.cells[self.idx(xy)]
+    }
+
+    fn idx(&self, xy: Coord) -> usize {
+        xy.y as usize * self.width + xy.x as usize
+    }
+
+    fn contains(&self, xy: Coord) -> bool {
+        xy.x >= 0 && xy.y >= 0 && (xy.x as usize) < self.width && (xy.y as usize) < self.height
+    }
+
+    fn neighbours(&self, xy: Coord) -> Vec<Coord> {
+        let mut neighbours = Vec::with_capacity(8);
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let neighbour = xy + (x, y);
+                if (x, y) != (0, 0) && self.contains(neighbour) {
+                    neighbours.push(neighbour);
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// True once the first cell has been revealed
+    pub fn has_started(&self) -> bool {
+        self.placed
+    }
+
+    /// Place the mines anywhere except `safe` and the cells around it, so the first
+    /// reveal always opens an area
+    fn place_mines(&mut self, safe: Coord, rng: &mut Rng) {
+        let mut blocked = self.neighbours(safe);
+        blocked.push(safe);
+        let mut free: Vec<Coord> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| coord!(x, y)))
+            .filter(|xy| !blocked.contains(xy))
+            .collect();
+        for _ in 0..self.mines.min(free.len()) {
+            let xy = free.swap_remove(rng.usize(0..free.len()));
+            let idx = self.idx(xy);
+            self.cells[idx].mine = true;
+        }
+        self.count_adjacent();
+        self.placed = true;
+    }
+
+    fn count_adjacent(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let xy = coord!(x, y);
+                let adjacent = self
+                    .neighbours(xy)
+                    .iter()
+                    .filter(|neighbour| self.cell(**neighbour).mine)
+                    .count();
+                let idx = self.idx(xy);
+                self.cells[idx].adjacent = adjacent;
+            }
+        }
+    }
+
+    /// Reveal the cell at `xy`, if it has no adjacent mines the cells around it are revealed too
+    ///
+    /// Flagged and revealed cells are ignored
+    pub fn reveal(&mut self, xy: Coord, rng: &mut Rng) -> Outcome {
+        if self.cell(xy).cover != Cover::Hidden {
+            return Outcome::Nothing;
+        }
+        if !self.placed {
+            self.place_mines(xy, rng);
+        }
+        let mut queue = VecDeque::from([xy]);
+        while let Some(xy) = queue.pop_front() {
+            let idx = self.idx(xy);
+            if self.cells[idx].cover != Cover::Hidden {
+                continue;
+            }
+            self.cells[idx].cover = Cover::Revealed;
+            if self.cells[idx].mine {
+                return Outcome::Exploded(xy);
+            }
+            if self.cells[idx].adjacent == 0 {
+                queue.extend(self.neighbours(xy));
+            }
+        }
+        Outcome::Safe
+    }
+
+    /// If the revealed number at `xy` has that many flags around it, reveal the rest of the
+    /// cells around it
+    pub fn chord(&mut self, xy: Coord, rng: &mut Rng) -> Outcome {
+        let cell = *self.cell(xy);
+        if cell.cover != Cover::Revealed || cell.adjacent == 0 {
+            return Outcome::Nothing;
+        }
+        let neighbours = self.neighbours(xy);
+        let flags = neighbours
+            .iter()
+            .filter(|neighbour| self.cell(**neighbour).cover == Cover::Flagged)
+            .count();
+        if flags != cell.adjacent {
+            return Outcome::Nothing;
+        }
+        let mut outcome = Outcome::Nothing;
+        for neighbour in neighbours {
+            match self.reveal(neighbour, rng) {
+                Outcome::Nothing => {}
+                Outcome::Safe => {
+                    if outcome == Outcome::Nothing {
+                        outcome = Outcome::Safe;
+                    }
+                }
+                exploded => outcome = exploded,
+            }
+        }
+        outcome
+    }
+
+    /// Flag or unflag the hidden cell at `xy`, returns false if it's already revealed
+    pub fn toggle_flag(&mut self, xy: Coord) -> bool {
+        let idx = self.idx(xy);
+        self.cells[idx].cover = match self.cells[idx].cover {
+            Cover::Hidden => Cover::Flagged,
+            Cover::Flagged => Cover::Hidden,
+            Cover::Revealed => return false,
+        };
+        true
+    }
+
+    pub fn flags(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| cell.cover == Cover::Flagged)
+            .count()
+    }
+
+    /// True if every cell without a mine has been revealed
+    pub fn is_cleared(&self) -> bool {
+        self.placed
+            && self
+                .cells
+                .iter()
+                .all(|cell| cell.mine || cell.cover == Cover::Revealed)
+    }
+
+    /// Flag every mine, used once the board is cleared
+    pub fn flag_mines(&mut self) {
+        for cell in &mut self.cells {
+            if cell.mine {
+                cell.cover = Cover::Flagged;
+            }
+        }
+    }
+
+    /// Reveal every mine that isn't flagged, used once a mine has exploded
+    pub fn reveal_mines(&mut self) {
+        for cell in &mut self.cells {
+            if cell.mine && cell.cover == Cover::Hidden {
+                cell.cover = Cover::Revealed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Board with mines at `mines`, as if they had been placed by the first reveal
+    fn mined(width: usize, height: usize, mines: &[(usize, usize)]) -> Board {
+        let mut board = Board::new(width, height, mines.len());
+        for (x, y) in mines {
+            let idx = board.idx(coord!(*x, *y));
+            board.cells[idx].mine = true;
+        }
+        board.count_adjacent();
+        board.placed = true;
+        board
+    }
+
+    #[test]
+    fn first_reveal_is_safe() {
+        for seed in 0..20 {
+            let mut rng = Rng::with_seed(seed);
+            let mut board = Board::new(9, 9, 10);
+            let xy = coord!(rng.usize(0..9), rng.usize(0..9));
+            assert_eq!(board.reveal(xy, &mut rng), Outcome::Safe);
+            assert_eq!(board.cell(xy).adjacent, 0);
+            assert_eq!(board.cells.iter().filter(|cell| cell.mine).count(), 10);
+        }
+    }
+
+    #[test]
+    fn reveal_opens_empty_areas() {
+        let mut rng = Rng::with_seed(1);
+        let mut board = mined(5, 5, &[(4, 4)]);
+        assert_eq!(board.reveal(coord!(0, 0), &mut rng), Outcome::Safe);
+        assert_eq!(board.cell(coord!(4, 3)).cover, Cover::Revealed);
+        assert_eq!(board.cell(coord!(4, 3)).adjacent, 1);
+        assert_eq!(board.cell(coord!(4, 4)).cover, Cover::Hidden);
+        assert!(board.is_cleared());
+    }
+
+    #[test]
+    fn flags_block_reveals() {
+        let mut rng = Rng::with_seed(1);
+        let mut board = mined(3, 3, &[(1, 1)]);
+        assert!(board.toggle_flag(coord!(1, 1)));
+        assert_eq!(board.flags(), 1);
+        assert_eq!(board.reveal(coord!(1, 1), &mut rng), Outcome::Nothing);
+        assert!(board.toggle_flag(coord!(1, 1)));
+        assert_eq!(board.flags(), 0);
+        assert_eq!(
+            board.reveal(coord!(1, 1), &mut rng),
+            Outcome::Exploded(coord!(1, 1))
+        );
+        assert!(!board.toggle_flag(coord!(1, 1)));
+    }
+
+    #[test]
+    fn chord_needs_matching_flags() {
+        let mut rng = Rng::with_seed(1);
+        let mut board = mined(3, 2, &[(0, 0), (2, 0)]);
+        board.reveal(coord!(1, 0), &mut rng);
+        assert_eq!(board.chord(coord!(1, 0), &mut rng), Outcome::Nothing);
+        board.toggle_flag(coord!(0, 0));
+        board.toggle_flag(coord!(2, 0));
+        assert_eq!(board.chord(coord!(1, 0), &mut rng), Outcome::Safe);
+        assert!(board.is_cleared());
+
+        let mut board = mined(3, 2, &[(0, 0), (2, 0)]);
+        board.reveal(coord!(1, 0), &mut rng);
+        board.toggle_flag(coord!(0, 0));
+        board.toggle_flag(coord!(1, 1));
+        assert_eq!(
+            board.chord(coord!(1, 0), &mut rng),
+            Outcome::Exploded(coord!(2, 0))
+        );
+    }
+}
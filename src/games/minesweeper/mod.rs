@@ -0,0 +1,635 @@
+mod board;
+
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::games::minesweeper::board::{Board, Cover, Outcome};
+use crate::games::minesweeper::Difficulty::*;
+use crate::games::minesweeper::State::*;
+use crate::games::GAME_INPUT_DELAY;
+use crate::high_scores::{submit_score, Ranking};
+use crate::input::{Action, Input};
+use crate::sprite::draw_sprite;
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::{
+    Center, CenterTop, LeftTop, RightTop,
+};
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+pub const ID: &str = "minesweeper";
+/// Best time tables for each [Difficulty]
+pub const EASY_ID: &str = "minesweeper_easy";
+pub const MEDIUM_ID: &str = "minesweeper_medium";
+pub const HARD_ID: &str = "minesweeper_hard";
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+//the board is centered in the play area below this
+const HUD_HEIGHT: usize = 14;
+//seconds a direction is held before the cursor repeats
+const CURSOR_DELAY: f64 = 0.25;
+const CURSOR_RATE: f64 = 0.06;
+//the timer stops counting here
+pub const MAX_SECONDS: usize = 999;
+const SPRITE_SIZE: usize = 5;
+const FLAG: [u8; SPRITE_SIZE] = [0b00110, 0b01110, 0b00010, 0b00010, 0b00111];
+const MINE: [u8; SPRITE_SIZE] = [0b10101, 0b01110, 0b11111, 0b01110, 0b10101];
+const SETUP_TITLE_Y: isize = 16;
+const SETUP_Y: isize = 44;
+const SETUP_STEP: isize = 16;
+const SETUP_DESC_Y: isize = 110;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Choosing the [Difficulty]
+    Setup,
+    Playing,
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 3] = [Easy, Medium, Hard];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Easy => "EASY",
+            Medium => "MEDIUM",
+            Hard => "HARD",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Easy => "9X9, 10 MINES",
+            Medium => "16X16, 40 MINES",
+            Hard => "20X16, 64 MINES",
+        }
+    }
+
+    /// Columns, rows and mines, the board has to fit in the play area below the HUD
+    fn size(&self) -> (usize, usize, usize) {
+        match self {
+            Easy => (9, 9, 10),
+            Medium => (16, 16, 40),
+            Hard => (20, 16, 64),
+        }
+    }
+
+    /// Pixels per cell, including the gap between cells
+    fn cell_size(&self) -> usize {
+        match self {
+            Easy => 12,
+            Medium | Hard => 8,
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Easy => EASY_ID,
+            Medium => MEDIUM_ID,
+            Hard => HARD_ID,
+        }
+    }
+}
+
+pub struct Minesweeper {
+    board: Board,
+    difficulty: Difficulty,
+    /// Index into [Difficulty::ALL] while in [State::Setup]
+    setup_cursor: usize,
+    cursor: Coord,
+    move_direction: Coord,
+    next_move: f64,
+    /// Seconds since the first reveal
+    time: f64,
+    /// Mine that ended the game
+    exploded: Option<Coord>,
+    state: State,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+    rng: Rng,
+}
+
+impl Minesweeper {
+    pub fn new(seed: u64) -> Box<Self> {
+        let mut minesweeper = Box::new(Self {
+            board: Board::new(0, 0, 0),
+            difficulty: Easy,
+            setup_cursor: 0,
+            cursor: Coord::default(),
+            move_direction: Coord::default(),
+            next_move: 0.0,
+            time: 0.0,
+            exploded: None,
+            state: Setup,
+            result: Nothing,
+            input_timer: Timer::new(GAME_INPUT_DELAY),
+            button_bar: ButtonBar::new_blank(coord!(0, PLAY_HEIGHT), SCREEN_WIDTH),
+            rng: Rng::with_seed(seed),
+        });
+        minesweeper.setup();
+        minesweeper
+    }
+}
+
+impl Minesweeper {
+    /// Back to choosing the difficulty, the last one played is selected
+    fn setup(&mut self) {
+        self.state = Setup;
+        self.setup_cursor = Difficulty::ALL
+            .iter()
+            .position(|difficulty| *difficulty == self.difficulty)
+            .unwrap_or_default();
+        self.button_bar.set_buttons(&[
+            ("PAUSE", ButtonDef::Back),
+            ("SIZE", ButtonDef::Vert),
+            ("START", ButtonDef::Confirm),
+        ]);
+    }
+
+    fn start(&mut self, difficulty: Difficulty) {
+        let (width, height, mines) = difficulty.size();
+        self.difficulty = difficulty;
+        self.board = Board::new(width, height, mines);
+        self.cursor = coord!(width / 2, height / 2);
+        self.move_direction = Coord::default();
+        self.time = 0.0;
+        self.exploded = None;
+        self.state = Playing;
+        self.button_bar.set_buttons(&[
+            ("MOVE", ButtonDef::Cursor),
+            ("OPEN", ButtonDef::Confirm),
+            ("FLAG", ButtonDef::Secondary),
+        ]);
+    }
+
+    /// Whole seconds shown on the timer, and submitted as the score
+    fn seconds(&self) -> usize {
+        (self.time.ceil() as usize).min(MAX_SECONDS)
+    }
+
+    /// Top left of the board
+    fn board_pos(&self) -> Coord {
+        let cell_size = self.difficulty.cell_size();
+        coord!(
+            (SCREEN_WIDTH - self.board.width * cell_size) / 2,
+            HUD_HEIGHT + (PLAY_HEIGHT - HUD_HEIGHT - self.board.height * cell_size) / 2
+        )
+    }
+
+    fn update_setup(&mut self, timing: &Timing, input: &Input) {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                self.setup_cursor =
+                    (self.setup_cursor + Difficulty::ALL.len() - 1) % Difficulty::ALL.len();
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                self.setup_cursor = (self.setup_cursor + 1) % Difficulty::ALL.len();
+            } else if input.pressed(Action::Confirm) {
+                self.start(Difficulty::ALL[self.setup_cursor]);
+            }
+        }
+    }
+
+    /// Moves the cursor once when a direction is pressed, then repeatedly while it's held
+    fn update_cursor(&mut self, timing: &Timing, input: &Input) {
+        let mut direction = Coord::default();
+        for (action, delta) in [
+            (Action::Up, (0, -1)),
+            (Action::Down, (0, 1)),
+            (Action::Left, (-1, 0)),
+            (Action::Right, (1, 0)),
+        ] {
+            if input.held(action) {
+                direction = direction + delta;
+            }
+        }
+        if direction != self.move_direction {
+            self.move_direction = direction;
+            if direction != Coord::default() {
+                self.move_cursor(direction);
+                self.next_move = CURSOR_DELAY;
+            }
+        } else if direction != Coord::default() {
+            self.next_move -= timing.fixed_time_step;
+            while self.next_move <= 0.0 {
+                self.move_cursor(direction);
+                self.next_move += CURSOR_RATE;
+            }
+        }
+    }
+
+    /// Move the cursor by `direction`, wrapping around the edges of the board
+    fn move_cursor(&mut self, direction: Coord) {
+        let cursor = self.cursor + direction;
+        self.cursor = coord!(
+            cursor.x.rem_euclid(self.board.width as isize),
+            cursor.y.rem_euclid(self.board.height as isize)
+        );
+    }
+
+    fn update_playing(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) {
+        if self.board.has_started() {
+            self.time += timing.fixed_time_step;
+        }
+        self.update_cursor(timing, input);
+
+        if input.pressed(Action::Confirm) {
+            let outcome = if self.board.cell(self.cursor).cover == Cover::Revealed {
+                self.board.chord(self.cursor, &mut self.rng)
+            } else {
+                self.board.reveal(self.cursor, &mut self.rng)
+            };
+            match outcome {
+                Outcome::Nothing => {}
+                Outcome::Safe => {
+                    if self.board.is_cleared() {
+                        self.win(audio);
+                    } else {
+                        audio.play(Sound::Paddle);
+                    }
+                }
+                Outcome::Exploded(xy) => self.lose(xy, audio),
+            }
+        } else if input.pressed(Action::Secondary) && self.board.toggle_flag(self.cursor) {
+            audio.play(Sound::Wall);
+        }
+    }
+
+    fn win(&mut self, audio: &mut Audio) {
+        audio.play(Sound::Bonus);
+        self.board.flag_mines();
+        self.finish(Won);
+        if self.result == Nothing {
+            self.result = submit_score(self.difficulty.table(), self.seconds());
+        }
+    }
+
+    fn lose(&mut self, mine: Coord, audio: &mut Audio) {
+        audio.play(Sound::Death);
+        self.exploded = Some(mine);
+        self.board.reveal_mines();
+        self.finish(Lost);
+    }
+
+    fn finish(&mut self, state: State) {
+        self.state = state;
+        self.button_bar
+            .set_buttons(&[("PAUSE", ButtonDef::Back), ("NEW GAME", ButtonDef::Confirm)]);
+    }
+
+    fn render_board(&self, graphics: &mut Graphics) {
+        let cell_size = self.difficulty.cell_size();
+        let board_pos = self.board_pos();
+        //cells are drawn one pixel smaller than their size to leave a gap
+        let inner = cell_size - 1;
+        let sprite_offset = (inner - SPRITE_SIZE) / 2;
+        for y in 0..self.board.height {
+            for x in 0..self.board.width {
+                let xy = coord!(x, y);
+                let pos = board_pos + xy * (cell_size, cell_size);
+                let rect = Rect::new_with_size(pos, inner - 1, inner - 1);
+                let cell = self.board.cell(xy);
+                match cell.cover {
+                    Cover::Hidden => graphics.draw_rect(rect, fill(CLR_1)),
+                    Cover::Flagged => {
+                        graphics.draw_rect(rect.clone(), fill(CLR_1));
+                        draw_sprite(
+                            graphics,
+                            pos + (sprite_offset, sprite_offset),
+                            &FLAG,
+                            SPRITE_SIZE,
+                            CLR_3,
+                        );
+                        if self.state == Lost && !cell.mine {
+                            graphics.draw_line(rect.top_left(), rect.bottom_right(), CLR_2);
+                            graphics.draw_line(rect.bottom_left(), rect.top_right(), CLR_2);
+                        }
+                    }
+                    Cover::Revealed if cell.mine => {
+                        let color = if self.exploded == Some(xy) {
+                            graphics.draw_rect(rect, fill(CLR_3));
+                            CLR_0
+                        } else {
+                            CLR_3
+                        };
+                        draw_sprite(
+                            graphics,
+                            pos + (sprite_offset, sprite_offset),
+                            &MINE,
+                            SPRITE_SIZE,
+                            color,
+                        );
+                    }
+                    Cover::Revealed => {
+                        if cell.adjacent > 0 {
+                            let color = if cell.adjacent == 1 { CLR_2 } else { CLR_3 };
+                            graphics.draw_text(
+                                &cell.adjacent.to_string(),
+                                TextPos::px(pos + (inner / 2, inner / 2)),
+                                (color, Standard4x5, Center),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        if self.state == Playing {
+            let pos = board_pos + self.cursor * (cell_size, cell_size);
+            graphics.draw_rect(
+                Rect::new_with_size(pos - (1, 1), inner, inner),
+                stroke(CLR_3),
+            );
+        }
+    }
+
+    fn render_hud(&self, graphics: &mut Graphics) {
+        match self.state {
+            Won => graphics.draw_text(
+                &format!("CLEARED IN {}", Ranking::Time.format(self.seconds())),
+                TextPos::Px(SCREEN_WIDTH as isize / 2, 3),
+                (CLR_3, Standard8x10, CenterTop),
+            ),
+            Lost => graphics.draw_text(
+                "GAME OVER",
+                TextPos::Px(SCREEN_WIDTH as isize / 2, 3),
+                (CLR_3, Standard8x10, CenterTop),
+            ),
+            Setup | Playing => {
+                let mines = self.board.mines as isize - self.board.flags() as isize;
+                graphics.draw_text(
+                    &format!("MINES {mines}"),
+                    TextPos::Px(3, 5),
+                    (CLR_2, Standard4x5, LeftTop),
+                );
+                graphics.draw_text(
+                    &format!("TIME {}", self.seconds()),
+                    TextPos::Px(SCREEN_WIDTH as isize - 3, 5),
+                    (CLR_2, Standard4x5, RightTop),
+                );
+            }
+        }
+    }
+
+    fn render_setup(&self, graphics: &mut Graphics) {
+        let x1 = 15;
+        let y1 = 8;
+        let x2 = 144;
+        let y2 = 126;
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+        graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+        graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+        graphics.draw_text(
+            "MINESWEEPER",
+            TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_TITLE_Y),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+        for (i, difficulty) in Difficulty::ALL.iter().enumerate() {
+            let (text, color) = if self.setup_cursor == i {
+                (format!("< {} >", difficulty.name()), CLR_3)
+            } else {
+                (difficulty.name().to_string(), CLR_1)
+            };
+            graphics.draw_text(
+                &text,
+                TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_Y + i as isize * SETUP_STEP),
+                (color, Standard8x10, CenterTop),
+            );
+        }
+        graphics.draw_text(
+            Difficulty::ALL[self.setup_cursor].description(),
+            TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_DESC_Y),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+    }
+}
+
+impl Game for Minesweeper {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        if self.state == Setup {
+            self.render_setup(graphics);
+        } else {
+            self.render_board(graphics);
+            self.render_hud(graphics);
+        }
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        match self.state {
+            Setup => self.update_setup(timing, input),
+            Playing => self.update_playing(timing, input, audio),
+            Won | Lost => {
+                if input.pressed(Action::Confirm) {
+                    self.setup();
+                }
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn minesweeper(difficulty: Difficulty) -> Harness<Minesweeper> {
+        let mut harness = Harness::new(Minesweeper::new(1));
+        harness.game.start(difficulty);
+        harness
+    }
+
+    /// Every cell on the board, mines first if `mines` is true otherwise the safe cells
+    fn cells(board: &Board, mines: bool) -> Vec<Coord> {
+        (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| coord!(x, y)))
+            .filter(|xy| board.cell(*xy).mine == mines)
+            .collect()
+    }
+
+    #[test]
+    fn hud_counters_dont_touch() {
+        for difficulty in Difficulty::ALL {
+            //every cell flagged at the longest time
+            let (width, height, mines) = difficulty.size();
+            let mines = format!("MINES {}", mines as isize - (width * height) as isize);
+            let time = format!("TIME {MAX_SECONDS}");
+            let gap =
+                SCREEN_WIDTH - 6 - Standard4x5.measure(&mines).0 - Standard4x5.measure(&time).0;
+            assert!(gap >= Standard4x5.char_width(), "{mines} and {time} touch");
+        }
+    }
+
+    #[test]
+    fn setup() {
+        let mut harness = Harness::new(Minesweeper::new(1));
+        harness.tick();
+        harness.assert_snapshot("minesweeper_setup");
+        //skip the input delays
+        harness.game.input_timer = Timer::new(GAME_INPUT_DELAY);
+        harness.press(Action::Down);
+        harness.game.input_timer = Timer::new(GAME_INPUT_DELAY);
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.difficulty, Medium);
+        assert_eq!(harness.game.board.width, 16);
+    }
+
+    #[test]
+    fn boards_fit_play_area() {
+        for difficulty in Difficulty::ALL {
+            let (width, height, mines) = difficulty.size();
+            assert!(width * difficulty.cell_size() <= SCREEN_WIDTH);
+            assert!(height * difficulty.cell_size() <= PLAY_HEIGHT - HUD_HEIGHT);
+            //first reveal needs a mine free 3x3
+            assert!(mines <= width * height - 9);
+        }
+    }
+
+    #[test]
+    fn cursor_repeats_and_wraps() {
+        let mut harness = minesweeper(Easy);
+        assert_eq!(harness.game.cursor, coord!(4, 4));
+        harness.hold(Action::Right);
+        harness.tick();
+        assert_eq!(harness.game.cursor, coord!(5, 4));
+        harness.run(CURSOR_DELAY - 0.05);
+        assert_eq!(harness.game.cursor, coord!(5, 4));
+        harness.run(0.05 + CURSOR_RATE * 3.0 + 0.01);
+        assert_eq!(harness.game.cursor, coord!(0, 4));
+        harness.release(Action::Right);
+        harness.tick();
+        harness.press_and_release(Action::Up);
+        harness.press_and_release(Action::Left);
+        assert_eq!(harness.game.cursor, coord!(8, 3));
+    }
+
+    #[test]
+    fn first_reveal_is_safe_and_starts_timer() {
+        let mut harness = minesweeper(Hard);
+        assert!(!harness.game.board.has_started());
+        harness.run(1.0);
+        assert_eq!(harness.game.seconds(), 0);
+        harness.press_and_release(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(
+            harness.game.board.cell(harness.game.cursor).cover,
+            Cover::Revealed
+        );
+        harness.run(1.5);
+        assert_eq!(harness.game.seconds(), 2);
+    }
+
+    #[test]
+    fn flags_and_chords() {
+        let mut harness = minesweeper(Easy);
+        harness.press_and_release(Action::Confirm);
+        let board = harness.game.board.clone();
+        //a revealed number next to a mine
+        let (number, mine) = cells(&board, true)
+            .into_iter()
+            .find_map(|mine| {
+                cells(&board, false)
+                    .into_iter()
+                    .find(|xy| {
+                        board.cell(*xy).cover == Cover::Revealed
+                            && board.cell(*xy).adjacent == 1
+                            && (xy.x - mine.x).abs() <= 1
+                            && (xy.y - mine.y).abs() <= 1
+                    })
+                    .map(|xy| (xy, mine))
+            })
+            .unwrap();
+
+        harness.game.cursor = mine;
+        harness.press_and_release(Action::Secondary);
+        assert_eq!(harness.game.board.cell(mine).cover, Cover::Flagged);
+        assert_eq!(harness.game.board.flags(), 1);
+        //flagged cells can't be opened
+        harness.press_and_release(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+
+        harness.game.cursor = number;
+        harness.press_and_release(Action::Confirm);
+        assert_ne!(harness.game.state, Lost);
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let xy = number + (x, y);
+                if (0..9).contains(&xy.x) && (0..9).contains(&xy.y) {
+                    assert_ne!(harness.game.board.cell(xy).cover, Cover::Hidden);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hitting_a_mine_loses() {
+        let mut harness = minesweeper(Easy);
+        harness.press_and_release(Action::Confirm);
+        let mine = cells(&harness.game.board, true)[0];
+        harness.game.cursor = mine;
+        harness.press_and_release(Action::Confirm);
+        assert_eq!(harness.game.state, Lost);
+        assert_eq!(harness.game.exploded, Some(mine));
+        assert!(cells(&harness.game.board, true).iter().all(|xy| harness
+            .game
+            .board
+            .cell(*xy)
+            .cover
+            == Cover::Revealed));
+        harness.assert_snapshot("minesweeper_lost");
+        harness.press_and_release(Action::Confirm);
+        assert_eq!(harness.game.state, Setup);
+        assert_eq!(harness.game.setup_cursor, 0);
+    }
+
+    #[test]
+    fn clearing_the_board_wins() {
+        let mut harness = minesweeper(Easy);
+        harness.press_and_release(Action::Confirm);
+        for xy in cells(&harness.game.board, false) {
+            if harness.game.board.cell(xy).cover == Cover::Hidden {
+                harness.game.cursor = xy;
+                harness.press_and_release(Action::Confirm);
+            }
+        }
+        assert_eq!(harness.game.state, Won);
+        assert_eq!(
+            harness.game.board.flags(),
+            harness.game.board.mines,
+            "mines are flagged"
+        );
+    }
+
+    #[test]
+    fn playing() {
+        let mut harness = minesweeper(Medium);
+        harness.press_and_release(Action::Confirm);
+        let mine = cells(&harness.game.board, true)[0];
+        harness.game.cursor = mine;
+        harness.press_and_release(Action::Secondary);
+        harness.assert_snapshot("minesweeper_playing");
+    }
+}
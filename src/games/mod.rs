@@ -1,3 +1,243 @@
+use crate::date::{civil_from_days, days_since_epoch};
+use crate::games::asteroids::Asteroids;
+use crate::games::breakout::Breakout;
+use crate::games::invaders::Invaders;
+use crate::games::minesweeper::Minesweeper;
+use crate::games::pong::Pong;
+use crate::games::snake::Snake;
+use crate::games::tetris::Tetris;
+use crate::high_scores::{Ranking, ScoreTable};
+use crate::settings::DEFAULT_SETTINGS;
+use crate::Game;
+
+pub mod asteroids;
+pub mod attract;
+pub mod ball;
+pub mod breakout;
+pub mod controls;
+pub mod enter_initials;
+pub mod high_score_table;
+pub mod invaders;
 pub mod menu;
+pub mod minesweeper;
+pub mod pause;
 pub mod pong;
+pub mod replay;
+pub mod settings;
 pub mod snake;
+pub mod tetris;
+
+pub const ICON_SIZE: usize = 8;
+/// Seconds between repeats when holding a direction in a game's own setup and game over menus,
+/// fixed rather than the [input_delay][crate::settings::input_delay] setting so replays play back the same
+pub const GAME_INPUT_DELAY: f64 = DEFAULT_SETTINGS.input_delay;
+
+/// Everything the host and menu need to know about a game
+///
+/// To add a game implement [Game] for it and add an entry to [GAMES]
+pub struct GameDescriptor {
+    /// Unique, stable name for the game, used to launch it
+    pub id: &'static str,
+    /// Shown in the menu
+    pub name: &'static str,
+    /// Shown in the menu when the game is selected
+    pub description: &'static str,
+    /// 8x8 1 bit sprite, see [draw_sprite][crate::sprite::draw_sprite]
+    pub icon: [u8; ICON_SIZE],
+    /// Takes the seed for the game's random number generator
+    pub constructor: fn(u64) -> Box<dyn Game>,
+    /// Starts the game playing itself for the menu's attract mode, if it can
+    pub demo: Option<fn(u64) -> Box<dyn Game>>,
+    /// Shown in the high score screen in this order
+    pub score_tables: &'static [ScoreTable],
+}
+
+pub const GAMES: &[GameDescriptor] = &[
+    GameDescriptor {
+        id: pong::ID,
+        name: "PONG",
+        description: "KEEP THE BALL IN PLAY",
+        icon: [
+            0b00000000, 0b10000000, 0b10000001, 0b10011001, 0b10011001, 0b10000001, 0b00000001,
+            0b00000000,
+        ],
+        constructor: |seed| Pong::new(seed),
+        demo: Some(|seed| Pong::demo(seed)),
+        score_tables: &[ScoreTable {
+            id: pong::ID,
+            name: "PONG",
+            ranking: Ranking::Points,
+        }],
+    },
+    GameDescriptor {
+        id: snake::ID,
+        name: "SNAKE",
+        description: "EAT FRUIT, DON'T HIT ANYTHING",
+        icon: [
+            0b00000110, 0b00000110, 0b00000000, 0b01111100, 0b01000100, 0b01000100, 0b11000111,
+            0b00000000,
+        ],
+        constructor: |seed| Snake::new(seed),
+        demo: Some(|seed| Snake::demo(seed)),
+        score_tables: &[
+            ScoreTable {
+                id: snake::ID,
+                name: "SNAKE",
+                ranking: Ranking::Points,
+            },
+            ScoreTable {
+                id: snake::WRAP_ID,
+                name: "SNAKE WRAP",
+                ranking: Ranking::Points,
+            },
+            ScoreTable {
+                id: snake::TIMED_ID,
+                name: "SNAKE TIMED",
+                ranking: Ranking::Points,
+            },
+            ScoreTable {
+                id: snake::ZEN_ID,
+                name: "SNAKE ZEN",
+                ranking: Ranking::Points,
+            },
+        ],
+    },
+    GameDescriptor {
+        id: invaders::ID,
+        name: "INVADERS",
+        description: "DEFEND AGAINST THE ALIEN WAVES",
+        icon: [
+            0b00011000, 0b00111100, 0b01111110, 0b11011011, 0b11111111, 0b00100100, 0b01011010,
+            0b10100101,
+        ],
+        constructor: |seed| Invaders::new(seed),
+        demo: None,
+        score_tables: &[ScoreTable {
+            id: invaders::ID,
+            name: "INVADERS",
+            ranking: Ranking::Points,
+        }],
+    },
+    GameDescriptor {
+        id: breakout::ID,
+        name: "BREAKOUT",
+        description: "BREAK EVERY BRICK",
+        icon: [
+            0b11101110, 0b00000000, 0b01110111, 0b00000000, 0b00000000, 0b00010000, 0b00000000,
+            0b00111100,
+        ],
+        constructor: |seed| Breakout::new(seed),
+        demo: None,
+        score_tables: &[ScoreTable {
+            id: breakout::ID,
+            name: "BREAKOUT",
+            ranking: Ranking::Points,
+        }],
+    },
+    GameDescriptor {
+        id: tetris::ID,
+        name: "TETRIS",
+        description: "CLEAR LINES WITH FALLING BLOCKS",
+        icon: [
+            0b00000000, 0b00011000, 0b00011000, 0b00011000, 0b00000000, 0b11000011, 0b11100111,
+            0b11111111,
+        ],
+        constructor: |seed| Tetris::new(seed),
+        demo: None,
+        score_tables: &[ScoreTable {
+            id: tetris::ID,
+            name: "TETRIS",
+            ranking: Ranking::Points,
+        }],
+    },
+    GameDescriptor {
+        id: asteroids::ID,
+        name: "ASTEROIDS",
+        description: "SHOOT THE ROCKS, DODGE THE UFOS",
+        icon: [
+            0b00010000, 0b00010000, 0b00101000, 0b00101000, 0b01000100, 0b01010100, 0b10101010,
+            0b11000110,
+        ],
+        constructor: |seed| Asteroids::new(seed),
+        demo: None,
+        score_tables: &[ScoreTable {
+            id: asteroids::ID,
+            name: "ASTEROIDS",
+            ranking: Ranking::Points,
+        }],
+    },
+    GameDescriptor {
+        id: minesweeper::ID,
+        name: "MINESWEEPER",
+        description: "CLEAR THE MINEFIELD",
+        icon: [
+            0b00010000, 0b01010100, 0b00111000, 0b11111110, 0b00111000, 0b01010100, 0b00010000,
+            0b00000000,
+        ],
+        constructor: |seed| Minesweeper::new(seed),
+        demo: None,
+        score_tables: &[
+            ScoreTable {
+                id: minesweeper::EASY_ID,
+                name: "MINES EASY",
+                ranking: Ranking::Time,
+            },
+            ScoreTable {
+                id: minesweeper::MEDIUM_ID,
+                name: "MINES MEDIUM",
+                ranking: Ranking::Time,
+            },
+            ScoreTable {
+                id: minesweeper::HARD_ID,
+                name: "MINES HARD",
+                ranking: Ranking::Time,
+            },
+        ],
+    },
+];
+
+pub fn find_game(id: &str) -> Option<&'static GameDescriptor> {
+    GAMES.iter().find(|game| game.id == id)
+}
+
+/// Every high score table, in the order they're shown
+pub fn score_tables() -> Vec<&'static ScoreTable> {
+    GAMES.iter().flat_map(|game| game.score_tables).collect()
+}
+
+pub fn find_score_table(id: &str) -> Option<&'static ScoreTable> {
+    score_tables().into_iter().find(|table| table.id == id)
+}
+
+/// Seed for a new game when one hasn't been picked
+pub fn random_seed() -> u64 {
+    fastrand::u32(..) as u64
+}
+
+/// Today's game and seed, the seed is the date as YYYYMMDD
+pub fn daily_challenge() -> (&'static GameDescriptor, u64) {
+    let days = days_since_epoch();
+    let (y, m, d) = civil_from_days(days);
+    let seed = (y * 10000 + m * 100 + d) as u64;
+    (&GAMES[days.rem_euclid(GAMES.len() as i64) as usize], seed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SCREEN_WIDTH;
+    use pixels_graphics_lib::prelude::font::standard_4x5;
+
+    #[test]
+    fn descriptions_fit_screen() {
+        //the menu draws descriptions centred in Standard4x5, with a pixel between letters
+        for game in GAMES {
+            let width = game.description.len() * (standard_4x5::CHAR_WIDTH + 1) - 1;
+            assert!(
+                width <= SCREEN_WIDTH,
+                "{} description is {width}px wide",
+                game.id
+            );
+        }
+    }
+}
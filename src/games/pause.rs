@@ -0,0 +1,158 @@
+use crate::audio::Audio;
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::input::{Action, Input};
+use crate::settings::input_delay;
+use crate::GameUpdateResult::{Nothing, Pop, Push, Quit, Restart};
+use crate::{
+    Game, GameUpdateResult, Screen, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{CenterTop, LeftTop};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::font::standard_8x10;
+use pixels_graphics_lib::prelude::PixelFont::Standard8x10;
+use pixels_graphics_lib::prelude::*;
+
+const TITLE: &str = "PAUSED";
+const OPTIONS: [(&str, GameUpdateResult); 4] = [
+    ("RESUME", Pop),
+    ("RESTART", Restart),
+    ("SETTINGS", Push(Screen::Settings)),
+    ("QUIT", Quit),
+];
+const PANEL: (isize, isize, isize, isize) = (20, 30, 140, 118);
+const CURSOR_X: isize = PANEL.0 + 10;
+const MENU_X: isize = CURSOR_X + 12;
+const MENU_START_Y: isize = PANEL.1 + 24;
+const MENU_STEP: usize = standard_8x10::CHAR_HEIGHT + 4;
+//stops the key that opened the menu from also closing it
+const START_DELAY: f64 = 0.3;
+
+/// Shown over a paused game, see [GameHost][crate::GameHost]
+pub struct PauseMenu {
+    cursor_idx: usize,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+}
+
+impl PauseMenu {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            cursor_idx: 0,
+            result: Nothing,
+            input_timer: Timer::new_with_delay(START_DELAY, input_delay()),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("RESUME", ButtonDef::Back),
+                    ("CURSOR", ButtonDef::Vert),
+                    ("SELECT", ButtonDef::Confirm),
+                ],
+            ),
+        })
+    }
+}
+
+impl Game for PauseMenu {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        let (x1, y1, x2, y2) = PANEL;
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+        graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+        graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+        graphics.draw_text(
+            TITLE,
+            Px((x1 + x2) / 2, y1 + 6),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+
+        for (i, (name, _)) in OPTIONS.iter().enumerate() {
+            let color = if self.cursor_idx == i { CLR_3 } else { CLR_2 };
+            let y = MENU_START_Y + (i * MENU_STEP) as isize;
+            graphics.draw_text(name, Px(MENU_X, y), (color, Standard8x10, LeftTop));
+        }
+        graphics.draw_triangle(
+            Triangle::equilateral(
+                (
+                    CURSOR_X + 3,
+                    MENU_START_Y + 4 + (self.cursor_idx * MENU_STEP) as isize,
+                ),
+                6,
+                FlatSide::Left,
+            ),
+            fill(CLR_3),
+        );
+
+        graphics.draw_rect(
+            Rect::new(
+                (0, SCREEN_HEIGHT - BAR_HEIGHT),
+                (SCREEN_WIDTH, SCREEN_HEIGHT),
+            ),
+            fill(CLR_0),
+        );
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, _: &mut Audio) -> GameUpdateResult {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                if self.cursor_idx == 0 {
+                    self.cursor_idx = OPTIONS.len() - 1;
+                } else {
+                    self.cursor_idx -= 1;
+                }
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                if self.cursor_idx == OPTIONS.len() - 1 {
+                    self.cursor_idx = 0;
+                } else {
+                    self.cursor_idx += 1;
+                }
+            } else if input.held(Action::Confirm) {
+                self.result = OPTIONS[self.cursor_idx].1;
+            } else if input.held(Action::Back) || input.held(Action::Pause) {
+                self.result = Pop;
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.input_timer.reset();
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn options_fit_panel() {
+        //the panel border is 3px, leave at least 2px more after each option
+        for (name, _) in OPTIONS {
+            let right = MENU_X + Standard8x10.measure(name).0 as isize - 1;
+            assert!(right <= PANEL.2 - 5, "{name} ends at {right}");
+        }
+    }
+
+    #[test]
+    fn opens_settings() {
+        let mut harness = Harness::new(PauseMenu::new());
+        harness.run(START_DELAY);
+        for _ in 0..2 {
+            harness.press(Action::Down);
+            harness.run(input_delay());
+        }
+        assert_eq!(harness.press(Action::Confirm), Push(Screen::Settings));
+
+        harness.game.resuming();
+        assert_eq!(harness.tick(), Nothing);
+    }
+}
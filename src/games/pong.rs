@@ -1,21 +1,50 @@
+use crate::audio::{Audio, Sound};
 use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
-use crate::games::pong::Direction::*;
+use crate::games::ball::{Ball, Towards};
+use crate::games::pong::State::*;
+use crate::games::GAME_INPUT_DELAY;
+use crate::high_scores::submit_score;
+use crate::input;
+use crate::input::{Action, Input};
 use crate::GameUpdateResult::{Nothing, Pop};
-use crate::{Game, GameUpdateResult, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::{Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
 use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
 use pixels_graphics_lib::buffer_graphics_lib::shapes::CreateDrawable;
 use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::CenterTop;
 use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
 use pixels_graphics_lib::buffer_graphics_lib::text::Text;
-use pixels_graphics_lib::prelude::PixelFont::Standard8x10;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
 use pixels_graphics_lib::prelude::*;
+use std::cmp::Ordering;
 
-const PADDLE_X_H: usize = 0;
-const PADDLE_X_C: usize = SCREEN_WIDTH - 6;
-const SCORE_H: Coord = Coord::new(40, 6);
-const SCORE_C: Coord = Coord::new(120, 6);
-const BALL_MOVE_RATE: f64 = 0.01;
+pub const ID: &str = "pong";
+
+const PADDLE_X_L: usize = 0;
+const PADDLE_X_R: usize = SCREEN_WIDTH - 6;
+const SCORE_L: Coord = Coord::new(40, 6);
+const SCORE_R: Coord = Coord::new(120, 6);
+const LABEL_OFFSET: Coord = Coord::new(0, 14);
+const MATCH_POINT_OFFSET: Coord = Coord::new(0, 22);
+const CLOCK_POS: Coord = Coord::new(SCREEN_WIDTH as isize / 2, 2);
+const SETUP_TITLE_Y: isize = 16;
+//the CPU row is only shown for one player
+const SETUP_ROWS: [&str; 3] = ["PLAYERS", "RULES", "CPU"];
+const SETUP_Y: isize = 46;
+const SETUP_STEP: isize = 26;
+const GAME_OVER_OPTIONS: [&str; 2] = ["REMATCH", "MENU"];
+const GAME_OVER_PANEL: (isize, isize, isize, isize) = (20, 36, 140, 118);
+const WINNING_SCORE: usize = 11;
+const MATCH_LENGTH: f64 = 120.0;
+const CPU_SERVE_DELAY: f64 = 1.0;
 const PADDLE_MOVE_DISTANCE: isize = 2;
+const BALL_RADIUS: f32 = 5.0;
+//pixels per second
+const BALL_START_SPEED: f32 = 70.0;
+const BALL_MAX_SPEED: f32 = 200.0;
+//multiplier applied on every paddle hit
+const BALL_SPEED_UP: f32 = 1.07;
+const MAX_SERVE_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
 
 const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
 
@@ -24,6 +53,9 @@ struct Player {
     paddle: Drawable<Rect>,
     next_move: Timer,
     score: usize,
+    /// Pixels per second, positive is down
+    velocity: f32,
+    last_top: isize,
 }
 
 impl Player {
@@ -32,65 +64,293 @@ impl Player {
             paddle: Drawable::from_obj(Rect::new((x, 0), (x + 6, 30)), fill(CLR_3)),
             next_move: Timer::new_once(0.0001),
             score: 0,
+            velocity: 0.0,
+            last_top: 0,
         }
     }
 }
 
+impl Player {
+    #[allow(clippy::collapsible_if)] //for readability
+    fn move_with(&mut self, timing: &Timing, input: &Input, player: input::Player) {
+        if self.next_move.update(timing) {
+            if input.held_by(player, Action::Up) {
+                if self.paddle.obj().top() > 0 {
+                    self.paddle = self.paddle.with_translation((0, -PADDLE_MOVE_DISTANCE));
+                    self.next_move.reset();
+                }
+            } else if input.held_by(player, Action::Down) {
+                if self.paddle.obj().bottom() < PLAY_HEIGHT as isize {
+                    self.paddle = self.paddle.with_translation((0, PADDLE_MOVE_DISTANCE));
+                    self.next_move.reset();
+                }
+            }
+        }
+    }
+
+    /// Must be called once per update after the paddle has moved
+    fn track_velocity(&mut self, timing: &Timing) {
+        let top = self.paddle.obj().top();
+        self.velocity = (top - self.last_top) as f32 / timing.fixed_time_step as f32;
+        self.last_top = top;
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Direction {
-    Top,
-    Left,
-    Right,
-    Bottom,
+enum State {
+    Setup,
+    Playing,
+    /// The side that won
+    GameOver(Side),
 }
 
-#[derive(Debug)]
-struct Ball {
-    shape: Drawable<Circle>,
-    direction: isize,
-    next_move: f64,
-    last_bounce_side: Direction,
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Perfect,
 }
 
-impl Ball {
-    pub fn new() -> Self {
+/// How a [Difficulty] plays
+struct CpuProfile {
+    /// Seconds between the CPU looking at the ball
+    reaction: f64,
+    /// Work out where the ball will reach the paddle, including wall bounces,
+    /// otherwise just follow the ball
+    predicts: bool,
+    /// Pixels per second
+    max_speed: f32,
+    /// Largest distance, in pixels, the CPU will deliberately miss its target by
+    error: f32,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Perfect,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Perfect => "PERFECT",
+        }
+    }
+
+    fn profile(&self) -> CpuProfile {
+        match self {
+            Difficulty::Easy => CpuProfile {
+                reaction: 0.4,
+                predicts: false,
+                max_speed: 60.0,
+                error: 16.0,
+            },
+            Difficulty::Normal => CpuProfile {
+                reaction: 0.25,
+                predicts: true,
+                max_speed: 90.0,
+                error: 10.0,
+            },
+            Difficulty::Hard => CpuProfile {
+                reaction: 0.12,
+                predicts: true,
+                max_speed: 130.0,
+                error: 4.0,
+            },
+            Difficulty::Perfect => CpuProfile {
+                reaction: 0.0,
+                predicts: true,
+                max_speed: BALL_MAX_SPEED,
+                error: 0.0,
+            },
+        }
+    }
+}
+
+/// Controls the right paddle in one player games, and both in the attract mode demo
+struct Cpu {
+    difficulty: Difficulty,
+    /// Paddle being controlled
+    side: Side,
+    profile: CpuProfile,
+    reaction: Timer,
+    /// Where the paddle's center is heading
+    target: f32,
+    /// Paddle top, kept separately so the paddle can move less than a pixel per update
+    y: f32,
+    /// Set while the ball is heading towards the CPU, a new error is picked for every return
+    incoming: bool,
+    error: f32,
+}
+
+impl Cpu {
+    pub fn new(difficulty: Difficulty, side: Side) -> Self {
+        let profile = difficulty.profile();
         Self {
-            last_bounce_side: Left,
-            shape: Drawable::from_obj(Circle::new((40, 40), 5), fill(CLR_3)),
-            direction: 135,
-            next_move: 0.0,
+            difficulty,
+            side,
+            reaction: Timer::new(profile.reaction),
+            profile,
+            target: PLAY_HEIGHT as f32 / 2.0,
+            y: 0.0,
+            incoming: false,
+            error: 0.0,
+        }
+    }
+}
+
+impl Cpu {
+    fn update(&mut self, timing: &Timing, ball: &Ball, player: &mut Player, rng: &mut Rng) {
+        let rect = player.paddle.obj().clone();
+        let height = rect.height() as f32;
+        if self.reaction.update(timing) {
+            let incoming = match self.side {
+                Side::Left => ball.velocity.0 < 0.0,
+                Side::Right => ball.velocity.0 > 0.0,
+            };
+            if incoming && !self.incoming {
+                self.error = (rng.f32() * 2.0 - 1.0) * self.profile.error;
+            }
+            self.incoming = incoming;
+            self.target = if !incoming && self.profile.predicts {
+                PLAY_HEIGHT as f32 / 2.0
+            } else if self.profile.predicts {
+                let x = match self.side {
+                    Side::Left => rect.right() as f32 + 1.0 + BALL_RADIUS,
+                    Side::Right => rect.left() as f32 - BALL_RADIUS,
+                };
+                predict_y(ball, x) + self.error
+            } else {
+                ball.pos.1 + self.error
+            };
+        }
+
+        self.y = rect.top() as f32 + (self.y - self.y.round());
+        let max_move = self.profile.max_speed * timing.fixed_time_step as f32;
+        let diff = self.target - (self.y + height / 2.0);
+        self.y += diff.clamp(-max_move, max_move);
+        self.y = self.y.clamp(0.0, PLAY_HEIGHT as f32 - height);
+        player.paddle = player
+            .paddle
+            .with_move((rect.left(), self.y.round() as isize));
+    }
+}
+
+/// Where the center of `ball` will be when it reaches `x`, bouncing off the top and bottom walls
+fn predict_y(ball: &Ball, x: f32) -> f32 {
+    if ball.velocity.0 == 0.0 {
+        return ball.pos.1;
+    }
+    let time = ((x - ball.pos.0) / ball.velocity.0).max(0.0);
+    let y = ball.pos.1 + ball.velocity.1 * time;
+    let min = BALL_RADIUS;
+    let span = PLAY_HEIGHT as f32 - BALL_RADIUS * 2.0;
+    //unfold the bounces, every two spans the ball is back to travelling the same way
+    let offset = (y - min).rem_euclid(span * 2.0);
+    if offset > span {
+        min + span * 2.0 - offset
+    } else {
+        min + offset
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Rules {
+    /// First to [WINNING_SCORE]
+    FirstTo,
+    /// First to [WINNING_SCORE] with a lead of at least two
+    WinBy2,
+    /// Highest score after [MATCH_LENGTH], if tied the next point wins
+    Timed,
+}
+
+impl Rules {
+    const ALL: [Rules; 3] = [Rules::FirstTo, Rules::WinBy2, Rules::Timed];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Rules::FirstTo => "FIRST TO 11",
+            Rules::WinBy2 => "WIN BY 2",
+            Rules::Timed => "2 MINUTES",
+        }
+    }
+
+    /// Side that has won with these scores, if either
+    fn winner(&self, left: usize, right: usize, time_up: bool) -> Option<Side> {
+        let leader = match left.cmp(&right) {
+            Ordering::Greater => Side::Left,
+            Ordering::Less => Side::Right,
+            Ordering::Equal => return None,
+        };
+        let won = match self {
+            Rules::FirstTo => left.max(right) >= WINNING_SCORE,
+            Rules::WinBy2 => left.max(right) >= WINNING_SCORE && left.abs_diff(right) >= 2,
+            Rules::Timed => time_up,
+        };
+        if won {
+            Some(leader)
+        } else {
+            None
+        }
+    }
+}
+
+enum BallEvent {
+    Wall,
+    Paddle,
+    /// Ball went past this side
+    Out(Side),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn other(&self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
         }
     }
 }
 
 pub struct Pong {
-    human: Player,
-    cpu: Player,
+    state: State,
+    /// Index into [SETUP_ROWS] or [GAME_OVER_OPTIONS]
+    cursor: usize,
+    two_player: bool,
+    rules: Rules,
+    /// Seconds left in a [Rules::Timed] match, only counts down while the ball is moving
+    time_left: f64,
+    /// Which way the next serve goes, always towards the player that conceded the last point
+    serve_to: Side,
+    cpu_serve: Timer,
+    cpu: Cpu,
+    /// Controls the left paddle in the attract mode demo, scores aren't saved
+    demo_cpu: Option<Cpu>,
+    left: Player,
+    /// Controlled by the CPU or the second player
+    right: Player,
     ball: Ball,
     separator: Drawable<Rect>,
     serving: bool,
     result: GameUpdateResult,
-    #[allow(unused)] //needed to play sound
-    audio_engine: AudioEngine,
-    paddle: SoundEffect,
-    miss: SoundEffect,
-    wall: SoundEffect,
+    input_timer: Timer,
     button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
 }
 
 impl Pong {
-    pub fn new() -> Box<Self> {
-        let audio_engine = AudioEngine::new().unwrap();
-        let wall = audio_engine
-            .load_from_bytes(include_bytes!("../../assets/wall.wav"), 0.2)
-            .unwrap();
-        let paddle = audio_engine
-            .load_from_bytes(include_bytes!("../../assets/paddle.wav"), 0.2)
-            .unwrap();
-        let miss = audio_engine
-            .load_from_bytes(include_bytes!("../../assets/ball.wav"), 0.4)
-            .unwrap();
-
+    pub fn new(seed: u64) -> Box<Self> {
         let separator = Drawable::from_obj(
             Rect::new(
                 (SCREEN_WIDTH / 2 - 1, 0),
@@ -100,164 +360,687 @@ impl Pong {
         );
         Box::new(Self {
             result: Nothing,
-            paddle,
-            miss,
             serving: true,
-            human: Player::new(PADDLE_X_H),
-            cpu: Player::new(PADDLE_X_C),
-            ball: Ball::new(),
+            state: Setup,
+            cursor: 0,
+            two_player: false,
+            rules: Rules::FirstTo,
+            time_left: MATCH_LENGTH,
+            serve_to: Side::Right,
+            cpu_serve: Timer::new_once(CPU_SERVE_DELAY),
+            cpu: Cpu::new(Difficulty::Normal, Side::Right),
+            demo_cpu: None,
+            left: Player::new(PADDLE_X_L),
+            right: Player::new(PADDLE_X_R),
+            ball: Ball::new(BALL_RADIUS),
             separator,
-            wall,
-            audio_engine,
+            input_timer: Timer::new(GAME_INPUT_DELAY),
             button_bar: ButtonBar::new(
                 coord!(0, PLAY_HEIGHT + 1),
                 SCREEN_WIDTH,
                 &[
-                    ("EXIT", ButtonDef::Escape),
-                    ("PADDLE", ButtonDef::Vert),
-                    ("SERVE", ButtonDef::Space),
+                    ("PAUSE", ButtonDef::Back),
+                    ("OPTION", ButtonDef::Cursor),
+                    ("START", ButtonDef::Confirm),
                 ],
             ),
+            seed,
+            rng: Rng::with_seed(seed),
         })
     }
+
+    /// One player match with the CPU playing both sides
+    pub fn demo(seed: u64) -> Box<Self> {
+        let mut pong = Pong::new(seed);
+        pong.cpu = Cpu::new(Difficulty::Hard, Side::Right);
+        pong.demo_cpu = Some(Cpu::new(Difficulty::Hard, Side::Left));
+        pong.start();
+        pong
+    }
 }
 
 impl Pong {
+    /// Get ready to serve towards [Pong::serve_to] from the other side
     fn reset_play(&mut self) {
         self.serving = true;
-        self.ball.shape = self.ball.shape.with_move((40, fastrand::isize(40..100)));
-        self.human.paddle = self.human.paddle.with_move((PADDLE_X_H, PLAY_HEIGHT / 2));
-        self.cpu.paddle = self.cpu.paddle.with_move((PADDLE_X_C, PLAY_HEIGHT / 2));
-        self.ball.last_bounce_side = Left;
-        self.ball.direction = [45, 135][fastrand::usize(0..=1)]
+        self.cpu_serve.reset();
+        let x = match self.serve_to {
+            Side::Right => SCORE_L.x,
+            Side::Left => SCORE_R.x,
+        };
+        self.ball.pos = (x as f32, self.rng.isize(40..100) as f32);
+        self.ball.velocity = (0.0, 0.0);
+        self.left.paddle = self.left.paddle.with_move((PADDLE_X_L, PLAY_HEIGHT / 2));
+        self.right.paddle = self.right.paddle.with_move((PADDLE_X_R, PLAY_HEIGHT / 2));
+    }
+
+    /// Start the ball moving towards [Pong::serve_to] at a random angle
+    fn serve(&mut self) {
+        let angle = (self.rng.f32() * 2.0 - 1.0) * MAX_SERVE_ANGLE;
+        let angle = match self.serve_to {
+            Side::Right => angle,
+            Side::Left => std::f32::consts::PI - angle,
+        };
+        self.ball.launch(BALL_START_SPEED, angle);
+    }
+
+    /// Move the ball in steps of at most a pixel so it can't pass through paddles
+    fn move_ball(&mut self, timing: &Timing) -> Option<BallEvent> {
+        let (steps, step) = self.ball.steps(timing.fixed_time_step as f32);
+        let mut event = None;
+        for _ in 0..steps {
+            let ball = &mut self.ball;
+            ball.advance(step);
+
+            if ball.pos.1 - BALL_RADIUS < 0.0 {
+                ball.pos.1 = BALL_RADIUS;
+                ball.velocity.1 = ball.velocity.1.abs();
+                event = Some(BallEvent::Wall);
+            } else if ball.pos.1 + BALL_RADIUS > PLAY_HEIGHT as f32 {
+                ball.pos.1 = PLAY_HEIGHT as f32 - BALL_RADIUS;
+                ball.velocity.1 = -ball.velocity.1.abs();
+                event = Some(BallEvent::Wall);
+            }
+
+            //only the front of the paddle returns the ball, once past it's a miss
+            let left = self.left.paddle.obj();
+            let right = self.right.paddle.obj();
+            if ball.velocity.0 < 0.0 && ball.pos.0 > left.right() as f32 + 1.0 && ball.touches(left)
+            {
+                ball.speed_up(BALL_SPEED_UP, BALL_MAX_SPEED);
+                ball.bounce(left, self.left.velocity, Towards::Right);
+                event = Some(BallEvent::Paddle);
+            } else if ball.velocity.0 > 0.0
+                && ball.pos.0 < right.left() as f32
+                && ball.touches(right)
+            {
+                ball.speed_up(BALL_SPEED_UP, BALL_MAX_SPEED);
+                ball.bounce(right, self.right.velocity, Towards::Left);
+                event = Some(BallEvent::Paddle);
+            }
+
+            if ball.pos.0 + BALL_RADIUS < 0.0 {
+                return Some(BallEvent::Out(Side::Left));
+            } else if ball.pos.0 - BALL_RADIUS > SCREEN_WIDTH as f32 {
+                return Some(BallEvent::Out(Side::Right));
+            }
+        }
+        event
+    }
+
+    fn start(&mut self) {
+        self.state = Playing;
+        self.cpu = Cpu::new(self.cpu.difficulty, Side::Right);
+        if let Some(cpu) = &mut self.demo_cpu {
+            *cpu = Cpu::new(cpu.difficulty, Side::Left);
+        }
+        self.left.score = 0;
+        self.right.score = 0;
+        self.time_left = MATCH_LENGTH;
+        self.serve_to = Side::Right;
+        self.reset_play();
+        if self.two_player {
+            self.button_bar.set_buttons(&[
+                ("P1", ButtonDef::Vert),
+                ("PAUSE", ButtonDef::Back),
+                ("SERVE", ButtonDef::Confirm),
+                ("P2", ButtonDef::P2Vert),
+            ]);
+        } else {
+            self.button_bar.set_buttons(&[
+                ("PAUSE", ButtonDef::Back),
+                ("PADDLE", ButtonDef::Vert),
+                ("SERVE", ButtonDef::Confirm),
+            ]);
+        }
+    }
+
+    fn end(&mut self, winner: Side) {
+        self.state = GameOver(winner);
+        self.cursor = 0;
+        self.input_timer.reset();
+        self.button_bar.set_buttons(&[
+            ("PAUSE", ButtonDef::Back),
+            ("CURSOR", ButtonDef::Vert),
+            ("SELECT", ButtonDef::Confirm),
+        ]);
+        if !self.two_player && self.demo_cpu.is_none() {
+            self.result = submit_score(ID, self.left.score);
+        }
+    }
+
+    fn setup_rows(&self) -> usize {
+        if self.two_player {
+            SETUP_ROWS.len() - 1
+        } else {
+            SETUP_ROWS.len()
+        }
+    }
+
+    fn update_setup(&mut self, timing: &Timing, input: &Input) {
+        if self.input_timer.update(timing) {
+            let rows = self.setup_rows();
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + rows - 1) % rows;
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + 1) % rows;
+            } else if input.held(Action::Left) || input.held(Action::Right) {
+                self.input_timer.reset();
+                let forward = input.held(Action::Right);
+                let cycle = |idx: usize, len: usize| {
+                    if forward {
+                        (idx + 1) % len
+                    } else {
+                        (idx + len - 1) % len
+                    }
+                };
+                match self.cursor {
+                    0 => self.two_player = !self.two_player,
+                    1 => {
+                        let idx = Rules::ALL.iter().position(|r| *r == self.rules).unwrap();
+                        self.rules = Rules::ALL[cycle(idx, Rules::ALL.len())];
+                    }
+                    _ => {
+                        let idx = Difficulty::ALL
+                            .iter()
+                            .position(|d| *d == self.cpu.difficulty)
+                            .unwrap();
+                        let difficulty = Difficulty::ALL[cycle(idx, Difficulty::ALL.len())];
+                        self.cpu = Cpu::new(difficulty, Side::Right);
+                    }
+                }
+            } else if input.pressed(Action::Confirm) {
+                self.start();
+            }
+        }
+    }
+
+    fn update_game_over(&mut self, timing: &Timing, input: &Input) {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) || input.held(Action::Down) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + 1) % GAME_OVER_OPTIONS.len();
+            } else if input.pressed(Action::Confirm) {
+                if self.cursor == 0 {
+                    self.start();
+                } else {
+                    self.result = Pop;
+                }
+            }
+        }
+    }
+
+    /// The side that wins the match if they score the next point
+    fn match_point(&self) -> Option<Side> {
+        if self.rules == Rules::Timed {
+            return None;
+        }
+        [Side::Left, Side::Right].into_iter().find(|side| {
+            let (left, right) = match side {
+                Side::Left => (self.left.score + 1, self.right.score),
+                Side::Right => (self.left.score, self.right.score + 1),
+            };
+            self.rules.winner(left, right, false) == Some(*side)
+        })
+    }
+
+    fn side_name(&self, side: Side) -> &'static str {
+        match (side, self.two_player) {
+            (Side::Left, true) => "P1",
+            (Side::Right, true) => "P2",
+            (Side::Left, false) => "YOU",
+            (Side::Right, false) => "CPU",
+        }
+    }
+
+    fn render_setup(&self, graphics: &mut Graphics) {
+        graphics.draw_text(
+            "PONG",
+            TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_TITLE_Y),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+        let players = if self.two_player {
+            "2 PLAYERS"
+        } else {
+            "1 PLAYER"
+        };
+        for (i, (name, value)) in SETUP_ROWS
+            .iter()
+            .zip([players, self.rules.name(), self.cpu.difficulty.name()])
+            .take(self.setup_rows())
+            .enumerate()
+        {
+            let color = if self.cursor == i { CLR_3 } else { CLR_2 };
+            let y = SETUP_Y + i as isize * SETUP_STEP;
+            graphics.draw_text(
+                name,
+                TextPos::Px(SCREEN_WIDTH as isize / 2, y),
+                (CLR_1, Standard4x5, CenterTop),
+            );
+            let value = if self.cursor == i {
+                format!("< {value} >")
+            } else {
+                value.to_string()
+            };
+            graphics.draw_text(
+                &value,
+                TextPos::Px(SCREEN_WIDTH as isize / 2, y + 8),
+                (color, Standard8x10, CenterTop),
+            );
+        }
+    }
+
+    fn render_game_over(&self, graphics: &mut Graphics, winner: Side) {
+        let (x1, y1, x2, y2) = GAME_OVER_PANEL;
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+        graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+        graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+        let title = match (winner, self.two_player) {
+            (Side::Left, false) => String::from("YOU WIN!"),
+            (Side::Right, false) => String::from("CPU WINS"),
+            (side, true) => format!("{} WINS!", self.side_name(side)),
+        };
+        let mid_x = (x1 + x2) / 2;
+        graphics.draw_text(
+            &title,
+            TextPos::Px(mid_x, y1 + 8),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+        graphics.draw_text(
+            &format!("{} - {}", self.left.score, self.right.score),
+            TextPos::Px(mid_x, y1 + 22),
+            (CLR_2, Standard8x10, CenterTop),
+        );
+        graphics.draw_text(
+            &format!("SEED {}", self.seed),
+            TextPos::Px(mid_x, y2 - 10),
+            (CLR_1, Standard4x5, CenterTop),
+        );
+        for (i, option) in GAME_OVER_OPTIONS.iter().enumerate() {
+            let color = if self.cursor == i { CLR_3 } else { CLR_2 };
+            let y = y1 + 44 + i as isize * 14;
+            graphics.draw_text(
+                option,
+                TextPos::Px(mid_x, y),
+                (color, Standard8x10, CenterTop),
+            );
+            if self.cursor == i {
+                let width = Standard8x10.measure(option).0 as isize;
+                graphics.draw_triangle(
+                    Triangle::equilateral((mid_x - width / 2 - 8, y + 4), 6, FlatSide::Left),
+                    fill(CLR_3),
+                );
+            }
+        }
+    }
+
+    fn render_hud(&self, graphics: &mut Graphics) {
+        if self.rules == Rules::Timed {
+            let secs = self.time_left.max(0.0).ceil() as usize;
+            let text = if secs == 0 {
+                String::from("GOLDEN POINT")
+            } else {
+                format!("{}:{:02}", secs / 60, secs % 60)
+            };
+            let (width, height) = Standard4x5.measure(&text);
+            graphics.draw_rect(
+                Rect::new_with_size(
+                    CLOCK_POS - (width as isize / 2 + 1, 1),
+                    width + 2,
+                    height + 2,
+                ),
+                fill(CLR_0),
+            );
+            graphics.draw_text(
+                &text,
+                TextPos::px(CLOCK_POS),
+                (CLR_3, Standard4x5, CenterTop),
+            );
+        }
+        if self.state == Playing {
+            if let Some(side) = self.match_point() {
+                let pos = match side {
+                    Side::Left => SCORE_L,
+                    Side::Right => SCORE_R,
+                };
+                graphics.draw_text(
+                    "MATCH POINT",
+                    TextPos::px(pos + MATCH_POINT_OFFSET),
+                    (CLR_3, Standard4x5, CenterTop),
+                );
+            }
+        }
+    }
+
+    /// Player for the side that is serving, when it's a person
+    fn server(&self) -> Option<input::Player> {
+        match (self.serve_to.other(), self.two_player) {
+            (Side::Left, _) if self.demo_cpu.is_some() => None,
+            (Side::Left, _) => Some(input::Player::One),
+            (Side::Right, true) => Some(input::Player::Two),
+            (Side::Right, false) => None,
+        }
     }
 }
 
 impl Game for Pong {
     fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        if self.state == Setup {
+            self.render_setup(graphics);
+            self.button_bar.render(graphics, controller);
+            return;
+        }
+
         self.separator.render(graphics);
 
         graphics.draw(&Text::new(
-            &format!("{}", self.human.score),
-            TextPos::px(SCORE_H),
+            &format!("{}", self.left.score),
+            TextPos::px(SCORE_L),
             (CLR_2, Standard8x10, CenterTop),
         ));
         graphics.draw(&Text::new(
-            &format!("{}", self.cpu.score),
-            TextPos::px(SCORE_C),
+            &format!("{}", self.right.score),
+            TextPos::px(SCORE_R),
             (CLR_2, Standard8x10, CenterTop),
         ));
+        if self.two_player {
+            graphics.draw_text(
+                "P1",
+                TextPos::px(SCORE_L + LABEL_OFFSET),
+                (CLR_2, Standard4x5, CenterTop),
+            );
+            graphics.draw_text(
+                "P2",
+                TextPos::px(SCORE_R + LABEL_OFFSET),
+                (CLR_2, Standard4x5, CenterTop),
+            );
+        } else {
+            graphics.draw_text(
+                &format!("CPU {}", self.cpu.difficulty.name()),
+                TextPos::px(SCORE_R + LABEL_OFFSET),
+                (CLR_2, Standard4x5, CenterTop),
+            );
+        }
 
-        self.human.paddle.render(graphics);
-        self.cpu.paddle.render(graphics);
-        self.ball.shape.render(graphics);
+        self.left.paddle.render(graphics);
+        self.right.paddle.render(graphics);
+        self.ball.render(graphics);
+        self.render_hud(graphics);
+        if let GameOver(winner) = self.state {
+            self.render_game_over(graphics, winner);
+        }
         self.button_bar.render(graphics, controller)
     }
 
     fn on_key_press(&mut self, _: KeyCode) {}
 
     #[allow(clippy::collapsible_if)] //for readability
-    fn update(
-        &mut self,
-        timing: &Timing,
-        held_keys: &Vec<&KeyCode>,
-        controller: &GameController,
-    ) -> GameUpdateResult {
-        self.wall.update(timing);
-        self.paddle.update(timing);
-        self.miss.update(timing);
-
-        if self.serving && (held_keys.contains(&&KeyCode::Space) || controller.action.south) {
-            self.serving = false
-        }
-
-        if held_keys.contains(&&KeyCode::Escape) || controller.action.east {
-            self.result = Pop;
-        }
-
-        if self.human.next_move.update(timing) {
-            if held_keys.contains(&&KeyCode::ArrowUp) || controller.direction.up {
-                if self.human.paddle.obj().top() > 0 {
-                    self.human.paddle = self
-                        .human
-                        .paddle
-                        .with_translation((0, -PADDLE_MOVE_DISTANCE));
-                    self.human.next_move.reset();
-                }
-            } else if held_keys.contains(&&KeyCode::ArrowDown) || controller.direction.down {
-                if self.human.paddle.obj().bottom() < PLAY_HEIGHT as isize {
-                    self.human.paddle = self
-                        .human
-                        .paddle
-                        .with_translation((0, PADDLE_MOVE_DISTANCE));
-                    self.human.next_move.reset();
-                }
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        match self.state {
+            Setup => {
+                self.update_setup(timing, input);
+                return self.result;
+            }
+            GameOver(_) => {
+                self.update_game_over(timing, input);
+                return self.result;
             }
+            Playing => {}
         }
 
-        let ball_center = self.ball.shape.obj().center();
-        if !self.serving {
-            if self.ball.next_move <= 0.0 {
-                let next_coord = Coord::from_angle(ball_center, 1, self.ball.direction);
-                self.ball.shape = self.ball.shape.with_move(next_coord);
-                self.ball.next_move = BALL_MOVE_RATE;
-
-                if ball_center.y == 0 {
-                    if self.ball.last_bounce_side != Top {
-                        self.ball.direction = if self.ball.direction == 45 { 135 } else { 225 };
-                        self.ball.last_bounce_side = Top;
-                        self.wall.play();
-                    }
-                } else if ball_center.y == PLAY_HEIGHT as isize {
-                    if self.ball.last_bounce_side != Bottom {
-                        self.ball.direction = if self.ball.direction == 135 { 45 } else { 315 };
-                        self.ball.last_bounce_side = Bottom;
-                        self.wall.play();
-                    }
-                } else if self.human.paddle.obj().contains(ball_center) {
-                    if self.ball.last_bounce_side != Left {
-                        self.ball.direction = if self.ball.direction == 315 { 45 } else { 135 };
-                        self.ball.last_bounce_side = Left;
-                        self.paddle.play();
-                    }
-                } else if self.cpu.paddle.obj().contains(ball_center) {
-                    if self.ball.last_bounce_side != Right {
-                        self.ball.direction = if self.ball.direction == 135 { 225 } else { 315 };
-                        self.ball.last_bounce_side = Right;
-                        self.paddle.play();
-                    }
-                } else if ball_center.x == 0 {
-                    self.cpu.score += 1;
-                    self.reset_play();
-                    self.miss.play();
-                } else if ball_center.x == SCREEN_WIDTH as isize {
-                    self.human.score += 1;
-                    self.reset_play();
-                    self.miss.play();
-                }
+        if self.serving {
+            let serve = match self.server() {
+                Some(player) => input.pressed_by(player, Action::Confirm),
+                None => self.cpu_serve.update(timing),
+            };
+            if serve {
+                self.serving = false;
+                self.serve();
             }
-            self.ball.next_move -= timing.fixed_time_step;
+        }
+
+        if self.demo_cpu.is_none() {
+            self.left.move_with(timing, input, input::Player::One);
+        }
+        if self.two_player {
+            self.right.move_with(timing, input, input::Player::Two);
+        }
 
-            if self.cpu.next_move.update(timing) {
-                if fastrand::bool() {
-                    let cpu_center = self.cpu.paddle.obj().center();
-                    if cpu_center.y < ball_center.y
-                        && self.cpu.paddle.obj().bottom() < PLAY_HEIGHT as isize
+        if !self.serving {
+            if self.rules == Rules::Timed {
+                self.time_left -= timing.fixed_time_step;
+            }
+            match self.move_ball(timing) {
+                Some(BallEvent::Wall) => audio.play(Sound::Wall),
+                Some(BallEvent::Paddle) => audio.play(Sound::Paddle),
+                Some(BallEvent::Out(side)) => {
+                    match side {
+                        Side::Left => self.right.score += 1,
+                        Side::Right => self.left.score += 1,
+                    }
+                    audio.play(Sound::Ball);
+                    let time_up = self.time_left <= 0.0;
+                    match self
+                        .rules
+                        .winner(self.left.score, self.right.score, time_up)
                     {
-                        self.cpu.paddle =
-                            self.cpu.paddle.with_translation((0, PADDLE_MOVE_DISTANCE));
-                        self.cpu.next_move.reset();
-                    } else if cpu_center.y > ball_center.y && self.cpu.paddle.obj().top() > 0 {
-                        self.cpu.paddle =
-                            self.cpu.paddle.with_translation((0, -PADDLE_MOVE_DISTANCE));
-                        self.cpu.next_move.reset();
+                        Some(winner) => self.end(winner),
+                        None => {
+                            self.serve_to = side;
+                            self.reset_play();
+                        }
                     }
                 }
+                None => {}
             }
         }
 
+        if !self.two_player {
+            self.cpu
+                .update(timing, &self.ball, &mut self.right, &mut self.rng);
+        }
+        if let Some(cpu) = &mut self.demo_cpu {
+            cpu.update(timing, &self.ball, &mut self.left, &mut self.rng);
+        }
+
+        self.left.track_velocity(timing);
+        self.right.track_velocity(timing);
+
         self.result
     }
 
-    fn resuming(&mut self) {}
+    fn resuming(&mut self) {
+        self.input_timer.reset();
+        self.result = Nothing;
+    }
+
+    fn players(&self) -> usize {
+        if self.two_player {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn playing() -> Harness<Pong> {
+        let mut harness = Harness::new(Pong::new(1));
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        harness
+    }
+
+    /// Put the ball in play at `pos`, moving horizontally
+    fn launch(harness: &mut Harness<Pong>, pos: (f32, f32), speed: f32) {
+        harness.game.serving = false;
+        harness.game.ball.pos = pos;
+        harness.game.ball.velocity = (speed, 0.0);
+        harness.game.ball.speed = speed.abs();
+    }
+
+    #[test]
+    fn first_to() {
+        assert_eq!(Rules::FirstTo.winner(10, 9, false), None);
+        assert_eq!(Rules::FirstTo.winner(11, 10, false), Some(Side::Left));
+        assert_eq!(Rules::FirstTo.winner(3, 11, false), Some(Side::Right));
+    }
+
+    #[test]
+    fn win_by_2() {
+        assert_eq!(Rules::WinBy2.winner(11, 10, false), None);
+        assert_eq!(Rules::WinBy2.winner(12, 10, false), Some(Side::Left));
+        assert_eq!(Rules::WinBy2.winner(14, 15, false), None);
+    }
+
+    #[test]
+    fn timed() {
+        assert_eq!(Rules::Timed.winner(20, 2, false), None);
+        assert_eq!(Rules::Timed.winner(4, 4, true), None);
+        assert_eq!(Rules::Timed.winner(4, 5, true), Some(Side::Right));
+    }
+
+    #[test]
+    fn right_scores_when_left_misses() {
+        let mut harness = playing();
+        launch(&mut harness, (40.0, 10.0), -BALL_START_SPEED);
+        harness.run_until(2.0, |game| game.serving);
+        assert_eq!(harness.game.left.score, 0);
+        assert_eq!(harness.game.right.score, 1);
+        //conceded the point so gets served to
+        assert_eq!(harness.game.serve_to, Side::Left);
+    }
+
+    #[test]
+    fn paddle_returns_ball() {
+        let mut harness = playing();
+        let center = harness.game.left.paddle.obj().center();
+        launch(&mut harness, (40.0, center.y as f32), -BALL_START_SPEED);
+        harness.run_until(2.0, |game| game.ball.velocity.0 > 0.0);
+        assert!(!harness.game.serving);
+        assert_eq!(harness.game.right.score, 0);
+        assert!(harness.game.ball.speed > BALL_START_SPEED);
+    }
+
+    #[test]
+    fn winning_point_ends_match() {
+        let mut harness = playing();
+        harness.game.left.score = WINNING_SCORE - 1;
+        //already past the CPU's paddle
+        launch(
+            &mut harness,
+            (SCREEN_WIDTH as f32 - 2.0, 10.0),
+            BALL_START_SPEED,
+        );
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, GameOver(Side::Left));
+        assert_eq!(harness.game.left.score, WINNING_SCORE);
+        harness.assert_snapshot("pong_game_over");
+    }
+
+    #[test]
+    fn second_player_moves_right_paddle() {
+        let mut harness = Harness::new(Pong::new(1));
+        harness.hold(Action::Right);
+        harness.tick();
+        harness.release(Action::Right);
+        assert!(harness.game.two_player);
+        //skip waiting for the menu to accept input again
+        harness.game.input_timer = Timer::new(GAME_INPUT_DELAY);
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.players(), 2);
+
+        let left = harness.game.left.paddle.obj().top();
+        let right = harness.game.right.paddle.obj().top();
+        harness.hold_by(input::Player::Two, Action::Up);
+        harness.run(0.1);
+        assert_eq!(harness.game.left.paddle.obj().top(), left);
+        assert!(harness.game.right.paddle.obj().top() < right);
+    }
+
+    #[test]
+    fn demo_plays_itself() {
+        let mut harness = Harness::new(Pong::demo(1));
+        assert_eq!(harness.game.state, Playing);
+        let left = harness.game.left.paddle.obj().top();
+        harness.run_until(10.0, |game| game.ball.velocity.0 < 0.0);
+        harness.run_until(10.0, |game| game.ball.velocity.0 > 0.0);
+        assert_ne!(harness.game.left.paddle.obj().top(), left);
+        assert_eq!(harness.game.left.score + harness.game.right.score, 0);
+    }
+
+    #[test]
+    fn predicts_wall_bounces() {
+        let top = BALL_RADIUS;
+        let bottom = PLAY_HEIGHT as f32 - BALL_RADIUS;
+        let mut ball = Ball::new(BALL_RADIUS);
+        ball.pos = (100.0, 50.0);
+
+        ball.velocity = (-50.0, 10.0);
+        assert_eq!(predict_y(&ball, 0.0), 70.0);
+        //moving away, so it won't get any closer
+        assert_eq!(predict_y(&ball, 150.0), 50.0);
+
+        //45 up to the top, then back down 15
+        ball.velocity = (-50.0, -30.0);
+        assert_eq!(predict_y(&ball, 0.0), top + 15.0);
+
+        //up to the top, all the way down, then back up 10
+        ball.velocity = (-100.0, -(50.0 - top) - (bottom - top) - 10.0);
+        assert_eq!(predict_y(&ball, 0.0), bottom - 10.0);
+
+        ball.velocity = (0.0, 10.0);
+        assert_eq!(predict_y(&ball, 0.0), 50.0);
+    }
+
+    /// Send the ball at the CPU's paddle from the middle of the court,
+    /// true if the CPU returns it
+    fn cpu_returns(harness: &mut Harness<Pong>, y: f32, angle: f32) -> bool {
+        harness.game.reset_play();
+        harness.game.serving = false;
+        harness.game.ball.pos = (SCREEN_WIDTH as f32 / 2.0, y);
+        harness.game.ball.launch(BALL_START_SPEED * 1.5, angle);
+        harness.run_until(10.0, |game| game.serving || game.ball.velocity.0 < 0.0);
+        !harness.game.serving
+    }
+
+    fn cpu_shots(difficulty: Difficulty) -> Vec<bool> {
+        let mut harness = playing();
+        harness.game.cpu = Cpu::new(difficulty, Side::Right);
+        let mut returned = vec![];
+        for y in [20.0, 60.0, 100.0] {
+            for angle in [-0.9, -0.5, 0.0, 0.5, 0.9] {
+                returned.push(cpu_returns(&mut harness, y, angle));
+            }
+        }
+        returned
+    }
+
+    #[test]
+    fn perfect_cpu_always_returns() {
+        assert!(cpu_shots(Difficulty::Perfect)
+            .iter()
+            .all(|&returned| returned));
+    }
+
+    #[test]
+    fn easy_cpu_can_miss() {
+        assert!(cpu_shots(Difficulty::Easy)
+            .iter()
+            .any(|&returned| !returned));
+    }
+
+    #[test]
+    fn setup() {
+        let harness = Harness::new(Pong::new(1));
+        harness.assert_snapshot("pong_setup");
+    }
 }
@@ -0,0 +1,200 @@
+use crate::audio::Audio;
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::games::GameDescriptor;
+use crate::input::{Action, Input};
+use crate::replay::{Playback, Replay};
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_0, CLR_2, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::RightBottom;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
+use pixels_graphics_lib::prelude::*;
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+//updates per frame while fast forwarding
+const FAST_FORWARD_SPEED: usize = 4;
+const STATUS_SIZE: (usize, usize) = (34, 9);
+
+/// Plays a [Replay] by feeding the recorded input to a new copy of the game
+///
+/// The game's own results (menus, high scores, etc) are ignored
+pub struct ReplayPlayer {
+    game: Box<dyn Game>,
+    replay: Replay,
+    playback: Playback,
+    paused: bool,
+    fast_forward: bool,
+    result: GameUpdateResult,
+    button_bar: ButtonBar,
+}
+
+impl ReplayPlayer {
+    pub fn new(descriptor: &'static GameDescriptor, replay: Replay) -> Box<Self> {
+        Box::new(Self {
+            game: (descriptor.constructor)(replay.seed),
+            replay,
+            playback: Playback::default(),
+            paused: false,
+            fast_forward: false,
+            result: Nothing,
+            button_bar: ButtonBar::new(
+                coord!(0, PLAY_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("BACK", ButtonDef::Back),
+                    ("FAST", ButtonDef::Horz),
+                    ("PAUSE", ButtonDef::Confirm),
+                ],
+            ),
+        })
+    }
+}
+
+impl ReplayPlayer {
+    fn finished(&self) -> bool {
+        self.playback.finished(&self.replay)
+    }
+
+    /// Play the next recorded update, returns false if the replay has finished
+    fn step(&mut self, timing: &Timing, audio: &mut Audio) -> bool {
+        self.playback
+            .step(&self.replay, self.game.as_mut(), timing, audio)
+    }
+
+    fn status(&self) -> &'static str {
+        if self.finished() {
+            "END"
+        } else if self.paused {
+            "PAUSED"
+        } else if self.fast_forward {
+            "REPLAY >>"
+        } else {
+            "REPLAY"
+        }
+    }
+}
+
+impl Game for ReplayPlayer {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        self.game.render(graphics, controller);
+
+        let (width, height) = STATUS_SIZE;
+        graphics.draw_rect(
+            Rect::new_with_size((SCREEN_WIDTH - width, PLAY_HEIGHT - height), width, height),
+            fill(CLR_0),
+        );
+        graphics.draw_text(
+            self.status(),
+            Px(SCREEN_WIDTH as isize - 2, PLAY_HEIGHT as isize - 2),
+            (CLR_2, Standard4x5, RightBottom),
+        );
+
+        graphics.draw_rect(
+            Rect::new((0, PLAY_HEIGHT), (SCREEN_WIDTH, SCREEN_HEIGHT)),
+            fill(CLR_0),
+        );
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        if input.pressed(Action::Back) {
+            self.result = Pop;
+        } else if input.pressed(Action::Confirm) {
+            self.paused = !self.paused;
+        }
+        self.fast_forward = input.held(Action::Left) || input.held(Action::Right);
+
+        if !self.paused {
+            let speed = if self.fast_forward {
+                FAST_FORWARD_SPEED
+            } else {
+                1
+            };
+            for _ in 0..speed {
+                if !self.step(timing, audio) {
+                    break;
+                }
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::{find_game, pong};
+    use crate::harness::{render_game, Harness};
+    use crate::UPDATES_PER_SECOND;
+
+    /// Play some Pong, returning the harness it was played in
+    fn played() -> Harness<pong::Pong> {
+        let mut harness = Harness::new(pong::Pong::new(5));
+        harness.record(pong::ID, 5);
+        harness.press(Action::Confirm);
+        harness.hold(Action::Up);
+        harness.run(1.0);
+        harness.release(Action::Up);
+        harness.hold(Action::Down);
+        harness.run(0.7);
+        harness.release(Action::Down);
+        harness.run(3.0);
+        harness
+    }
+
+    fn player(replay: Replay) -> Harness<ReplayPlayer> {
+        Harness::new(ReplayPlayer::new(find_game(pong::ID).unwrap(), replay))
+    }
+
+    #[test]
+    fn reaches_same_state() {
+        let played = played();
+        let mut harness = player(played.replay());
+        harness.run_until(10.0, |player| player.finished());
+        assert_eq!(harness.game.status(), "END");
+        assert_eq!(
+            render_game(harness.game.game.as_ref()).to_string(),
+            played.render().to_string()
+        );
+    }
+
+    #[test]
+    fn pauses_and_fast_forwards() {
+        let played = played();
+        let replay = played.replay();
+        let mut harness = player(replay.clone());
+        harness.run(0.5);
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.status(), "PAUSED");
+        let paused = render_game(harness.game.game.as_ref()).to_string();
+        harness.run(1.0);
+        assert_eq!(render_game(harness.game.game.as_ref()).to_string(), paused);
+
+        harness.press(Action::Confirm);
+        harness.hold(Action::Right);
+        harness.tick();
+        assert_eq!(harness.game.status(), "REPLAY >>");
+        //the rest of the replay plays at four times the speed
+        let remaining = replay.ticks() as f64 / UPDATES_PER_SECOND as f64 - 0.5;
+        harness.run_until(remaining / 3.0, |player| player.finished());
+        assert_eq!(
+            render_game(harness.game.game.as_ref()).to_string(),
+            played.render().to_string()
+        );
+    }
+
+    #[test]
+    fn back_closes() {
+        let mut harness = player(played().replay());
+        assert_eq!(harness.press(Action::Back), Pop);
+        harness.game.resuming();
+        assert_eq!(harness.tick(), Nothing);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::audio::{Audio, Channel};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::input::{Action, Input};
+use crate::palette::{palette, palettes};
+use crate::settings::{
+    input_delay, set_settings, settings, Scale, Settings, INPUT_DELAYS, VOLUME_STEPS,
+};
+use crate::GameUpdateResult::{Nothing, Pop};
+use crate::{Game, GameUpdateResult, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::Positioning::{
+    CenterTop, LeftTop, RightTop,
+};
+use pixels_graphics_lib::buffer_graphics_lib::prelude::TextPos::Px;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+
+const TITLE: &str = "SETTINGS";
+const TITLE_POS: TextPos = Px(8, 8);
+const ROWS_Y: isize = 28;
+const ROW_STEP: isize = 12;
+const NAME_X: isize = 8;
+const VALUE_X: isize = SCREEN_WIDTH as isize - 8;
+const MESSAGE_Y: isize = (SCREEN_HEIGHT - BAR_HEIGHT) as isize - 10;
+const RESTART_MESSAGE: &str = "APPLIED AFTER RESTART";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Row {
+    Volume(Channel),
+    Mute,
+    Scale,
+    Palette,
+    Vsync,
+    InputDelay,
+}
+
+const ROWS: [Row; 8] = [
+    Row::Volume(Channel::Master),
+    Row::Volume(Channel::Music),
+    Row::Volume(Channel::Sfx),
+    Row::Mute,
+    Row::Scale,
+    Row::Palette,
+    Row::Vsync,
+    Row::InputDelay,
+];
+
+impl Row {
+    fn name(&self) -> &'static str {
+        match self {
+            Row::Volume(Channel::Master) => "VOLUME",
+            Row::Volume(Channel::Music) => "MUSIC",
+            Row::Volume(Channel::Sfx) => "EFFECTS",
+            Row::Mute => "MUTE",
+            Row::Scale => "WINDOW SCALE",
+            Row::Palette => "PALETTE",
+            Row::Vsync => "VSYNC",
+            Row::InputDelay => "INPUT DELAY",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        let on_off = |value: bool| String::from(if value { "ON" } else { "OFF" });
+        match self {
+            Row::Volume(channel) => {
+                format!(
+                    "{}%",
+                    settings.volume(*channel) as usize * 100 / VOLUME_STEPS as usize
+                )
+            }
+            Row::Mute => on_off(settings.muted),
+            Row::Scale => settings.scale.name().to_string(),
+            Row::Palette => palette(settings.palette).name.to_string(),
+            Row::Vsync => on_off(settings.vsync),
+            Row::InputDelay => format!("{:.2}S", settings.input_delay),
+        }
+    }
+
+    fn needs_restart(&self) -> bool {
+        matches!(self, Row::Scale | Row::Vsync)
+    }
+}
+
+/// Change and save [Settings], audio and palette changes are applied immediately
+pub struct SettingsMenu {
+    settings: Settings,
+    /// Called with the settings after every change
+    save: fn(Settings),
+    cursor: usize,
+    result: GameUpdateResult,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Box<Self> {
+        Self::with_settings(settings(), set_settings)
+    }
+
+    pub fn with_settings(settings: Settings, save: fn(Settings)) -> Box<Self> {
+        Box::new(Self {
+            settings,
+            save,
+            cursor: 0,
+            result: Nothing,
+            input_timer: Timer::new(input_delay()),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("BACK", ButtonDef::Back),
+                    ("CURSOR", ButtonDef::Vert),
+                    ("CHANGE", ButtonDef::Horz),
+                ],
+            ),
+        })
+    }
+}
+
+impl SettingsMenu {
+    /// Move the value of the selected row one step, wrapping for everything except volumes
+    fn change(&mut self, forward: bool, audio: &mut Audio) {
+        let cycle = |idx: usize, len: usize| {
+            if forward {
+                (idx + 1) % len
+            } else {
+                (idx + len - 1) % len
+            }
+        };
+        let settings = &mut self.settings;
+        match ROWS[self.cursor] {
+            Row::Volume(channel) => {
+                let volume = &mut settings.volumes[channel as usize];
+                *volume = if forward {
+                    (*volume + 1).min(VOLUME_STEPS)
+                } else {
+                    volume.saturating_sub(1)
+                };
+            }
+            Row::Mute => settings.muted = !settings.muted,
+            Row::Scale => {
+                let idx = Scale::ALL
+                    .iter()
+                    .position(|scale| *scale == settings.scale)
+                    .unwrap_or_default();
+                settings.scale = Scale::ALL[cycle(idx, Scale::ALL.len())];
+            }
+            Row::Palette => settings.palette = cycle(settings.palette, palettes().len()),
+            Row::Vsync => settings.vsync = !settings.vsync,
+            Row::InputDelay => {
+                let idx = INPUT_DELAYS
+                    .iter()
+                    .position(|delay| *delay == settings.input_delay)
+                    .unwrap_or_default();
+                settings.input_delay = INPUT_DELAYS[cycle(idx, INPUT_DELAYS.len())];
+            }
+        }
+        settings.apply_audio(audio);
+        (self.save)(*settings);
+    }
+}
+
+impl Game for SettingsMenu {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.draw_text(TITLE, TITLE_POS, (CLR_3, Standard8x10, LeftTop));
+
+        for (i, row) in ROWS.iter().enumerate() {
+            let y = ROWS_Y + i as isize * ROW_STEP;
+            let color = if self.cursor == i { CLR_3 } else { CLR_2 };
+            graphics.draw_text(row.name(), Px(NAME_X, y), (color, Standard4x5, LeftTop));
+            graphics.draw_text(
+                &row.value(&self.settings),
+                Px(VALUE_X, y),
+                (color, Standard4x5, RightTop),
+            );
+        }
+
+        if ROWS[self.cursor].needs_restart() {
+            graphics.draw_text(
+                RESTART_MESSAGE,
+                Px(SCREEN_WIDTH as isize / 2, MESSAGE_Y),
+                (CLR_1, Standard4x5, CenterTop),
+            );
+        }
+
+        self.button_bar.render(graphics, controller);
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + ROWS.len() - 1) % ROWS.len();
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + 1) % ROWS.len();
+            } else if input.held(Action::Left) {
+                self.input_timer.reset();
+                self.change(false, audio);
+            } else if input.held(Action::Right) || input.pressed(Action::Confirm) {
+                self.input_timer.reset();
+                self.change(true, audio);
+            } else if input.pressed(Action::Back) {
+                self.result = Pop;
+            }
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.input_timer.reset();
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+    use crate::settings::DEFAULT_SETTINGS;
+
+    fn menu() -> Harness<SettingsMenu> {
+        Harness::new(SettingsMenu::with_settings(DEFAULT_SETTINGS, |_| {}))
+    }
+
+    /// Press `action` and wait for the menu to accept input again
+    fn tap(harness: &mut Harness<SettingsMenu>, action: Action) {
+        harness.press(action);
+        harness.run(input_delay());
+    }
+
+    fn select(harness: &mut Harness<SettingsMenu>, row: Row) {
+        while ROWS[harness.game.cursor] != row {
+            tap(harness, Action::Down);
+        }
+    }
+
+    #[test]
+    fn volumes_are_clamped() {
+        let mut harness = menu();
+        tap(&mut harness, Action::Right);
+        assert_eq!(harness.game.settings.volume(Channel::Master), VOLUME_STEPS);
+
+        select(&mut harness, Row::Volume(Channel::Sfx));
+        for _ in 0..VOLUME_STEPS + 2 {
+            tap(&mut harness, Action::Left);
+        }
+        assert_eq!(harness.game.settings.volume(Channel::Sfx), 0);
+        tap(&mut harness, Action::Right);
+        assert_eq!(harness.game.settings.volume(Channel::Sfx), 1);
+        assert_eq!(harness.game.settings.volume(Channel::Music), VOLUME_STEPS);
+    }
+
+    #[test]
+    fn choices_cycle() {
+        let mut harness = menu();
+        select(&mut harness, Row::Scale);
+        tap(&mut harness, Action::Right);
+        assert_eq!(harness.game.settings.scale, Scale::Quad);
+        tap(&mut harness, Action::Right);
+        assert_eq!(harness.game.settings.scale, Scale::Native);
+        tap(&mut harness, Action::Left);
+        assert_eq!(harness.game.settings.scale, Scale::Quad);
+
+        select(&mut harness, Row::Vsync);
+        tap(&mut harness, Action::Confirm);
+        assert!(!harness.game.settings.vsync);
+
+        select(&mut harness, Row::InputDelay);
+        for expected in [0.25, 0.3, 0.1] {
+            tap(&mut harness, Action::Right);
+            assert_eq!(harness.game.settings.input_delay, expected);
+        }
+    }
+
+    #[test]
+    fn mute_toggles() {
+        let mut harness = menu();
+        select(&mut harness, Row::Mute);
+        tap(&mut harness, Action::Left);
+        assert!(harness.game.settings.muted);
+        assert_eq!(Row::Mute.value(&harness.game.settings), "ON");
+        tap(&mut harness, Action::Left);
+        assert!(!harness.game.settings.muted);
+    }
+
+    #[test]
+    fn shows_restart_notice() {
+        let restart: Vec<Row> = ROWS.into_iter().filter(Row::needs_restart).collect();
+        assert_eq!(restart, [Row::Scale, Row::Vsync]);
+
+        let mut harness = menu();
+        select(&mut harness, Row::Palette);
+        harness.assert_snapshot("settings_palette");
+        select(&mut harness, Row::Scale);
+        harness.assert_snapshot("settings_scale");
+    }
+
+    #[test]
+    fn back_closes() {
+        let mut harness = menu();
+        assert_eq!(harness.press(Action::Back), Pop);
+    }
+}
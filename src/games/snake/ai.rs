@@ -0,0 +1,205 @@
+use crate::games::snake::{Direction, ARENA_HEIGHT, ARENA_WIDTH};
+use pixels_graphics_lib::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Direction for the snake's next move, used by the attract mode demo
+///
+/// Heads for the nearest reachable fruit as long as the tail can still be reached once it's eaten,
+/// otherwise follows the tail, failing that it moves towards the most open space.
+/// None if every direction is blocked
+pub fn choose_direction(
+    body: &[Coord],
+    fruits: &[Coord],
+    walls: &[Coord],
+    wraps: bool,
+) -> Option<Direction> {
+    let head = body[0];
+    let tail = body[body.len() - 1];
+    let blocked = obstacles(body, walls);
+
+    if let Some(path) = find_path(head, |xy| fruits.contains(&xy), &blocked, wraps) {
+        let after = follow(body, &path);
+        let after_tail = after[after.len() - 1];
+        let escape = find_path(
+            after[0],
+            |xy| xy == after_tail,
+            &obstacles(&after, walls),
+            wraps,
+        );
+        if escape.is_some() {
+            return direction_to(head, path[0], wraps);
+        }
+    }
+
+    if let Some(path) = find_path(head, |xy| xy == tail, &blocked, wraps) {
+        return direction_to(head, path[0], wraps);
+    }
+
+    Direction::ALL
+        .into_iter()
+        .filter_map(|direction| {
+            let next = neighbour(head, direction, wraps)?;
+            (!blocked.contains(&next)).then(|| (direction, space(next, &blocked, wraps)))
+        })
+        .max_by_key(|(_, space)| *space)
+        .map(|(direction, _)| direction)
+}
+
+/// Tile next to `xy`, None if it's outside the arena
+fn neighbour(xy: Coord, direction: Direction, wraps: bool) -> Option<Coord> {
+    let next = xy + direction.delta();
+    if wraps {
+        Some(Coord::new(
+            (next.x - 1).rem_euclid(ARENA_WIDTH as isize) + 1,
+            (next.y - 1).rem_euclid(ARENA_HEIGHT as isize) + 1,
+        ))
+    } else if next.x < 1
+        || next.y < 1
+        || next.x > ARENA_WIDTH as isize
+        || next.y > ARENA_HEIGHT as isize
+    {
+        None
+    } else {
+        Some(next)
+    }
+}
+
+fn direction_to(from: Coord, to: Coord, wraps: bool) -> Option<Direction> {
+    Direction::ALL
+        .into_iter()
+        .find(|direction| neighbour(from, *direction, wraps) == Some(to))
+}
+
+/// Walls and the body, apart from the tail as it moves out of the way
+fn obstacles(body: &[Coord], walls: &[Coord]) -> HashSet<Coord> {
+    walls
+        .iter()
+        .chain(&body[..body.len() - 1])
+        .copied()
+        .collect()
+}
+
+/// Body after moving along `path` and eating the fruit at the end of it
+fn follow(body: &[Coord], path: &[Coord]) -> Vec<Coord> {
+    path.iter()
+        .rev()
+        .chain(body)
+        .take(body.len() + 1)
+        .copied()
+        .collect()
+}
+
+/// Shortest path from `from` to the first tile where `goal` is true, `from` isn't included
+fn find_path(
+    from: Coord,
+    goal: impl Fn(Coord) -> bool,
+    blocked: &HashSet<Coord>,
+    wraps: bool,
+) -> Option<Vec<Coord>> {
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    came_from.insert(from, from);
+    while let Some(xy) = queue.pop_front() {
+        if xy != from && goal(xy) {
+            let mut path = vec![xy];
+            let mut current = xy;
+            while came_from[&current] != from {
+                current = came_from[&current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for direction in Direction::ALL {
+            if let Some(next) = neighbour(xy, direction, wraps) {
+                if !blocked.contains(&next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, xy);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Number of tiles reachable from `from`
+fn space(from: Coord, blocked: &HashSet<Coord>, wraps: bool) -> usize {
+    let mut seen = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(xy) = queue.pop_front() {
+        for direction in Direction::ALL {
+            if let Some(next) = neighbour(xy, direction, wraps) {
+                if !blocked.contains(&next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    seen.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::snake::Direction::*;
+
+    fn line(head: Coord, len: isize) -> Vec<Coord> {
+        (0..len).map(|i| head - Coord::new(i, 0)).collect()
+    }
+
+    #[test]
+    fn heads_for_nearest_fruit() {
+        let body = line(Coord::new(9, 7), 3);
+        let fruits = [Coord::new(9, 2), Coord::new(9, 10)];
+        assert_eq!(choose_direction(&body, &fruits, &[], false), Some(Down));
+        assert_eq!(
+            choose_direction(&body, &[Coord::new(15, 7)], &[], false),
+            Some(Right)
+        );
+    }
+
+    #[test]
+    fn goes_around_walls() {
+        let body = line(Coord::new(9, 7), 3);
+        let walls = [Coord::new(10, 6), Coord::new(10, 7), Coord::new(10, 8)];
+        let direction = choose_direction(&body, &[Coord::new(11, 7)], &walls, false);
+        assert!(matches!(direction, Some(Up) | Some(Down)));
+    }
+
+    #[test]
+    fn uses_wrap() {
+        let body = line(Coord::new(3, 7), 3);
+        assert_eq!(
+            choose_direction(&body, &[Coord::new(ARENA_WIDTH as isize, 8)], &[], true),
+            Some(Down)
+        );
+    }
+
+    #[test]
+    fn wont_trap_itself() {
+        //the fruit is at the end of a one tile dead end, eating it would trap the snake
+        let body = vec![
+            Coord::new(3, 3),
+            Coord::new(2, 3),
+            Coord::new(1, 3),
+            Coord::new(1, 4),
+            Coord::new(1, 5),
+        ];
+        let walls: Vec<Coord> = (1..=3)
+            .map(|x| Coord::new(x, 2))
+            .chain([Coord::new(5, 1), Coord::new(5, 2), Coord::new(5, 3)])
+            .chain([Coord::new(4, 1)])
+            .collect();
+        let direction = choose_direction(&body, &[Coord::new(4, 2)], &walls, false);
+        assert_ne!(direction, Some(Right));
+    }
+
+    #[test]
+    fn blocked_in() {
+        let body = vec![Coord::new(1, 1), Coord::new(1, 2), Coord::new(1, 3)];
+        assert_eq!(
+            choose_direction(&body, &[], &[Coord::new(2, 1)], false),
+            None
+        );
+    }
+}
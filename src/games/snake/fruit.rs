@@ -0,0 +1,141 @@
+use crate::audio::Sound;
+use crate::games::snake::fruit::FruitKind::*;
+use crate::games::snake::SCORE_PER_FRUIT;
+use fastrand::Rng;
+use pixels_graphics_lib::prelude::*;
+
+/// Width and height of special fruit sprites
+pub const SPRITE_SIZE: usize = 7;
+
+/// Relative chance of each kind being picked when fruit spawns
+const WEIGHTS: [(FruitKind, usize); 5] = [
+    (Normal, 70),
+    (Golden, 8),
+    (Slow, 8),
+    (Shrink, 7),
+    (Multiplier, 7),
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FruitKind {
+    /// Grows the snake and speeds it up a little
+    Normal,
+    /// Worth more, disappears quickly
+    Golden,
+    /// Slows the snake down
+    Slow,
+    /// Removes segments instead of growing
+    Shrink,
+    /// Multiplies all points for a while
+    Multiplier,
+}
+
+impl FruitKind {
+    pub fn pick(rng: &mut Rng) -> FruitKind {
+        let total: usize = WEIGHTS.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.usize(0..total);
+        for (kind, weight) in WEIGHTS {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        Normal
+    }
+
+    /// Seconds before uneaten fruit disappears, normal fruit stays until eaten
+    pub fn lifetime(&self) -> Option<f64> {
+        match self {
+            Normal => None,
+            Golden => Some(5.0),
+            Slow | Shrink | Multiplier => Some(10.0),
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        match self {
+            Golden => SCORE_PER_FRUIT * 5,
+            _ => SCORE_PER_FRUIT,
+        }
+    }
+
+    pub fn sound(&self) -> Sound {
+        match self {
+            Normal => Sound::Apple,
+            Golden => Sound::Golden,
+            Slow => Sound::Slow,
+            Shrink => Sound::Shrink,
+            Multiplier => Sound::Bonus,
+        }
+    }
+
+    /// [SPRITE_SIZE] 1 bit sprite, see [draw_sprite][crate::sprite::draw_sprite], normal fruit is drawn as a circle
+    pub fn sprite(&self) -> Option<[u8; SPRITE_SIZE]> {
+        match self {
+            Normal => None,
+            Golden => Some([
+                0b0001000, 0b0011100, 0b1111111, 0b0111110, 0b0011100, 0b0110110, 0b0100010,
+            ]),
+            Slow => Some([
+                0b1111111, 0b0100010, 0b0010100, 0b0001000, 0b0010100, 0b0100010, 0b1111111,
+            ]),
+            Shrink => Some([
+                0b1111111, 0b1000001, 0b1000001, 0b1011101, 0b1000001, 0b1000001, 0b1111111,
+            ]),
+            Multiplier => Some([
+                0b1100011, 0b1110111, 0b0111110, 0b0011100, 0b0111110, 0b1110111, 0b1100011,
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fruit {
+    pub xy: Coord,
+    pub kind: FruitKind,
+    /// Seconds until it disappears, see [FruitKind::lifetime]
+    pub life: Option<f64>,
+}
+
+impl Fruit {
+    pub fn new(xy: Coord, kind: FruitKind) -> Self {
+        Self {
+            xy,
+            kind,
+            life: kind.lifetime(),
+        }
+    }
+}
+
+impl Fruit {
+    /// 1.0 when spawned down to 0.0 when it disappears, None if it doesn't
+    pub fn life_left(&self) -> Option<f64> {
+        Some(self.life? / self.kind.lifetime()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn picks_by_weight() {
+        let mut rng = Rng::with_seed(1);
+        let mut counts = [0; WEIGHTS.len()];
+        for _ in 0..10000 {
+            let kind = FruitKind::pick(&mut rng);
+            counts[WEIGHTS.iter().position(|(k, _)| *k == kind).unwrap()] += 1;
+        }
+        assert!(counts[0] > 6500 && counts[0] < 7500, "{counts:?}");
+        assert!(counts.iter().all(|count| *count > 0));
+    }
+
+    #[test]
+    fn life_left() {
+        let mut fruit = Fruit::new(Coord::new(1, 1), Golden);
+        assert_eq!(fruit.life_left(), Some(1.0));
+        fruit.life = Some(2.5);
+        assert_eq!(fruit.life_left(), Some(0.5));
+        assert_eq!(Fruit::new(Coord::new(1, 1), Normal).life_left(), None);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::games::snake::Direction::*;
+use crate::games::snake::{Direction, ARENA_HEIGHT, ARENA_WIDTH};
+use pixels_graphics_lib::prelude::*;
+use std::sync::OnceLock;
+
+/// Length of the snake at the start of every level
+pub const START_LENGTH: usize = 3;
+
+/// Arena layout and goal, see `assets/snake_levels.txt` for the format
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Level {
+    /// Interior walls, the border isn't included
+    pub walls: Vec<Coord>,
+    /// Head of the snake
+    pub spawn: Coord,
+    pub direction: Direction,
+    /// Level is finished when the snake is this long
+    pub target: usize,
+}
+
+impl Level {
+    /// Snake at the start of the level, head first
+    pub fn body(&self) -> Vec<Coord> {
+        spawn_body(self.spawn, self.direction)
+    }
+}
+
+/// [START_LENGTH] snake with its head at `spawn` and the body trailing behind it
+pub fn spawn_body(spawn: Coord, direction: Direction) -> Vec<Coord> {
+    (0..START_LENGTH as isize)
+        .map(|i| spawn - direction.delta() * i)
+        .collect()
+}
+
+pub fn levels() -> &'static [Level] {
+    static LEVELS: OnceLock<Vec<Level>> = OnceLock::new();
+    LEVELS.get_or_init(|| parse_levels(include_str!("../../../assets/snake_levels.txt")).unwrap())
+}
+
+fn in_arena(xy: Coord) -> bool {
+    xy.x >= 1 && xy.y >= 1 && xy.x <= ARENA_WIDTH as isize && xy.y <= ARENA_HEIGHT as isize
+}
+
+/// Parse every level in `text`, the error includes the line number
+pub fn parse_levels(text: &str) -> Result<Vec<Level>, String> {
+    let mut levels = vec![];
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    while let Some((num, header)) = lines.next() {
+        //comments and blank lines are only allowed between levels as rows can start with a wall
+        if header.is_empty() || header.starts_with('#') {
+            continue;
+        }
+        let target = header
+            .strip_prefix("LEVEL ")
+            .and_then(|target| target.trim().parse::<usize>().ok())
+            .ok_or(format!("Line {num}: expected LEVEL <target length>"))?;
+        let mut walls = vec![];
+        let mut spawn = None;
+        for y in 1..=ARENA_HEIGHT {
+            let (num, row) = lines
+                .next()
+                .ok_or(format!("Line {num}: level needs {ARENA_HEIGHT} rows"))?;
+            if row.chars().count() != ARENA_WIDTH {
+                return Err(format!("Line {num}: rows must be {ARENA_WIDTH} tiles"));
+            }
+            for (x, chr) in row.chars().enumerate() {
+                let xy = Coord::from((x + 1, y));
+                let direction = match chr {
+                    '.' => continue,
+                    '#' => {
+                        walls.push(xy);
+                        continue;
+                    }
+                    '^' => Up,
+                    'v' => Down,
+                    '<' => Left,
+                    '>' => Right,
+                    _ => return Err(format!("Line {num}: unknown tile '{chr}'")),
+                };
+                if spawn.is_some() {
+                    return Err(format!("Line {num}: level has more than one snake"));
+                }
+                spawn = Some((xy, direction));
+            }
+        }
+        let Some((spawn, direction)) = spawn else {
+            return Err(format!("Line {num}: level has no snake"));
+        };
+        let level = Level {
+            walls,
+            spawn,
+            direction,
+            target,
+        };
+        if level
+            .body()
+            .iter()
+            .any(|xy| !in_arena(*xy) || level.walls.contains(xy))
+        {
+            return Err(format!("Line {num}: snake doesn't fit"));
+        }
+        if target <= START_LENGTH {
+            return Err(format!(
+                "Line {num}: target must be more than {START_LENGTH}"
+            ));
+        }
+        levels.push(level);
+    }
+    if levels.is_empty() {
+        return Err(String::from("No levels"));
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn map(rows: &[&str]) -> String {
+        let mut text = String::from("LEVEL 10\n");
+        for y in 0..ARENA_HEIGHT {
+            text.push_str(rows.get(y).unwrap_or(&".................."));
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn built_in_levels_are_valid() {
+        let levels = levels();
+        assert!(levels.len() > 1);
+        assert!(levels[0].walls.is_empty());
+        assert_eq!(
+            levels[0].body(),
+            vec![Coord::new(9, 7), Coord::new(8, 7), Coord::new(7, 7)]
+        );
+    }
+
+    #[test]
+    fn parses_walls_and_spawn() {
+        let levels = parse_levels(&map(&["#.................", "..^..............#"])).unwrap();
+        assert_eq!(
+            levels[0],
+            Level {
+                walls: vec![Coord::new(1, 1), Coord::new(18, 2)],
+                spawn: Coord::new(3, 2),
+                direction: Up,
+                target: 10,
+            }
+        );
+        assert_eq!(
+            levels[0].body(),
+            vec![Coord::new(3, 2), Coord::new(3, 3), Coord::new(3, 4)]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        assert!(parse_levels("").is_err());
+        assert!(parse_levels(&map(&[])).is_err());
+        assert!(parse_levels(&map(&["..>"])).is_err());
+        assert!(parse_levels(&map(&[">................."])).is_err());
+        assert!(parse_levels(&map(&["..>...>..........."])).is_err());
+        assert!(parse_levels(&map(&["..>......x........"])).is_err());
+        assert!(parse_levels(&map(&["#.>..............."])).is_err());
+        assert!(
+            parse_levels(&map(&["..>..............."]).replace("LEVEL 10", "LEVEL 3")).is_err()
+        );
+    }
+}
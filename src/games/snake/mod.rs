@@ -0,0 +1,1475 @@
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::games::snake::fruit::FruitKind::*;
+use crate::games::snake::fruit::{Fruit, FruitKind, SPRITE_SIZE};
+use crate::games::snake::level::{levels, spawn_body, Level, START_LENGTH};
+use crate::games::snake::Direction::*;
+use crate::games::snake::Mode::*;
+use crate::games::snake::State::*;
+use crate::games::GAME_INPUT_DELAY;
+use crate::high_scores::submit_score;
+use crate::input::{Action, Input, Player};
+use crate::sprite::draw_sprite;
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::shapes::CreateDrawable;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::{
+    Center, CenterTop, LeftTop, RightTop,
+};
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::{Standard4x5, Standard8x10};
+use pixels_graphics_lib::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Neg;
+use std::sync::OnceLock;
+
+mod ai;
+mod fruit;
+mod level;
+
+pub const ID: &str = "snake";
+/// High score tables for each [Mode], [Mode::Classic] uses [ID]
+pub const WRAP_ID: &str = "snake_wrap";
+pub const TIMED_ID: &str = "snake_timed";
+pub const ZEN_ID: &str = "snake_zen";
+
+const TILE_SIZE: usize = 8;
+const ARENA_WIDTH: usize = 18;
+const ARENA_HEIGHT: usize = 14;
+const ARENA_START: Coord = Coord::new(0, 16);
+const FRUIT_DELAY: f64 = 5.0;
+const SEED_OFFSET: Coord = Coord::new(0, 14);
+
+fn mid_point() -> Coord {
+    static MID_POINT: OnceLock<Coord> = OnceLock::new();
+    *(MID_POINT.get_or_init(|| {
+        ARENA_START + coord!(ARENA_WIDTH / 2 + 1, ARENA_HEIGHT / 2 + 1) * TILE_SIZE
+    }))
+}
+
+const SPEED_CHANGE_PER_TICK: f64 = 0.001;
+const SPEED_CHANGE_PER_FRUIT: f64 = 0.005;
+const DEFAULT_MOVE_SPEED: f64 = 0.4;
+const MIN_MOVE_SPEED: f64 = 0.07;
+
+const SCORE_PER_TICK: usize = 1;
+const SCORE_PER_FRUIT: usize = 100;
+
+const MAX_FRUIT_ON_SCREEN: usize = 3;
+const DYING_ANIM_RATE: f64 = 0.1;
+/// Seconds the level clear message is shown for
+const LEVEL_CLEAR_DELAY: f64 = 2.0;
+const SCORE_PER_LEVEL: usize = 1000;
+/// Seconds added to the move delay by [FruitKind::Slow]
+const SLOW_DOWN: f64 = 0.1;
+/// Segments removed by [FruitKind::Shrink]
+const SHRINK_LENGTH: usize = 3;
+/// Seconds points are multiplied for after eating [FruitKind::Multiplier]
+const MULTIPLIER_LENGTH: f64 = 10.0;
+const MULTIPLIER: usize = 2;
+/// Most turns that can be queued, presses after this are ignored
+const MAX_QUEUED_TURNS: usize = 3;
+/// Shown under the score while the multiplier is active
+const MULTIPLIER_BAR_POS: Coord = Coord::new(3, 14);
+const MULTIPLIER_BAR_WIDTH: usize = 116;
+/// Seconds in a [Mode::Timed] game
+const TIME_ATTACK_LENGTH: f64 = 90.0;
+/// Round wins needed to win a [Mode::Versus] match
+const ROUNDS_TO_WIN: usize = 3;
+/// Head and starting direction of each player's snake in [Mode::Versus]
+const VERSUS_SPAWNS: [(Coord, Direction); 2] =
+    [(Coord::new(5, 4), Right), (Coord::new(14, 11), Left)];
+/// Distance from the screen edge to each player's score, next to their round wins
+const VERSUS_SCORE_X: isize = 58;
+const SETUP_PANEL: (isize, isize, isize, isize) = (15, 8, 144, 126);
+const SETUP_TITLE_Y: isize = 16;
+const SETUP_Y: isize = 36;
+const SETUP_STEP: isize = 14;
+const SETUP_DESC_Y: isize = 110;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Choosing the [Mode]
+    Setup,
+    Playing,
+    /// Level target reached, next level starts after [LEVEL_CLEAR_DELAY]
+    Cleared,
+    /// Last level cleared, or a player won the [Mode::Versus] match
+    Won,
+    /// A snake crashed in [Mode::Versus], next round starts after [LEVEL_CLEAR_DELAY]
+    RoundOver,
+    Dying,
+    Dead,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    /// Die on walls, play through every level
+    Classic,
+    /// Classic, but leaving the arena comes back on the other side
+    Wrap,
+    /// First level only, the snake doesn't grow, score as much as possible in [TIME_ATTACK_LENGTH]
+    Timed,
+    /// First level only, edges wrap and running into the snake cuts it instead of killing it,
+    /// the player decides when to finish
+    Zen,
+    /// Two snakes in the empty arena, the last one alive wins the round,
+    /// first to [ROUNDS_TO_WIN] rounds wins
+    Versus,
+}
+
+impl Mode {
+    const ALL: [Mode; 5] = [Classic, Wrap, Timed, Zen, Versus];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Classic => "CLASSIC",
+            Wrap => "WRAP",
+            Timed => "TIMED",
+            Zen => "ZEN",
+            Versus => "VERSUS",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Classic => "GROW TO CLEAR EACH LEVEL",
+            Wrap => "THE EDGES WRAP AROUND",
+            Timed => "NO GROWING, 90 SECONDS",
+            Zen => "NO DYING, END ANY TIME",
+            Versus => "2 PLAYERS, BEST OF 5",
+        }
+    }
+
+    /// High score table id, [Mode::Versus] isn't scored
+    fn table(&self) -> Option<&'static str> {
+        match self {
+            Classic => Some(ID),
+            Wrap => Some(WRAP_ID),
+            Timed => Some(TIMED_ID),
+            Zen => Some(ZEN_ID),
+            Versus => None,
+        }
+    }
+
+    fn wraps(&self) -> bool {
+        matches!(self, Wrap | Zen)
+    }
+
+    fn has_levels(&self) -> bool {
+        matches!(self, Classic | Wrap)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Direction {
+    Up,
+    Left,
+    Right,
+    Down,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [Up, Left, Right, Down];
+
+    fn from_action(action: Action) -> Option<Direction> {
+        match action {
+            Action::Up => Some(Up),
+            Action::Left => Some(Left),
+            Action::Right => Some(Right),
+            Action::Down => Some(Down),
+            _ => None,
+        }
+    }
+
+    fn action(&self) -> Action {
+        match self {
+            Up => Action::Up,
+            Left => Action::Left,
+            Right => Action::Right,
+            Down => Action::Down,
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Up => Down,
+            Left => Right,
+            Right => Left,
+            Down => Up,
+        }
+    }
+
+    pub fn delta(&self) -> Coord {
+        match self {
+            Up => Coord::new(0, -1),
+            Left => Coord::new(-1, 0),
+            Right => Coord::new(1, 0),
+            Down => Coord::new(0, 1),
+        }
+    }
+}
+
+/// One player's snake, [Mode::Versus] has two
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Serpent {
+    /// Head first
+    body: Vec<Coord>,
+    /// Direction of the last move
+    direction: Direction,
+    /// Turns to make, one per move
+    turns: VecDeque<Direction>,
+    /// Directions pressed on the keyboard since the last update, in order, see [Game::on_press]
+    presses: Vec<Direction>,
+    /// Kept between levels and [Mode::Versus] rounds
+    score: usize,
+}
+
+impl Serpent {
+    fn new(body: Vec<Coord>, direction: Direction) -> Self {
+        Self {
+            body,
+            direction,
+            turns: VecDeque::new(),
+            presses: vec![],
+            score: 0,
+        }
+    }
+
+    /// Add `direction` to the turn buffer, turns that reverse or repeat the last queued
+    /// direction are ignored
+    fn queue_turn(&mut self, direction: Direction) {
+        let last = self.turns.back().copied().unwrap_or(self.direction);
+        if self.turns.len() < MAX_QUEUED_TURNS && direction != last && direction != last.opposite()
+        {
+            self.turns.push_back(direction);
+        }
+    }
+
+    /// Queue turns for keys pressed since the last update, then for any other direction just
+    /// pressed by `player` (controllers don't send key presses)
+    fn queue_turns(&mut self, input: &Input, player: Player) {
+        let mut pressed = std::mem::take(&mut self.presses);
+        for direction in Direction::ALL {
+            if input.pressed_by(player, direction.action()) && !pressed.contains(&direction) {
+                pressed.push(direction);
+            }
+        }
+        for direction in pressed {
+            self.queue_turn(direction);
+        }
+    }
+}
+
+pub struct Snake {
+    fruit: Drawable<Circle>,
+    segment: Drawable<Rect>,
+    /// Player two's segments in [Mode::Versus]
+    rival_segment: Drawable<Rect>,
+    wall: Drawable<Rect>,
+    /// Player one first
+    snakes: Vec<Serpent>,
+    fruits: Vec<Fruit>,
+    next_fruit_spawn: Timer,
+    result: GameUpdateResult,
+    move_speed: f64,
+    next_move: f64,
+    state: State,
+    mode: Mode,
+    /// Index into [Mode::ALL] while in [State::Setup]
+    cursor: usize,
+    /// Seconds left in a [Mode::Timed] game
+    time_left: f64,
+    /// Seconds left of [FruitKind::Multiplier]
+    multiplier_left: f64,
+    /// Rounds won by each player in [Mode::Versus]
+    wins: [usize; 2],
+    /// Index into [Serpent]s of the last round's winner, None if it was a draw
+    round_winner: Option<usize>,
+    /// Steered by [ai] instead of the player, for the attract mode demo, scores aren't saved
+    autopilot: bool,
+    /// Index into [levels]
+    level: usize,
+    /// Also the delay between [Mode::Versus] rounds
+    next_level: Timer,
+    next_dying_anim: Timer,
+    input_timer: Timer,
+    button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
+}
+
+impl Snake {
+    pub fn new(seed: u64) -> Box<Self> {
+        let fruit = Drawable::from_obj(
+            Circle::new((TILE_SIZE / 2, TILE_SIZE / 2), TILE_SIZE / 2 - 1),
+            fill(CLR_3),
+        );
+        let segment = Drawable::from_obj(
+            Rect::new((0, 0), (TILE_SIZE - 2, TILE_SIZE - 2)),
+            fill(CLR_3),
+        );
+        let rival_segment = Drawable::from_obj(
+            Rect::new((0, 0), (TILE_SIZE - 2, TILE_SIZE - 2)),
+            fill(CLR_2),
+        );
+        let wall = Drawable::from_obj(
+            Rect::new((0, 0), (TILE_SIZE - 2, TILE_SIZE - 2)),
+            fill(CLR_1),
+        );
+        let mut snake = Self {
+            fruit,
+            segment,
+            rival_segment,
+            wall,
+            snakes: vec![],
+            fruits: vec![],
+            next_fruit_spawn: Timer::new(FRUIT_DELAY / 3.0),
+            next_move: 0.0,
+            move_speed: DEFAULT_MOVE_SPEED,
+            result: Nothing,
+            state: Setup,
+            mode: Classic,
+            cursor: 0,
+            time_left: TIME_ATTACK_LENGTH,
+            multiplier_left: 0.0,
+            next_dying_anim: Timer::new(DYING_ANIM_RATE),
+            wins: [0; 2],
+            round_winner: None,
+            autopilot: false,
+            level: 0,
+            next_level: Timer::new_once(LEVEL_CLEAR_DELAY),
+            input_timer: Timer::new(GAME_INPUT_DELAY),
+            button_bar: ButtonBar::new(
+                coord!(0, SCREEN_HEIGHT - BAR_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("PAUSE", ButtonDef::Back),
+                    ("MODE", ButtonDef::Vert),
+                    ("START", ButtonDef::Confirm),
+                ],
+            ),
+            seed,
+            rng: Rng::with_seed(seed),
+        };
+        //so the arena is shown behind the mode list
+        snake.start_level(0);
+        snake.state = Setup;
+        Box::new(snake)
+    }
+
+    /// Classic game played by [ai]
+    pub fn demo(seed: u64) -> Box<Self> {
+        let mut snake = Snake::new(seed);
+        snake.autopilot = true;
+        snake.start(Classic);
+        snake
+    }
+}
+
+impl Snake {
+    fn level(&self) -> &'static Level {
+        &levels()[self.level]
+    }
+
+    fn start(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.time_left = TIME_ATTACK_LENGTH;
+        self.wins = [0; 2];
+        self.snakes.clear();
+        let mut buttons = vec![("PAUSE", ButtonDef::Back), ("SNAKE", ButtonDef::Cursor)];
+        if mode == Zen {
+            buttons.push(("FINISH", ButtonDef::Confirm));
+        }
+        self.button_bar.set_buttons(&buttons);
+        self.start_level(0);
+    }
+
+    /// Game over, without the dying animation
+    fn finish(&mut self) {
+        self.fruits.clear();
+        self.state = Dead;
+        self.submit_score();
+    }
+
+    fn submit_score(&mut self) {
+        if self.autopilot {
+            return;
+        }
+        if let Some(table) = self.mode.table() {
+            if self.result == Nothing {
+                self.result = submit_score(table, self.snakes[0].score);
+            }
+        }
+    }
+
+    fn add_score(&mut self, idx: usize, points: usize) {
+        if self.multiplier_left > 0.0 {
+            self.snakes[idx].score += points * MULTIPLIER;
+        } else {
+            self.snakes[idx].score += points;
+        }
+    }
+
+    /// Snake `idx` eats `fruit`
+    fn eat(&mut self, idx: usize, fruit: Fruit, audio: &mut Audio) {
+        audio.play(fruit.kind.sound());
+        self.add_score(idx, fruit.kind.score());
+        match fruit.kind {
+            Normal | Golden => self.move_speed -= SPEED_CHANGE_PER_FRUIT,
+            Slow => self.move_speed = (self.move_speed + SLOW_DOWN).min(DEFAULT_MOVE_SPEED),
+            Shrink => {
+                let body = &mut self.snakes[idx].body;
+                let len = body.len().saturating_sub(SHRINK_LENGTH);
+                body.truncate(len.max(START_LENGTH));
+            }
+            Multiplier => self.multiplier_left = MULTIPLIER_LENGTH,
+        }
+    }
+
+    /// Crashing into `tile` would kill a snake, heads moving onto the same tile are checked
+    /// separately
+    fn blocked(&self, tile: Coord) -> bool {
+        tile.x == 0
+            || tile.y == 0
+            || tile.x == ARENA_WIDTH as isize + 1
+            || tile.y == ARENA_HEIGHT as isize + 1
+            || self.snakes.iter().any(|snake| snake.body.contains(&tile))
+            || self.level().walls.contains(&tile)
+    }
+
+    /// Score the [Mode::Versus] round, `crashed` has an entry for each snake
+    fn end_round(&mut self, crashed: &[bool]) {
+        let alive: Vec<usize> = (0..crashed.len()).filter(|i| !crashed[*i]).collect();
+        self.round_winner = if alive.len() == 1 {
+            Some(alive[0])
+        } else {
+            None
+        };
+        if let Some(winner) = self.round_winner {
+            self.wins[winner] += 1;
+        }
+        self.fruits.clear();
+        if self.wins.iter().any(|wins| *wins >= ROUNDS_TO_WIN) {
+            self.state = Won;
+        } else {
+            self.state = RoundOver;
+            self.next_level.reset();
+        }
+    }
+
+    /// Tile next to `from`, wrapping around the arena if the mode allows it
+    fn step(&self, from: Coord, direction: Direction) -> Coord {
+        let next = from + direction.delta();
+        if self.mode.wraps() {
+            Coord::new(
+                (next.x - 1).rem_euclid(ARENA_WIDTH as isize) + 1,
+                (next.y - 1).rem_euclid(ARENA_HEIGHT as isize) + 1,
+            )
+        } else {
+            next
+        }
+    }
+
+    fn update_setup(&mut self, timing: &Timing, input: &Input) {
+        if self.input_timer.update(timing) {
+            if input.held(Action::Up) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + Mode::ALL.len() - 1) % Mode::ALL.len();
+            } else if input.held(Action::Down) {
+                self.input_timer.reset();
+                self.cursor = (self.cursor + 1) % Mode::ALL.len();
+            } else if input.pressed(Action::Confirm) {
+                self.start(Mode::ALL[self.cursor]);
+            }
+        }
+    }
+
+    fn render_setup(&self, graphics: &mut Graphics) {
+        let (x1, y1, x2, y2) = SETUP_PANEL;
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+        graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+        graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+        graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+        graphics.draw_text(
+            "SNAKE",
+            TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_TITLE_Y),
+            (CLR_3, Standard8x10, CenterTop),
+        );
+        for (i, mode) in Mode::ALL.iter().enumerate() {
+            let (text, color) = if self.cursor == i {
+                (format!("< {} >", mode.name()), CLR_3)
+            } else {
+                (mode.name().to_string(), CLR_1)
+            };
+            graphics.draw_text(
+                &text,
+                TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_Y + i as isize * SETUP_STEP),
+                (color, Standard8x10, CenterTop),
+            );
+        }
+        graphics.draw_text(
+            Mode::ALL[self.cursor].description(),
+            TextPos::Px(SCREEN_WIDTH as isize / 2, SETUP_DESC_Y),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+    }
+
+    fn render_hud(&self, graphics: &mut Graphics) {
+        if self.mode == Versus {
+            graphics.draw_text(
+                &format!("P1: {}", self.wins[0]),
+                TextPos::Px(3, 3),
+                (CLR_3, Standard8x10, LeftTop),
+            );
+            graphics.draw_text(
+                &format!("{}", self.snakes[0].score),
+                TextPos::Px(VERSUS_SCORE_X, 6),
+                (CLR_3, Standard4x5, LeftTop),
+            );
+            graphics.draw_text(
+                &format!("P2: {}", self.wins[1]),
+                TextPos::Px(SCREEN_WIDTH as isize - 3, 3),
+                (CLR_2, Standard8x10, RightTop),
+            );
+            graphics.draw_text(
+                &format!("{}", self.snakes[1].score),
+                TextPos::Px(SCREEN_WIDTH as isize - VERSUS_SCORE_X, 6),
+                (CLR_2, Standard4x5, RightTop),
+            );
+            return;
+        }
+        graphics.draw_text(
+            &format!("SCORE: {: >6}", self.snakes[0].score),
+            TextPos::Px(3, 3),
+            (
+                if self.state != Playing { CLR_3 } else { CLR_2 },
+                Standard8x10,
+                LeftTop,
+            ),
+        );
+        if self.multiplier_left > 0.0 {
+            let width = MULTIPLIER_BAR_WIDTH as f64 * self.multiplier_left / MULTIPLIER_LENGTH;
+            graphics.draw_rect(
+                Rect::new_with_size(MULTIPLIER_BAR_POS, width.ceil() as usize, 1),
+                fill(CLR_3),
+            );
+        }
+        let (first, second) = match self.mode {
+            Classic | Wrap => (
+                format!("LEVEL {}", self.level + 1),
+                format!("{}/{}", self.snakes[0].body.len(), self.level().target),
+            ),
+            Timed => (
+                String::from("TIME"),
+                format!("{}S", self.time_left.max(0.0).ceil()),
+            ),
+            Zen => (
+                String::from("ZEN"),
+                format!("{}", self.snakes[0].body.len()),
+            ),
+            Versus => return,
+        };
+        graphics.draw_text(
+            &first,
+            TextPos::Px(SCREEN_WIDTH as isize - 3, 3),
+            (CLR_2, Standard4x5, RightTop),
+        );
+        graphics.draw_text(
+            &second,
+            TextPos::Px(SCREEN_WIDTH as isize - 3, 9),
+            (CLR_2, Standard4x5, RightTop),
+        );
+    }
+
+    /// Reset the snake, fruit and speed for level `idx`, the score is kept
+    fn start_level(&mut self, idx: usize) {
+        self.level = idx;
+        let level = self.level();
+        let scores: Vec<usize> = self.snakes.iter().map(|snake| snake.score).collect();
+        self.snakes = if self.mode == Versus {
+            VERSUS_SPAWNS
+                .iter()
+                .map(|(spawn, direction)| Serpent::new(spawn_body(*spawn, *direction), *direction))
+                .collect()
+        } else {
+            vec![Serpent::new(level.body(), level.direction)]
+        };
+        for (snake, score) in self.snakes.iter_mut().zip(scores) {
+            snake.score = score;
+        }
+        self.fruits.clear();
+        self.next_fruit_spawn = Timer::new(FRUIT_DELAY / 3.0);
+        self.next_move = 0.0;
+        self.move_speed = DEFAULT_MOVE_SPEED;
+        self.multiplier_left = 0.0;
+        self.state = Playing;
+    }
+
+    fn find_empty_slot(&mut self) -> Option<Coord> {
+        for _ in 0..200 {
+            let x = self.rng.usize(1..ARENA_WIDTH);
+            let y = self.rng.usize(1..ARENA_HEIGHT);
+            let xy = Coord::from((x, y));
+            if !self.fruits.iter().any(|fruit| fruit.xy == xy)
+                && !self.snakes.iter().any(|snake| snake.body.contains(&xy))
+                && !self.level().walls.contains(&xy)
+            {
+                return Some(xy);
+            }
+        }
+        None
+    }
+}
+
+impl Game for Snake {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.update_translate(ARENA_START + (1, 0));
+
+        self.button_bar.render(graphics, controller);
+
+        let wall_horz_size = ARENA_WIDTH + 1;
+        let wall_vert_size = ARENA_HEIGHT + 1;
+
+        for x in 0..=wall_horz_size {
+            graphics.draw(&self.wall.with_move(((x * TILE_SIZE) as isize, 0)));
+            graphics.draw(&self.wall.with_move((
+                (x * TILE_SIZE) as isize,
+                (wall_vert_size * TILE_SIZE) as isize,
+            )));
+        }
+        for y in 0..=wall_vert_size {
+            graphics.draw(&self.wall.with_move((0, (y * TILE_SIZE) as isize)));
+            graphics.draw(&self.wall.with_move((
+                (wall_horz_size * TILE_SIZE) as isize,
+                (y * TILE_SIZE) as isize,
+            )));
+        }
+
+        for wall in &self.level().walls {
+            let xy = *wall * (TILE_SIZE, TILE_SIZE);
+            graphics.draw(&self.wall.with_move(xy));
+        }
+
+        for (i, snake) in self.snakes.iter().enumerate() {
+            let segment = if i == 0 {
+                &self.segment
+            } else {
+                &self.rival_segment
+            };
+            for xy in &snake.body {
+                graphics.draw(&segment.with_move(*xy * (TILE_SIZE, TILE_SIZE)));
+            }
+        }
+
+        for fruit in &self.fruits {
+            let xy = fruit.xy * (TILE_SIZE, TILE_SIZE);
+            match fruit.kind.sprite() {
+                Some(sprite) => draw_sprite(graphics, xy, &sprite, SPRITE_SIZE, CLR_3),
+                None => graphics.draw(&self.fruit.with_move(xy + (TILE_SIZE / 2, TILE_SIZE / 2))),
+            }
+            if let Some(life) = fruit.life_left() {
+                let width = (SPRITE_SIZE as f64 * life).ceil() as usize;
+                graphics.draw_line(
+                    xy + (0, SPRITE_SIZE),
+                    xy + (width as isize - 1, SPRITE_SIZE as isize),
+                    CLR_2,
+                );
+            }
+        }
+
+        graphics.update_translate((ARENA_START + (1, 0)).neg());
+
+        if self.state == Setup {
+            self.render_setup(graphics);
+            return;
+        }
+
+        self.render_hud(graphics);
+
+        match self.state {
+            Setup => {}
+            Playing => {}
+            Cleared => {
+                let x1 = 35;
+                let y1 = 67;
+                let x2 = 120;
+                let y2 = 100;
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+                graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+                graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+                graphics.draw_text(
+                    "CLEAR!",
+                    TextPos::px(mid_point()),
+                    (CLR_3, Standard8x10, Center),
+                );
+                graphics.draw_text(
+                    &format!("NEXT: LEVEL {}", self.level + 2),
+                    TextPos::px(mid_point() + SEED_OFFSET),
+                    (CLR_2, Standard4x5, Center),
+                );
+            }
+            RoundOver => {
+                let x1 = 15;
+                let y1 = 67;
+                let x2 = 140;
+                let y2 = 100;
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+                graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+                graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+                let title = match self.round_winner {
+                    Some(winner) => format!("P{} WINS ROUND", winner + 1),
+                    None => String::from("DRAW"),
+                };
+                graphics.draw_text(
+                    &title,
+                    TextPos::px(mid_point()),
+                    (CLR_3, Standard8x10, Center),
+                );
+                graphics.draw_text(
+                    &format!("{} - {}", self.wins[0], self.wins[1]),
+                    TextPos::px(mid_point() + SEED_OFFSET),
+                    (CLR_2, Standard4x5, Center),
+                );
+            }
+            Won => {
+                let x1 = 35;
+                let y1 = 67;
+                let x2 = 120;
+                let y2 = 100;
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+                graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+                graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+                let (title, subtitle) = if self.mode == Versus {
+                    let winner = if self.wins[0] > self.wins[1] { 1 } else { 2 };
+                    (
+                        format!("P{winner} WINS!"),
+                        format!("{} - {}", self.wins[0], self.wins[1]),
+                    )
+                } else {
+                    (String::from("YOU WIN!"), format!("SEED {}", self.seed))
+                };
+                graphics.draw_text(
+                    &title,
+                    TextPos::px(mid_point()),
+                    (CLR_3, Standard8x10, Center),
+                );
+                graphics.draw_text(
+                    &subtitle,
+                    TextPos::px(mid_point() + SEED_OFFSET),
+                    (CLR_2, Standard4x5, Center),
+                );
+            }
+            Dying => {}
+            Dead => {
+                let x1 = 15;
+                let y1 = 67;
+                let x2 = 140;
+                let y2 = 100;
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), fill(CLR_0));
+                graphics.draw_rect(Rect::new((x1, y1), (x2, y2)), stroke(CLR_3));
+                graphics.draw_rect(Rect::new((x1 + 1, y1 + 1), (x2 - 1, y2 - 1)), stroke(CLR_2));
+                graphics.draw_rect(Rect::new((x1 + 2, y1 + 2), (x2 - 2, y2 - 2)), stroke(CLR_1));
+                let title = match self.mode {
+                    Timed if self.time_left <= 0.0 => "TIME UP!",
+                    Zen => "FINISHED!",
+                    _ => "YOU'RE DEAD!",
+                };
+                graphics.draw_text(
+                    title,
+                    TextPos::px(mid_point()),
+                    (CLR_3, Standard8x10, Center),
+                );
+                graphics.draw_text(
+                    &format!("SEED {}", self.seed),
+                    TextPos::px(mid_point() + SEED_OFFSET),
+                    (CLR_2, Standard4x5, Center),
+                );
+            }
+        }
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn on_press(&mut self, player: Player, action: Action) {
+        let idx = match player {
+            Player::One => 0,
+            Player::Two => 1,
+        };
+        if let (Some(snake), Some(direction)) =
+            (self.snakes.get_mut(idx), Direction::from_action(action))
+        {
+            snake.presses.push(direction);
+        }
+    }
+
+    #[allow(clippy::collapsible_if)] //for readability
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        for (i, snake) in self.snakes.iter_mut().enumerate() {
+            if self.state == Playing {
+                let player = if i == 0 { Player::One } else { Player::Two };
+                snake.queue_turns(input, player);
+            } else {
+                snake.presses.clear();
+            }
+        }
+
+        match self.state {
+            Setup => self.update_setup(timing, input),
+            Playing => {
+                if self.mode == Zen && input.pressed(Action::Confirm) {
+                    self.finish();
+                    return self.result;
+                }
+                if self.mode == Timed {
+                    self.time_left -= timing.fixed_time_step;
+                    if self.time_left <= 0.0 {
+                        self.finish();
+                        return self.result;
+                    }
+                }
+
+                if self.mode.has_levels() && self.snakes[0].body.len() >= self.level().target {
+                    self.snakes[0].score += SCORE_PER_LEVEL;
+                    if self.level + 1 < levels().len() {
+                        self.state = Cleared;
+                        self.next_level.reset();
+                    } else {
+                        self.state = Won;
+                        self.submit_score();
+                    }
+                    return self.result;
+                }
+
+                self.multiplier_left = (self.multiplier_left - timing.fixed_time_step).max(0.0);
+                for fruit in &mut self.fruits {
+                    if let Some(life) = &mut fruit.life {
+                        *life -= timing.fixed_time_step;
+                    }
+                }
+                self.fruits
+                    .retain(|fruit| fruit.life.map(|life| life > 0.0).unwrap_or(true));
+
+                if self.fruits.len() < MAX_FRUIT_ON_SCREEN {
+                    if self.next_fruit_spawn.update(timing) {
+                        if let Some(empty) = self.find_empty_slot() {
+                            //special fruit would only help whoever's closest
+                            let kind = if self.mode == Versus {
+                                Normal
+                            } else {
+                                FruitKind::pick(&mut self.rng)
+                            };
+                            self.fruits.push(Fruit::new(empty, kind));
+                        }
+                    }
+                }
+
+                if self.autopilot && self.next_move < 0.0 {
+                    let fruits: Vec<Coord> = self.fruits.iter().map(|fruit| fruit.xy).collect();
+                    let direction = ai::choose_direction(
+                        &self.snakes[0].body,
+                        &fruits,
+                        &self.level().walls,
+                        self.mode.wraps(),
+                    );
+                    if let Some(direction) = direction {
+                        self.snakes[0].turns = VecDeque::from([direction]);
+                    }
+                }
+
+                if self.next_move < 0.0 {
+                    let mut heads = vec![];
+                    for snake in &mut self.snakes {
+                        if let Some(turn) = snake.turns.pop_front() {
+                            snake.direction = turn;
+                        }
+                    }
+                    for snake in &self.snakes {
+                        heads.push(self.step(snake.body[0], snake.direction));
+                    }
+                    if self.mode == Zen {
+                        let body = &mut self.snakes[0].body;
+                        if let Some(i) = body.iter().position(|xy| xy == &heads[0]) {
+                            body.truncate(i);
+                        }
+                    }
+                    //heads meeting on the same tile both crash, swapping places is caught by
+                    //the body check
+                    let crashed: Vec<bool> = heads
+                        .iter()
+                        .enumerate()
+                        .map(|(i, head)| {
+                            self.blocked(*head)
+                                || heads
+                                    .iter()
+                                    .enumerate()
+                                    .any(|(j, other)| i != j && other == head)
+                        })
+                        .collect();
+                    if crashed.contains(&true) {
+                        audio.play(Sound::Death);
+                        if self.mode == Versus {
+                            self.end_round(&crashed);
+                        } else {
+                            self.state = Dying;
+                        }
+                        return self.result;
+                    }
+
+                    let mut ate = vec![false; heads.len()];
+                    for (i, head) in heads.into_iter().enumerate() {
+                        if let Some(f) = self.fruits.iter().position(|fruit| fruit.xy == head) {
+                            let fruit = self.fruits.remove(f);
+                            if self.mode == Timed || fruit.kind == Shrink {
+                                self.snakes[i].body.pop();
+                            }
+                            self.snakes[i].body.insert(0, head);
+                            self.eat(i, fruit, audio);
+                            ate[i] = true;
+                            if self.fruits.is_empty() {
+                                self.next_fruit_spawn.trigger();
+                                self.next_fruit_spawn.delay(0.2);
+                            }
+                        } else {
+                            self.snakes[i].body.pop();
+                            self.snakes[i].body.insert(0, head);
+                        }
+                    }
+                    self.next_move = self.move_speed;
+                    for (i, ate) in ate.iter().enumerate() {
+                        if !ate {
+                            self.add_score(i, SCORE_PER_TICK);
+                        }
+                    }
+                    if !ate.contains(&true) {
+                        self.move_speed -= SPEED_CHANGE_PER_TICK;
+                    }
+                    self.move_speed = self.move_speed.max(MIN_MOVE_SPEED);
+                }
+                self.next_move -= timing.fixed_time_step;
+            }
+            Cleared => {
+                if self.next_level.update(timing) {
+                    self.start_level(self.level + 1);
+                }
+            }
+            RoundOver => {
+                if self.next_level.update(timing) {
+                    self.start_level(0);
+                }
+            }
+            Won => {}
+            Dying => {
+                self.fruits.clear();
+                if self.next_dying_anim.update(timing) {
+                    if self.snakes[0].body.is_empty() {
+                        self.state = Dead;
+                        self.submit_score();
+                    } else {
+                        self.snakes[0].body.pop();
+                    }
+                }
+            }
+            Dead => {}
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.input_timer.reset();
+        self.result = Nothing;
+    }
+
+    fn players(&self) -> usize {
+        if self.mode == Versus {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+    use crate::replay::ReplayEvent;
+
+    fn snake() -> Harness<Snake> {
+        snake_in(Classic)
+    }
+
+    fn snake_in(mode: Mode) -> Harness<Snake> {
+        let mut harness = Harness::new(Snake::new(1));
+        harness.game.start(mode);
+        //stop fruit appearing
+        harness.game.next_fruit_spawn = Timer::new_once(1000.0);
+        harness
+    }
+
+    #[test]
+    fn hits_wall() {
+        let mut harness = snake();
+        harness.run_until(10.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Dying);
+        assert_eq!(
+            harness.game.snakes[0].body[0],
+            Coord::new(ARENA_WIDTH as isize, 7)
+        );
+    }
+
+    #[test]
+    fn hits_self() {
+        let mut harness = snake();
+        harness.game.snakes[0].body = vec![
+            Coord::new(5, 5),
+            Coord::new(5, 6),
+            Coord::new(6, 6),
+            Coord::new(6, 5),
+            Coord::new(7, 5),
+        ];
+        harness.game.snakes[0].direction = Right;
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Dying);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(5, 5));
+    }
+
+    #[test]
+    fn dies_then_shows_game_over() {
+        let mut harness = snake();
+        harness.run_until(15.0, |game| game.state == Dead);
+        assert!(harness.game.snakes[0].body.is_empty());
+        harness.assert_snapshot("snake_dead");
+    }
+
+    #[test]
+    fn cant_reverse() {
+        let mut harness = snake();
+        harness.press(Action::Left);
+        assert!(harness.game.snakes[0].turns.is_empty());
+        harness.press(Action::Up);
+        harness.run_until(1.0, |game| game.snakes[0].turns.is_empty());
+        assert_eq!(harness.game.snakes[0].direction, Up);
+    }
+
+    #[test]
+    fn queues_quick_turns() {
+        let mut harness = snake();
+        //time for both presses before the first move
+        harness.game.next_move = 0.5;
+        let head = harness.game.snakes[0].body[0];
+        harness.press(Action::Up);
+        harness.press(Action::Left);
+        assert_eq!(harness.game.snakes[0].direction, Right);
+        assert_eq!(harness.game.snakes[0].turns, [Up, Left]);
+        harness.run_until(1.0, |game| game.snakes[0].turns.is_empty());
+        harness.run_until(1.0, |game| game.snakes[0].body[0] != head + Up.delta());
+        assert_eq!(harness.game.snakes[0].direction, Left);
+        assert_eq!(
+            harness.game.snakes[0].body[0],
+            head + Up.delta() + Left.delta()
+        );
+    }
+
+    #[test]
+    fn cant_reverse_queued_turn() {
+        let mut harness = snake();
+        harness.game.next_move = 1000.0;
+        harness.press(Action::Up);
+        harness.press(Action::Down);
+        harness.press(Action::Up);
+        assert_eq!(harness.game.snakes[0].turns, [Up]);
+    }
+
+    #[test]
+    fn limits_queued_turns() {
+        let mut harness = snake();
+        harness.game.next_move = 1000.0;
+        for action in [Action::Up, Action::Left, Action::Down, Action::Right] {
+            harness.press(action);
+        }
+        assert_eq!(harness.game.snakes[0].turns, [Up, Left, Down]);
+    }
+
+    #[test]
+    fn turns_without_key_presses() {
+        let mut harness = snake();
+        harness.hold(Action::Down);
+        //controllers don't send key presses
+        harness.game.snakes[0].presses.clear();
+        harness.tick();
+        assert_eq!(harness.game.snakes[0].turns, [Down]);
+    }
+
+    #[test]
+    fn replays_quick_taps() {
+        let mut harness = Harness::new(Snake::new(3));
+        harness.record(ID, 3);
+        harness.hold(Action::Confirm);
+        harness.run_until(1.0, |game| game.state == Playing);
+        harness.release(Action::Confirm);
+        //each tap is released before the next update, some share an update and repeat keys
+        let taps: [&[Action]; 3] = [
+            &[Action::Up, Action::Left],
+            &[Action::Down],
+            &[Action::Right, Action::Up, Action::Right],
+        ];
+        for actions in taps {
+            for action in actions {
+                harness.tap(*action);
+            }
+            harness.run(0.4);
+        }
+        harness.run(1.0);
+        let replay = harness.replay();
+
+        let mut replayed = Harness::new(Snake::new(3));
+        replayed.play(&replay);
+        assert_eq!(replayed.game.snakes, harness.game.snakes);
+        assert_eq!(replayed.game.state, harness.game.state);
+
+        //the taps aren't in the recorded input masks
+        let mut untapped = replay.clone();
+        untapped
+            .events
+            .retain(|event| !matches!(event, ReplayEvent::Press { .. }));
+        let mut replayed = Harness::new(Snake::new(3));
+        replayed.play(&untapped);
+        assert_ne!(replayed.game.snakes, harness.game.snakes);
+    }
+
+    #[test]
+    fn eats_fruit() {
+        let mut harness = snake();
+        harness.game.fruits = vec![Fruit::new(Coord::new(10, 7), Normal)];
+        harness.run_until(1.0, |game| game.snakes[0].body.len() > 3);
+        assert!(harness.game.fruits.is_empty());
+        assert_eq!(harness.game.snakes[0].body.len(), 4);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(10, 7));
+        assert_eq!(harness.game.snakes[0].score, SCORE_PER_FRUIT);
+        assert_eq!(
+            harness.game.move_speed,
+            DEFAULT_MOVE_SPEED - SPEED_CHANGE_PER_FRUIT
+        );
+    }
+
+    #[test]
+    fn golden_fruit_disappears() {
+        let mut harness = snake();
+        harness.game.fruits = vec![Fruit::new(Coord::new(2, 2), Golden)];
+        //stop the snake hitting the wall
+        harness.game.next_move = 1000.0;
+        harness.run(Golden.lifetime().unwrap() - 0.5);
+        assert_eq!(harness.game.fruits.len(), 1);
+        harness.run(1.0);
+        assert!(harness.game.fruits.is_empty());
+    }
+
+    #[test]
+    fn special_fruit() {
+        let mut harness = snake();
+        harness.game.move_speed = MIN_MOVE_SPEED;
+        harness.game.snakes[0].body = vec![
+            Coord::new(9, 7),
+            Coord::new(8, 7),
+            Coord::new(7, 7),
+            Coord::new(6, 7),
+            Coord::new(5, 7),
+            Coord::new(4, 7),
+        ];
+        harness.game.fruits = vec![
+            Fruit::new(Coord::new(10, 7), Slow),
+            Fruit::new(Coord::new(11, 7), Shrink),
+            Fruit::new(Coord::new(12, 7), Multiplier),
+            Fruit::new(Coord::new(13, 7), Golden),
+        ];
+        harness.run_until(1.0, |game| game.fruits.len() == 3);
+        assert_eq!(harness.game.move_speed, MIN_MOVE_SPEED + SLOW_DOWN);
+        assert_eq!(harness.game.snakes[0].body.len(), 7);
+
+        harness.run_until(1.0, |game| game.fruits.len() == 2);
+        assert_eq!(harness.game.snakes[0].body.len(), 4);
+
+        harness.run_until(1.0, |game| game.fruits.len() == 1);
+        assert_eq!(harness.game.multiplier_left, MULTIPLIER_LENGTH);
+        harness.assert_snapshot("snake_special_fruit");
+
+        let score = harness.game.snakes[0].score;
+        harness.run_until(1.0, |game| game.fruits.is_empty());
+        assert_eq!(
+            harness.game.snakes[0].score,
+            score + Golden.score() * MULTIPLIER
+        );
+    }
+
+    #[test]
+    fn spawns_fruit() {
+        let mut harness = Harness::new(Snake::new(1));
+        harness.game.start(Classic);
+        //stop the snake eating any
+        harness.game.next_move = 1000.0;
+        harness.tick();
+        assert_eq!(harness.game.fruits.len(), 1);
+        harness.run(FRUIT_DELAY * 2.0 / 3.0 + 0.1);
+        assert_eq!(harness.game.fruits.len(), MAX_FRUIT_ON_SCREEN);
+        for fruit in &harness.game.fruits {
+            assert!(!harness.game.snakes[0].body.contains(&fruit.xy));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_fruit() {
+        let mut first = Harness::new(Snake::new(42));
+        let mut second = Harness::new(Snake::new(42));
+        first.game.start(Classic);
+        second.game.start(Classic);
+        first.run(2.0);
+        second.run(2.0);
+        assert_eq!(first.game.fruits, second.game.fruits);
+    }
+
+    /// Fill the top rows with the snake so it's as long as the level's target
+    fn reach_target(harness: &mut Harness<Snake>) {
+        harness.game.snakes[0].body = (0..harness.game.level().target)
+            .map(|i| {
+                Coord::new(
+                    (i % ARENA_WIDTH) as isize + 1,
+                    (i / ARENA_WIDTH) as isize + 1,
+                )
+            })
+            .collect();
+    }
+
+    #[test]
+    fn advances_to_next_level() {
+        let mut harness = snake();
+        reach_target(&mut harness);
+        harness.tick();
+        assert_eq!(harness.game.state, Cleared);
+        assert_eq!(harness.game.snakes[0].score, SCORE_PER_LEVEL);
+        harness.run(LEVEL_CLEAR_DELAY + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.level, 1);
+        assert_eq!(harness.game.snakes[0].body.len(), levels()[1].body().len());
+    }
+
+    #[test]
+    fn hits_level_wall() {
+        let mut harness = snake();
+        harness.game.start_level(2);
+        harness.game.next_fruit_spawn = Timer::new_once(1000.0);
+        harness.game.snakes[0].direction = Up;
+        harness.game.snakes[0].body = vec![Coord::new(9, 6), Coord::new(9, 7), Coord::new(9, 8)];
+        harness.run_until(5.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Dying);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(9, 5));
+    }
+
+    #[test]
+    fn wins_after_last_level() {
+        let mut harness = snake();
+        harness.game.start_level(levels().len() - 1);
+        reach_target(&mut harness);
+        let score = harness.game.snakes[0].score;
+        harness.tick();
+        assert_eq!(harness.game.state, Won);
+        assert_eq!(harness.game.snakes[0].score, score + SCORE_PER_LEVEL);
+        harness.assert_snapshot("snake_won");
+    }
+
+    #[test]
+    fn start() {
+        let harness = snake();
+        harness.assert_snapshot("snake_start");
+    }
+
+    #[test]
+    fn mode_names_fit_setup_panel() {
+        //selected modes are drawn as "< NAME >" centred in Standard8x10 inside the panel's 3px border
+        let (x1, _, x2, _) = SETUP_PANEL;
+        for mode in Mode::ALL {
+            let text = format!("< {} >", mode.name());
+            let width = Standard8x10.measure(&text).0 as isize;
+            let left = SCREEN_WIDTH as isize / 2 - width / 2;
+            assert!(
+                left > x1 + 3 && left + width < x2 - 3,
+                "{text} is {width}px wide"
+            );
+        }
+    }
+
+    #[test]
+    fn setup() {
+        let mut harness = Harness::new(Snake::new(1));
+        harness.tick();
+        harness.assert_snapshot("snake_setup");
+        //skip the input delays
+        harness.game.input_timer = Timer::new(GAME_INPUT_DELAY);
+        harness.press(Action::Down);
+        harness.game.input_timer = Timer::new(GAME_INPUT_DELAY);
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.mode, Wrap);
+    }
+
+    #[test]
+    fn wraps_around_edges() {
+        let mut harness = snake_in(Wrap);
+        harness.run_until(10.0, |game| game.snakes[0].body[0].x == 1);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(1, 7));
+        assert_eq!(
+            harness.game.snakes[0].body[1],
+            Coord::new(ARENA_WIDTH as isize, 7)
+        );
+    }
+
+    #[test]
+    fn cant_reverse_across_edge() {
+        let mut harness = snake_in(Wrap);
+        harness.game.snakes[0].body = vec![Coord::new(1, 7), Coord::new(ARENA_WIDTH as isize, 7)];
+        harness.hold(Action::Left);
+        harness.tick();
+        assert_eq!(harness.game.snakes[0].direction, Right);
+    }
+
+    #[test]
+    fn time_attack_doesnt_grow() {
+        let mut harness = snake_in(Timed);
+        harness.game.fruits = vec![Fruit::new(Coord::new(10, 7), Normal)];
+        harness.run_until(1.0, |game| game.fruits.is_empty());
+        assert_eq!(harness.game.snakes[0].body.len(), 3);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(10, 7));
+        assert!(harness.game.snakes[0].score >= SCORE_PER_FRUIT);
+    }
+
+    #[test]
+    fn time_attack_ends() {
+        let mut harness = snake_in(Timed);
+        harness.game.time_left = 0.5;
+        harness.run(1.0);
+        assert_eq!(harness.game.state, Dead);
+        assert!(!harness.game.snakes[0].body.is_empty());
+    }
+
+    #[test]
+    fn zen_cuts_instead_of_dying() {
+        let mut harness = snake_in(Zen);
+        harness.game.snakes[0].body = vec![
+            Coord::new(5, 5),
+            Coord::new(5, 6),
+            Coord::new(6, 6),
+            Coord::new(6, 5),
+            Coord::new(7, 5),
+            Coord::new(8, 5),
+        ];
+        harness.game.snakes[0].direction = Right;
+        harness.run_until(1.0, |game| game.snakes[0].body[0] == Coord::new(6, 5));
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(
+            harness.game.snakes[0].body,
+            vec![Coord::new(6, 5), Coord::new(5, 5), Coord::new(5, 6)]
+        );
+    }
+
+    #[test]
+    fn versus_steers_both_snakes() {
+        let mut harness = snake_in(Versus);
+        harness.game.next_move = 1000.0;
+        assert_eq!(harness.game.players(), 2);
+        assert_eq!(harness.game.snakes.len(), 2);
+        harness.hold_by(Player::Two, Action::Up);
+        harness.tick();
+        harness.release_by(Player::Two, Action::Up);
+        assert!(harness.game.snakes[0].turns.is_empty());
+        assert_eq!(harness.game.snakes[1].turns, [Up]);
+        harness.press(Action::Down);
+        assert_eq!(harness.game.snakes[0].turns, [Down]);
+        assert_eq!(harness.game.snakes[1].turns, [Up]);
+    }
+
+    #[test]
+    fn versus_scores_each_snake() {
+        let mut harness = snake_in(Versus);
+        let head = harness.game.snakes[1].body[0];
+        harness.game.fruits = vec![Fruit::new(head + Left.delta(), Normal)];
+        harness.run_until(1.0, |game| game.snakes[1].body.len() > START_LENGTH);
+        assert_eq!(harness.game.snakes[0].score, SCORE_PER_TICK);
+        assert_eq!(harness.game.snakes[1].score, SCORE_PER_FRUIT);
+    }
+
+    #[test]
+    fn versus_head_to_head_is_a_draw() {
+        let mut harness = snake_in(Versus);
+        harness.game.snakes[0] = Serpent::new(spawn_body(Coord::new(8, 7), Right), Right);
+        harness.game.snakes[1] = Serpent::new(spawn_body(Coord::new(10, 7), Left), Left);
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, RoundOver);
+        assert_eq!(harness.game.round_winner, None);
+        assert_eq!(harness.game.wins, [0, 0]);
+
+        harness.run(LEVEL_CLEAR_DELAY + 0.1);
+        harness.game.snakes[0] = Serpent::new(spawn_body(Coord::new(8, 7), Right), Right);
+        harness.game.snakes[1] = Serpent::new(spawn_body(Coord::new(9, 7), Left), Left);
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.round_winner, None);
+    }
+
+    #[test]
+    fn versus_hitting_rival_loses_round() {
+        let mut harness = snake_in(Versus);
+        harness.game.snakes[0] = Serpent::new(spawn_body(Coord::new(8, 7), Right), Right);
+        harness.game.snakes[1] = Serpent::new((3..10).map(|y| Coord::new(10, y)).collect(), Up);
+        harness.game.snakes[1].score = 250;
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, RoundOver);
+        assert_eq!(harness.game.round_winner, Some(1));
+        assert_eq!(harness.game.wins, [0, 1]);
+        assert_eq!(harness.game.snakes[0].body[0], Coord::new(9, 7));
+
+        harness.run(LEVEL_CLEAR_DELAY + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.wins, [0, 1]);
+        assert_eq!(harness.game.snakes[0].body.len(), START_LENGTH);
+        assert_eq!(harness.game.snakes[1].direction, Left);
+        assert!(harness.game.snakes[1].score >= 250);
+    }
+
+    #[test]
+    fn versus_wins_match() {
+        let mut harness = snake_in(Versus);
+        harness.game.wins = [ROUNDS_TO_WIN - 1, 1];
+        harness.game.snakes[1] = Serpent::new(spawn_body(Coord::new(1, 9), Left), Left);
+        harness.game.snakes[0].score = 420;
+        harness.game.snakes[1].score = 1200;
+        harness.run_until(1.0, |game| game.state != Playing);
+        assert_eq!(harness.game.state, Won);
+        assert_eq!(harness.game.wins, [ROUNDS_TO_WIN, 1]);
+        assert_eq!(harness.game.result, Nothing);
+        harness.assert_snapshot("snake_versus_won");
+    }
+
+    #[test]
+    fn demo_plays_itself() {
+        let mut harness = Harness::new(Snake::demo(1));
+        harness.run_until(30.0, |game| game.snakes[0].score >= SCORE_PER_FRUIT * 3);
+        assert_eq!(harness.game.state, Playing);
+        assert!(harness.game.snakes[0].body.len() > START_LENGTH);
+    }
+
+    #[test]
+    fn zen_finishes_on_confirm() {
+        let mut harness = snake_in(Zen);
+        harness.run(1.0);
+        assert_eq!(harness.game.state, Playing);
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game.state, Dead);
+        harness.assert_snapshot("snake_zen_finished");
+    }
+}
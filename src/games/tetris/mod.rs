@@ -0,0 +1,629 @@
+mod piece;
+
+use crate::audio::{Audio, Sound};
+use crate::button_bar::{ButtonBar, ButtonDef, BAR_HEIGHT};
+use crate::game_over::draw_game_over;
+use crate::games::tetris::piece::{Bag, Piece, Tetromino};
+use crate::games::tetris::State::*;
+use crate::high_scores::submit_score;
+use crate::input::{Action, Input};
+use crate::GameUpdateResult::Nothing;
+use crate::{Game, GameUpdateResult, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH};
+use fastrand::Rng;
+use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
+use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::CenterTop;
+use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
+use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
+use pixels_graphics_lib::prelude::*;
+use std::collections::VecDeque;
+
+pub const ID: &str = "tetris";
+
+const PLAY_HEIGHT: usize = SCREEN_HEIGHT - BAR_HEIGHT;
+
+const BOARD_WIDTH: usize = 10;
+//includes the hidden rows
+const BOARD_HEIGHT: usize = 22;
+//rows above the top of the visible board, pieces spawn here
+const HIDDEN_ROWS: usize = 2;
+const CELL_SIZE: usize = 7;
+const BOARD_POS: Coord = Coord::new(45, 4);
+const PREVIEW_CELL_SIZE: usize = 4;
+const NEXT_COUNT: usize = 3;
+//center of the left and right panels
+const HOLD_X: isize = 22;
+const NEXT_X: isize = 138;
+
+//seconds a direction is held before it repeats
+const AUTO_SHIFT_DELAY: f64 = 0.17;
+const AUTO_SHIFT_RATE: f64 = 0.05;
+//seconds per row when soft dropping, unless gravity is already faster
+const SOFT_DROP_RATE: f64 = 0.05;
+//seconds a piece can sit on the stack before locking
+const LOCK_DELAY: f64 = 0.5;
+//moves or rotations that restart the lock delay, so a piece can't be kept up forever
+const MAX_LOCK_RESETS: usize = 15;
+const CLEAR_DURATION: f64 = 0.5;
+const CLEAR_FLASH_RATE: f64 = 0.1;
+const LINES_PER_LEVEL: usize = 10;
+//gravity stops speeding up after this
+const MAX_SPEED_LEVEL: usize = 20;
+//multiplied by the level, indexed by lines cleared at once minus one
+const LINE_SCORES: [usize; 4] = [100, 300, 500, 800];
+const SOFT_DROP_SCORE: usize = 1;
+const HARD_DROP_SCORE: usize = 2;
+
+/// Seconds for a piece to fall one row at `level`
+fn gravity(level: usize) -> f64 {
+    let level = level.min(MAX_SPEED_LEVEL) as i32 - 1;
+    (0.8 - level as f64 * 0.007).powi(level)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Playing,
+    /// Full rows flash before they're removed
+    Clearing,
+    Dead,
+}
+
+pub struct Tetris {
+    board: [[bool; BOARD_WIDTH]; BOARD_HEIGHT],
+    piece: Piece,
+    hold: Option<Tetromino>,
+    /// False once the piece has been held, until the next piece is locked
+    can_hold: bool,
+    next: VecDeque<Tetromino>,
+    bag: Bag,
+    /// Seconds until the piece falls a row
+    next_fall: f64,
+    /// Seconds the piece has been resting on the stack
+    lock_time: f64,
+    lock_resets: usize,
+    /// Direction currently held, -1, 0 or 1
+    shift_direction: isize,
+    next_shift: f64,
+    clearing: Vec<usize>,
+    clear_time: f64,
+    score: usize,
+    lines: usize,
+    state: State,
+    result: GameUpdateResult,
+    button_bar: ButtonBar,
+    seed: u64,
+    rng: Rng,
+}
+
+impl Tetris {
+    pub fn new(seed: u64) -> Box<Self> {
+        let mut rng = Rng::with_seed(seed);
+        let mut bag = Bag::default();
+        let first = bag.next(&mut rng);
+        let next = (0..NEXT_COUNT).map(|_| bag.next(&mut rng)).collect();
+        Box::new(Self {
+            board: [[false; BOARD_WIDTH]; BOARD_HEIGHT],
+            piece: Piece::new(first, 1),
+            hold: None,
+            can_hold: true,
+            next,
+            bag,
+            next_fall: gravity(1),
+            lock_time: 0.0,
+            lock_resets: 0,
+            shift_direction: 0,
+            next_shift: 0.0,
+            clearing: vec![],
+            clear_time: 0.0,
+            score: 0,
+            lines: 0,
+            state: Playing,
+            result: Nothing,
+            button_bar: ButtonBar::new(
+                coord!(0, PLAY_HEIGHT),
+                SCREEN_WIDTH,
+                &[
+                    ("ROTATE", ButtonDef::Confirm),
+                    ("DROP", ButtonDef::Vert),
+                    ("HOLD", ButtonDef::Secondary),
+                ],
+            ),
+            seed,
+            rng,
+        })
+    }
+}
+
+impl Tetris {
+    fn level(&self) -> usize {
+        self.lines / LINES_PER_LEVEL + 1
+    }
+
+    fn fits(&self, piece: &Piece) -> bool {
+        piece.cells().iter().all(|cell| {
+            cell.x >= 0
+                && cell.y >= 0
+                && cell.x < BOARD_WIDTH as isize
+                && cell.y < BOARD_HEIGHT as isize
+                && !self.board[cell.y as usize][cell.x as usize]
+        })
+    }
+
+    fn on_stack(&self) -> bool {
+        !self.fits(&self.piece.moved(Coord::new(0, 1)))
+    }
+
+    /// Where the piece would land if hard dropped
+    fn landing(&self) -> Piece {
+        let mut piece = self.piece;
+        while self.fits(&piece.moved(Coord::new(0, 1))) {
+            piece = piece.moved(Coord::new(0, 1));
+        }
+        piece
+    }
+
+    /// Moved or rotated piece, restarts the lock delay if it's resting on the stack
+    fn replace_piece(&mut self, piece: Piece) {
+        self.piece = piece;
+        if self.lock_time > 0.0 && self.lock_resets < MAX_LOCK_RESETS {
+            self.lock_time = 0.0;
+            self.lock_resets += 1;
+        }
+    }
+
+    fn shift(&mut self, direction: isize) {
+        let piece = self.piece.moved(Coord::new(direction, 0));
+        if self.fits(&piece) {
+            self.replace_piece(piece);
+        }
+    }
+
+    /// Rotate clockwise, trying each of the SRS wall kicks in turn
+    fn rotate(&mut self) {
+        let rotated = self.piece.rotated();
+        if let Some(piece) = self
+            .piece
+            .kind
+            .kicks(self.piece.rotation)
+            .iter()
+            .map(|kick| rotated.moved(*kick))
+            .find(|piece| self.fits(piece))
+        {
+            self.replace_piece(piece);
+        }
+    }
+
+    fn spawn(&mut self, kind: Tetromino, audio: &mut Audio) {
+        self.piece = Piece::new(kind, 1);
+        self.next_fall = gravity(self.level());
+        self.lock_time = 0.0;
+        self.lock_resets = 0;
+        if !self.fits(&self.piece) {
+            self.game_over(audio);
+        }
+    }
+
+    fn spawn_next(&mut self, audio: &mut Audio) {
+        let kind = self.next.pop_front().unwrap();
+        self.next.push_back(self.bag.next(&mut self.rng));
+        self.spawn(kind, audio);
+    }
+
+    fn hold(&mut self, audio: &mut Audio) {
+        if !self.can_hold {
+            return;
+        }
+        self.can_hold = false;
+        match self.hold.replace(self.piece.kind) {
+            Some(kind) => self.spawn(kind, audio),
+            None => self.spawn_next(audio),
+        }
+    }
+
+    fn hard_drop(&mut self, audio: &mut Audio) {
+        let landing = self.landing();
+        self.score += (landing.pos.y - self.piece.pos.y) as usize * HARD_DROP_SCORE;
+        self.piece = landing;
+        self.lock(audio);
+    }
+
+    fn lock(&mut self, audio: &mut Audio) {
+        let cells = self.piece.cells();
+        for cell in cells {
+            self.board[cell.y as usize][cell.x as usize] = true;
+        }
+        self.can_hold = true;
+        if cells.iter().all(|cell| cell.y < HIDDEN_ROWS as isize) {
+            self.game_over(audio);
+            return;
+        }
+
+        self.clearing = (0..BOARD_HEIGHT)
+            .filter(|y| self.board[*y].iter().all(|filled| *filled))
+            .collect();
+        if self.clearing.is_empty() {
+            audio.play(Sound::Wall);
+            self.spawn_next(audio);
+        } else {
+            let count = self.clearing.len();
+            self.score += LINE_SCORES[count - 1] * self.level();
+            self.lines += count;
+            audio.play(if count == 4 {
+                Sound::Bonus
+            } else {
+                Sound::Apple
+            });
+            self.clear_time = CLEAR_DURATION;
+            self.state = Clearing;
+        }
+    }
+
+    fn remove_cleared_rows(&mut self) {
+        for y in &self.clearing {
+            for row in (1..=*y).rev() {
+                self.board[row] = self.board[row - 1];
+            }
+            self.board[0] = [false; BOARD_WIDTH];
+        }
+        self.clearing.clear();
+    }
+
+    fn game_over(&mut self, audio: &mut Audio) {
+        audio.play(Sound::Death);
+        self.state = Dead;
+        if self.result == Nothing {
+            self.result = submit_score(ID, self.score);
+        }
+    }
+
+    fn update_shift(&mut self, timing: &Timing, input: &Input) {
+        let direction = if input.held(Action::Left) {
+            -1
+        } else if input.held(Action::Right) {
+            1
+        } else {
+            0
+        };
+        if direction != self.shift_direction {
+            self.shift_direction = direction;
+            if direction != 0 {
+                self.shift(direction);
+                self.next_shift = AUTO_SHIFT_DELAY;
+            }
+        } else if direction != 0 {
+            self.next_shift -= timing.fixed_time_step;
+            while self.next_shift <= 0.0 {
+                self.shift(direction);
+                self.next_shift += AUTO_SHIFT_RATE;
+            }
+        }
+    }
+
+    fn update_fall(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) {
+        let soft_drop = input.held(Action::Down);
+        let mut rate = gravity(self.level());
+        if soft_drop {
+            rate = rate.min(SOFT_DROP_RATE);
+            self.next_fall = self.next_fall.min(rate);
+        }
+
+        if self.on_stack() {
+            self.next_fall = rate;
+            self.lock_time += timing.fixed_time_step;
+            if self.lock_time >= LOCK_DELAY {
+                self.lock(audio);
+            }
+            return;
+        }
+
+        self.lock_time = 0.0;
+        self.next_fall -= timing.fixed_time_step;
+        while self.next_fall <= 0.0 && !self.on_stack() {
+            self.piece = self.piece.moved(Coord::new(0, 1));
+            self.next_fall += rate;
+            if soft_drop {
+                self.score += SOFT_DROP_SCORE;
+            }
+        }
+    }
+}
+
+fn cell_rect(cell: Coord) -> Rect {
+    let y = cell.y - HIDDEN_ROWS as isize;
+    Rect::new_with_size(
+        BOARD_POS + (cell.x * CELL_SIZE as isize, y * CELL_SIZE as isize),
+        CELL_SIZE - 2,
+        CELL_SIZE - 2,
+    )
+}
+
+/// Draw the spawn rotation of `kind` at preview size, centered on `center`
+fn draw_preview(graphics: &mut Graphics, kind: Tetromino, center: Coord, color: Color) {
+    let cells = kind.cells(0);
+    let left = cells.iter().map(|cell| cell.x).min().unwrap_or_default();
+    let right = cells.iter().map(|cell| cell.x).max().unwrap_or_default();
+    let top = cells.iter().map(|cell| cell.y).min().unwrap_or_default();
+    let bottom = cells.iter().map(|cell| cell.y).max().unwrap_or_default();
+    let size = PREVIEW_CELL_SIZE as isize;
+    let origin = center - Coord::new((right - left + 1) * size / 2, (bottom - top + 1) * size / 2);
+    for cell in cells {
+        graphics.draw_rect(
+            Rect::new_with_size(
+                origin + ((cell.x - left) * size, (cell.y - top) * size),
+                PREVIEW_CELL_SIZE - 2,
+                PREVIEW_CELL_SIZE - 2,
+            ),
+            fill(color),
+        );
+    }
+}
+
+impl Game for Tetris {
+    fn render(&self, graphics: &mut Graphics, controller: Option<Controller>) {
+        graphics.draw_rect(
+            Rect::new_with_size(
+                BOARD_POS - (1, 1),
+                BOARD_WIDTH * CELL_SIZE,
+                (BOARD_HEIGHT - HIDDEN_ROWS) * CELL_SIZE,
+            ),
+            stroke(CLR_1),
+        );
+
+        let flash = ((self.clear_time / CLEAR_FLASH_RATE) as usize).is_multiple_of(2);
+        for (y, row) in self.board.iter().enumerate().skip(HIDDEN_ROWS) {
+            let color = if self.clearing.contains(&y) {
+                if flash {
+                    CLR_3
+                } else {
+                    continue;
+                }
+            } else {
+                CLR_2
+            };
+            for (x, filled) in row.iter().enumerate() {
+                if *filled {
+                    graphics.draw_rect(cell_rect(Coord::from((x, y))), fill(color));
+                }
+            }
+        }
+
+        if self.state == Playing {
+            for cell in self.landing().cells() {
+                if cell.y >= HIDDEN_ROWS as isize {
+                    graphics.draw_rect(cell_rect(cell), stroke(CLR_1));
+                }
+            }
+            for cell in self.piece.cells() {
+                if cell.y >= HIDDEN_ROWS as isize {
+                    graphics.draw_rect(cell_rect(cell), fill(CLR_3));
+                }
+            }
+        }
+
+        graphics.draw_text(
+            "HOLD",
+            TextPos::Px(HOLD_X, 4),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+        if let Some(kind) = self.hold {
+            let color = if self.can_hold { CLR_3 } else { CLR_1 };
+            draw_preview(graphics, kind, Coord::new(HOLD_X, 20), color);
+        }
+        for (i, (label, value)) in [
+            ("SCORE", self.score),
+            ("LINES", self.lines),
+            ("LEVEL", self.level()),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let y = 40 + i as isize * 20;
+            graphics.draw_text(
+                label,
+                TextPos::Px(HOLD_X, y),
+                (CLR_2, Standard4x5, CenterTop),
+            );
+            graphics.draw_text(
+                &value.to_string(),
+                TextPos::Px(HOLD_X, y + 7),
+                (CLR_3, Standard4x5, CenterTop),
+            );
+        }
+
+        graphics.draw_text(
+            "NEXT",
+            TextPos::Px(NEXT_X, 4),
+            (CLR_2, Standard4x5, CenterTop),
+        );
+        for (i, kind) in self.next.iter().enumerate() {
+            draw_preview(
+                graphics,
+                *kind,
+                Coord::new(NEXT_X, 20 + i as isize * 16),
+                CLR_3,
+            );
+        }
+
+        self.button_bar.render(graphics, controller);
+
+        if self.state == Dead {
+            draw_game_over(graphics, self.seed);
+        }
+    }
+
+    fn on_key_press(&mut self, _: KeyCode) {}
+
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult {
+        match self.state {
+            Playing => {
+                if input.pressed(Action::Secondary) {
+                    self.hold(audio);
+                }
+                if self.state == Playing && input.pressed(Action::Confirm) {
+                    self.rotate();
+                }
+                self.update_shift(timing, input);
+                if self.state == Playing {
+                    if input.pressed(Action::Up) {
+                        self.hard_drop(audio);
+                    } else {
+                        self.update_fall(timing, input, audio);
+                    }
+                }
+            }
+            Clearing => {
+                self.clear_time -= timing.fixed_time_step;
+                if self.clear_time <= 0.0 {
+                    self.remove_cleared_rows();
+                    self.state = Playing;
+                    self.spawn_next(audio);
+                }
+            }
+            Dead => {}
+        }
+
+        self.result
+    }
+
+    fn resuming(&mut self) {
+        self.result = Nothing;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn tetris_with(kind: Tetromino) -> Harness<Tetris> {
+        let mut harness = Harness::new(Tetris::new(1));
+        harness.game.piece = Piece::new(kind, 1);
+        harness
+    }
+
+    fn filled(game: &Tetris) -> usize {
+        game.board.iter().flatten().filter(|cell| **cell).count()
+    }
+
+    #[test]
+    fn hard_drop_locks_and_scores() {
+        let mut harness = tetris_with(Tetromino::I);
+        let first_next = harness.game.next[0];
+        harness.press_and_release(Action::Up);
+        assert!(harness.game.board[BOARD_HEIGHT - 1][3..7]
+            .iter()
+            .all(|cell| *cell));
+        assert_eq!(filled(&harness.game), 4);
+        assert_eq!(harness.game.score, (BOARD_HEIGHT - 3) * HARD_DROP_SCORE);
+        assert_eq!(harness.game.piece.kind, first_next);
+    }
+
+    #[test]
+    fn soft_drop_scores_per_row() {
+        let mut harness = tetris_with(Tetromino::O);
+        harness.hold(Action::Down);
+        harness.run_until(SOFT_DROP_RATE * 6.0, |game| game.piece.pos.y == 6);
+        assert_eq!(harness.game.score, 5 * SOFT_DROP_SCORE);
+    }
+
+    #[test]
+    fn falls_and_locks() {
+        let mut harness = tetris_with(Tetromino::O);
+        harness.run_until(30.0, |game| filled(game) > 0);
+        assert!(harness.game.board[BOARD_HEIGHT - 1][4]);
+        assert_eq!(harness.game.score, 0);
+    }
+
+    #[test]
+    fn clears_lines() {
+        let mut harness = tetris_with(Tetromino::I);
+        for x in (0..3).chain(7..BOARD_WIDTH) {
+            harness.game.board[BOARD_HEIGHT - 1][x] = true;
+        }
+        harness.game.board[BOARD_HEIGHT - 2][0] = true;
+        harness.press_and_release(Action::Up);
+        assert_eq!(harness.game.state, Clearing);
+        assert_eq!(harness.game.clearing, vec![BOARD_HEIGHT - 1]);
+        harness.run(CLEAR_DURATION + 0.1);
+        assert_eq!(harness.game.state, Playing);
+        assert_eq!(harness.game.lines, 1);
+        assert_eq!(filled(&harness.game), 1);
+        assert!(harness.game.board[BOARD_HEIGHT - 1][0]);
+        assert_eq!(
+            harness.game.score,
+            LINE_SCORES[0] + (BOARD_HEIGHT - 3) * HARD_DROP_SCORE
+        );
+    }
+
+    #[test]
+    fn levels_speed_up() {
+        let mut harness = tetris_with(Tetromino::I);
+        harness.game.lines = LINES_PER_LEVEL * 2 - 1;
+        assert_eq!(harness.game.level(), 2);
+        harness.game.lines += 1;
+        assert_eq!(harness.game.level(), 3);
+        assert!(gravity(3) < gravity(2));
+        assert!(gravity(2) < gravity(1));
+        assert_eq!(gravity(MAX_SPEED_LEVEL + 5), gravity(MAX_SPEED_LEVEL));
+    }
+
+    #[test]
+    fn holds_once_per_piece() {
+        let mut harness = tetris_with(Tetromino::T);
+        let next = harness.game.next[0];
+        harness.press_and_release(Action::Secondary);
+        assert_eq!(harness.game.hold, Some(Tetromino::T));
+        assert_eq!(harness.game.piece.kind, next);
+        harness.press_and_release(Action::Secondary);
+        assert_eq!(harness.game.hold, Some(Tetromino::T));
+        assert_eq!(harness.game.piece.kind, next);
+        harness.press_and_release(Action::Up);
+        let spawned = harness.game.piece.kind;
+        harness.press_and_release(Action::Secondary);
+        assert_eq!(harness.game.hold, Some(spawned));
+        assert_eq!(harness.game.piece.kind, Tetromino::T);
+    }
+
+    #[test]
+    fn rotates_with_wall_kick() {
+        let mut harness = tetris_with(Tetromino::T);
+        harness.game.piece = Piece {
+            kind: Tetromino::T,
+            rotation: 1,
+            pos: Coord::new(-1, 5),
+        };
+        harness.press_and_release(Action::Confirm);
+        assert_eq!(harness.game.piece.rotation, 2);
+        assert_eq!(harness.game.piece.pos, Coord::new(0, 5));
+    }
+
+    #[test]
+    fn auto_shifts_when_held() {
+        let mut harness = tetris_with(Tetromino::O);
+        harness.hold(Action::Left);
+        harness.tick();
+        assert_eq!(harness.game.piece.pos.x, 3);
+        harness.run(AUTO_SHIFT_DELAY / 2.0);
+        assert_eq!(harness.game.piece.pos.x, 3);
+        harness.run(AUTO_SHIFT_DELAY + AUTO_SHIFT_RATE * 3.0);
+        assert_eq!(harness.game.piece.pos.x, 0);
+    }
+
+    #[test]
+    fn start() {
+        let harness = Harness::new(Tetris::new(1));
+        harness.assert_snapshot("tetris_start");
+    }
+
+    #[test]
+    fn game_over() {
+        let mut harness = Harness::new(Tetris::new(1));
+        for _ in 0..100 {
+            if harness.game.state == Dead {
+                break;
+            }
+            harness.press_and_release(Action::Up);
+        }
+        assert_eq!(harness.game.state, Dead);
+        harness.assert_snapshot("tetris_game_over");
+    }
+}
@@ -0,0 +1,183 @@
+use crate::games::tetris::piece::Tetromino::*;
+use fastrand::Rng;
+use pixels_graphics_lib::prelude::*;
+
+/// Offsets tried when rotating clockwise from each rotation (spawn, right, 180, left),
+/// from the SRS tables, y is up in the tables so it's flipped when used
+const KICKS: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+/// [KICKS] for [Tetromino::I]
+const I_KICKS: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Tetromino {
+    I,
+    J,
+    L,
+    O,
+    S,
+    T,
+    Z,
+}
+
+impl Tetromino {
+    pub const ALL: [Tetromino; 7] = [I, J, L, O, S, T, Z];
+
+    /// Width and height of the square the piece rotates in
+    fn size(&self) -> isize {
+        match self {
+            I => 4,
+            O => 2,
+            _ => 3,
+        }
+    }
+
+    /// Cells in the spawn rotation, within [Tetromino::size], y is down
+    fn spawn_cells(&self) -> [(isize, isize); 4] {
+        match self {
+            I => [(0, 1), (1, 1), (2, 1), (3, 1)],
+            J => [(0, 0), (0, 1), (1, 1), (2, 1)],
+            L => [(2, 0), (0, 1), (1, 1), (2, 1)],
+            O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            S => [(1, 0), (2, 0), (0, 1), (1, 1)],
+            T => [(1, 0), (0, 1), (1, 1), (2, 1)],
+            Z => [(0, 0), (1, 0), (1, 1), (2, 1)],
+        }
+    }
+
+    /// Cells after `rotation` clockwise quarter turns, relative to the top left of the rotation square
+    pub fn cells(&self, rotation: usize) -> [Coord; 4] {
+        let size = self.size();
+        self.spawn_cells().map(|(mut x, mut y)| {
+            for _ in 0..rotation % 4 {
+                (x, y) = (size - 1 - y, x);
+            }
+            Coord::new(x, y)
+        })
+    }
+
+    /// Offsets to try, in order, when rotating clockwise from `rotation`
+    pub fn kicks(&self, rotation: usize) -> [Coord; 5] {
+        let table = match self {
+            I => &I_KICKS,
+            O => return [Coord::new(0, 0); 5],
+            _ => &KICKS,
+        };
+        table[rotation % 4].map(|(x, y)| Coord::new(x, -y))
+    }
+
+    /// Column of the rotation square's left edge when spawning, so it's centered on the board
+    pub fn spawn_x(&self) -> isize {
+        match self {
+            O => 4,
+            _ => 3,
+        }
+    }
+}
+
+/// Falling piece
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Piece {
+    pub kind: Tetromino,
+    /// Clockwise quarter turns from spawn
+    pub rotation: usize,
+    /// Board position of the top left of the rotation square
+    pub pos: Coord,
+}
+
+impl Piece {
+    pub fn new(kind: Tetromino, y: isize) -> Self {
+        Self {
+            kind,
+            rotation: 0,
+            pos: Coord::new(kind.spawn_x(), y),
+        }
+    }
+
+    /// Board cells covered by the piece
+    pub fn cells(&self) -> [Coord; 4] {
+        self.kind.cells(self.rotation).map(|cell| cell + self.pos)
+    }
+
+    pub fn moved(&self, delta: Coord) -> Piece {
+        Piece {
+            pos: self.pos + delta,
+            ..*self
+        }
+    }
+
+    pub fn rotated(&self) -> Piece {
+        Piece {
+            rotation: (self.rotation + 1) % 4,
+            ..*self
+        }
+    }
+}
+
+/// Deals every piece once, in a random order, before shuffling a new set
+#[derive(Debug, Default)]
+pub struct Bag {
+    pieces: Vec<Tetromino>,
+}
+
+impl Bag {
+    pub fn next(&mut self, rng: &mut Rng) -> Tetromino {
+        if self.pieces.is_empty() {
+            self.pieces = Tetromino::ALL.to_vec();
+            rng.shuffle(&mut self.pieces);
+        }
+        self.pieces.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted(mut cells: [Coord; 4]) -> Vec<(isize, isize)> {
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells.iter().map(|cell| (cell.x, cell.y)).collect()
+    }
+
+    #[test]
+    fn bag_deals_each_piece_once() {
+        let mut rng = Rng::with_seed(1);
+        let mut bag = Bag::default();
+        for _ in 0..3 {
+            let mut dealt: Vec<Tetromino> = (0..7).map(|_| bag.next(&mut rng)).collect();
+            for kind in Tetromino::ALL {
+                let i = dealt.iter().position(|k| *k == kind).unwrap();
+                dealt.remove(i);
+            }
+            assert!(dealt.is_empty());
+        }
+    }
+
+    #[test]
+    fn rotates_like_srs() {
+        assert_eq!(sorted(I.cells(1)), vec![(2, 0), (2, 1), (2, 2), (2, 3)]);
+        assert_eq!(sorted(I.cells(2)), vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(sorted(T.cells(1)), vec![(1, 0), (1, 1), (2, 1), (1, 2)]);
+        assert_eq!(sorted(T.cells(2)), vec![(0, 1), (1, 1), (2, 1), (1, 2)]);
+        assert_eq!(sorted(O.cells(3)), sorted(O.cells(0)));
+        for kind in Tetromino::ALL {
+            assert_eq!(kind.cells(4), kind.cells(0));
+        }
+    }
+
+    #[test]
+    fn kicks_flip_y() {
+        assert_eq!(T.kicks(0)[2], Coord::new(-1, -1));
+        assert_eq!(I.kicks(0)[4], Coord::new(1, -2));
+        assert!(O.kicks(2).iter().all(|kick| *kick == Coord::new(0, 0)));
+    }
+}
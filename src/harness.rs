@@ -0,0 +1,234 @@
+//! Runs games without a window or audio so they can be tested
+//!
+//! Input is scripted by holding and releasing [Action]s, which are turned into key presses using
+//! [DEFAULT_BINDINGS], and frames are rendered into an offscreen buffer
+//!
+//! Input can be recorded into a [Replay] and played back into another harness
+
+use crate::audio::Audio;
+use crate::input::{Action, Input, Player, RawInput, DEFAULT_BINDINGS};
+use crate::replay::{Playback, Replay};
+use crate::{
+    Game, GameUpdateResult, CLR_0, CLR_1, CLR_2, CLR_3, SCREEN_HEIGHT, SCREEN_WIDTH,
+    UPDATES_PER_SECOND,
+};
+use pixels_graphics_lib::prelude::*;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+
+/// Set to update snapshot files instead of comparing against them
+const UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+
+pub struct Harness<G: Game> {
+    pub game: Box<G>,
+    timing: Timing,
+    raw: RawInput,
+    previous: RawInput,
+    audio: Audio,
+    replay: Option<Replay>,
+}
+
+impl<G: Game> Harness<G> {
+    pub fn new(game: Box<G>) -> Self {
+        Self {
+            game,
+            timing: Timing::new(UPDATES_PER_SECOND),
+            raw: RawInput::default(),
+            previous: RawInput::default(),
+            audio: Audio::null(),
+            replay: None,
+        }
+    }
+}
+
+impl<G: Game> Harness<G> {
+    /// Hold `action` for the first player until [Harness::release] is called
+    pub fn hold(&mut self, action: Action) {
+        self.hold_by(Player::One, action);
+    }
+
+    pub fn hold_by(&mut self, player: Player, action: Action) {
+        let key = self.key_down(player, action);
+        self.raw.keys.insert(key);
+    }
+
+    /// Press and release `action` for the first player before the next update
+    pub fn tap(&mut self, action: Action) {
+        self.tap_by(Player::One, action);
+    }
+
+    pub fn tap_by(&mut self, player: Player, action: Action) {
+        self.key_down(player, action);
+    }
+
+    /// Send the key press for `action` like [GameHost][crate::GameHost] does
+    fn key_down(&mut self, player: Player, action: Action) -> KeyCode {
+        let key = DEFAULT_BINDINGS
+            .primary_key(player, action)
+            .expect("action has no default key");
+        self.game.on_key_press(key);
+        if let Some((player, action)) = DEFAULT_BINDINGS.press_for_key(self.game.players(), key) {
+            if let Some(replay) = &mut self.replay {
+                replay.press(player, action);
+            }
+            self.game.on_press(player, action);
+        }
+        key
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.release_by(Player::One, action);
+    }
+
+    pub fn release_by(&mut self, player: Player, action: Action) {
+        if let Some(key) = DEFAULT_BINDINGS.primary_key(player, action) {
+            self.raw.keys.remove(&key);
+        }
+    }
+
+    /// Hold `action` for a single update
+    pub fn press(&mut self, action: Action) -> GameUpdateResult {
+        self.hold(action);
+        let result = self.tick();
+        self.release(action);
+        result
+    }
+
+    /// [Harness::press] `action` then run an update with it released, so it can be pressed again
+    pub fn press_and_release(&mut self, action: Action) -> GameUpdateResult {
+        let result = self.press(action);
+        self.tick();
+        result
+    }
+
+    /// Run one update
+    pub fn tick(&mut self) -> GameUpdateResult {
+        let input = Input::new(
+            &DEFAULT_BINDINGS,
+            self.game.players(),
+            &self.raw,
+            &self.previous,
+        );
+        self.previous = self.raw.clone();
+        self.timing.updates += 1;
+        if let Some(replay) = &mut self.replay {
+            replay.record(&input);
+        }
+        self.game.update(&self.timing, &input, &mut self.audio)
+    }
+
+    /// Record input from now on, see [Harness::replay]
+    pub fn record(&mut self, game: &'static str, seed: u64) {
+        self.replay = Some(Replay::new(game, seed));
+    }
+
+    /// Input recorded since [Harness::record] was called
+    pub fn replay(&self) -> Replay {
+        self.replay.clone().expect("input isn't being recorded")
+    }
+
+    /// Play all of `replay`, ignoring any input held in this harness
+    pub fn play(&mut self, replay: &Replay) {
+        let mut playback = Playback::default();
+        loop {
+            self.timing.updates += 1;
+            if !playback.step(replay, self.game.as_mut(), &self.timing, &mut self.audio) {
+                break;
+            }
+        }
+    }
+
+    /// Run updates for `seconds` of game time, returns the last result
+    pub fn run(&mut self, seconds: f64) -> GameUpdateResult {
+        let mut result = GameUpdateResult::Nothing;
+        for _ in 0..(seconds * UPDATES_PER_SECOND as f64).round() as usize {
+            result = self.tick();
+        }
+        result
+    }
+
+    /// Run updates until `done` returns true, panics if it takes longer than `max_seconds`
+    pub fn run_until(&mut self, max_seconds: f64, done: impl Fn(&G) -> bool) {
+        let max = (max_seconds * UPDATES_PER_SECOND as f64).round() as usize;
+        for _ in 0..max {
+            if done(&self.game) {
+                return;
+            }
+            self.tick();
+        }
+        assert!(done(&self.game), "not done after {max_seconds}s");
+    }
+
+    pub fn render(&self) -> Frame {
+        render_game(self.game.as_ref())
+    }
+
+    /// Compare the current frame to `snapshots/<name>.txt`
+    ///
+    /// The file is written instead when `UPDATE_SNAPSHOTS` is set, a missing file fails
+    pub fn assert_snapshot(&self, name: &str) {
+        let frame = self.render().to_string();
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(format!("{name}.txt"));
+        if std::env::var_os(UPDATE_SNAPSHOTS).is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, frame).unwrap();
+            return;
+        }
+        assert!(
+            path.exists(),
+            "snapshot {path:?} is missing, run with {UPDATE_SNAPSHOTS}=1 to create it"
+        );
+        let expected = fs::read_to_string(&path).unwrap();
+        if expected != frame {
+            let first_diff = expected
+                .lines()
+                .zip(frame.lines())
+                .position(|(expected, actual)| expected != actual)
+                .unwrap_or_default();
+            panic!(
+                "frame doesn't match snapshot {path:?}, first different row is {first_diff}\nexpected:\n{expected}\nactual:\n{frame}"
+            );
+        }
+    }
+}
+
+/// Render `game` into an offscreen buffer, for games inside other screens
+pub fn render_game(game: &dyn Game) -> Frame {
+    let mut buffer = Graphics::create_buffer_u8(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let mut graphics = Graphics::new_u8_rgba(&mut buffer, SCREEN_WIDTH, SCREEN_HEIGHT).unwrap();
+    graphics.clear(CLR_0);
+    game.render(&mut graphics, None);
+    let pixels = (0..SCREEN_HEIGHT as isize)
+        .flat_map(|y| (0..SCREEN_WIDTH as isize).map(move |x| (x, y)))
+        .map(|(x, y)| graphics.get_pixel(x, y, false))
+        .collect();
+    Frame { pixels }
+}
+
+/// Rendered screen, as palette colours
+pub struct Frame {
+    pixels: Vec<Option<Color>>,
+}
+
+/// One line per row, each pixel is its palette index (`0`-`3`) or `?` for any other colour
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.pixels.chunks(SCREEN_WIDTH) {
+            let line: String = row
+                .iter()
+                .map(|color| match *color {
+                    Some(CLR_0) => '0',
+                    Some(CLR_1) => '1',
+                    Some(CLR_2) => '2',
+                    Some(CLR_3) => '3',
+                    _ => '?',
+                })
+                .collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use crate::date::{civil_from_days, days_since_epoch};
+use crate::games::find_score_table;
+use crate::GameUpdateResult::{Nothing, Push};
+use crate::{GameUpdateResult, Screen, PREFS_NAME, PREFS_ORG, PREFS_QUALIFIER};
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "high_scores.prefs";
+pub const MAX_ENTRIES: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: usize,
+    pub initials: String,
+    /// YYYY-MM-DD
+    pub date: String,
+}
+
+impl ScoreEntry {
+    pub fn new(score: usize, initials: String) -> Self {
+        Self {
+            score,
+            initials,
+            date: today(),
+        }
+    }
+}
+
+/// How the entries in a [ScoreTable] are ordered
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ranking {
+    /// Highest score first
+    Points,
+    /// Fastest first, scores are in seconds
+    Time,
+}
+
+impl Ranking {
+    /// Returns true if `score` should be above `other` in the table
+    pub fn beats(&self, score: usize, other: usize) -> bool {
+        match self {
+            Ranking::Points => score > other,
+            Ranking::Time => score < other,
+        }
+    }
+
+    /// `score` as shown in the table
+    pub fn format(&self, score: usize) -> String {
+        match self {
+            Ranking::Points => score.to_string(),
+            Ranking::Time => format!("{}:{:02}", score / 60, score % 60),
+        }
+    }
+}
+
+/// A high score table, games with modes can have one for each
+pub struct ScoreTable {
+    /// Key in [HighScores], the game id for games with one table
+    pub id: &'static str,
+    /// Shown above the table
+    pub name: &'static str,
+    pub ranking: Ranking,
+}
+
+/// Top [MAX_ENTRIES] scores for each game, keyed by game id
+///
+/// If the preferences directory isn't available the tables are kept in memory only
+pub struct HighScores {
+    prefs: Preferences<Vec<ScoreEntry>>,
+    /// False if the tables are only kept in memory
+    persist: bool,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        match get_pref_dir(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME) {
+            Ok(dir) => {
+                let mut prefs = Preferences::new(dir, FILE_NAME);
+                if let Err(e) = prefs.load() {
+                    error!("Unable to load high scores: {e:?}");
+                }
+                Self {
+                    prefs,
+                    persist: true,
+                }
+            }
+            Err(e) => {
+                error!("Unable to find high scores dir: {e:?}");
+                Self::in_memory()
+            }
+        }
+    }
+
+    /// Empty tables that are never saved
+    pub fn in_memory() -> Self {
+        Self {
+            prefs: Preferences::new(PathBuf::new(), FILE_NAME),
+            persist: false,
+        }
+    }
+}
+
+impl HighScores {
+    pub fn table(&self, game: &str) -> &[ScoreEntry] {
+        self.prefs
+            .get(game)
+            .map(|table| table.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if `score` would be added to the table for `game`
+    pub fn qualifies(&self, game: &str, score: usize) -> bool {
+        let table = self.table(game);
+        let ranking = ranking(game);
+        score > 0
+            && (table.len() < MAX_ENTRIES
+                || table
+                    .last()
+                    .map(|e| ranking.beats(score, e.score))
+                    .unwrap_or(true))
+    }
+
+    /// Add `entry` to the table for `game` and save
+    ///
+    /// Returns the position of the new entry, or None if it didn't qualify
+    pub fn insert(&mut self, game: &str, entry: ScoreEntry) -> Option<usize> {
+        if !self.qualifies(game, entry.score) {
+            return None;
+        }
+        let mut table = self.table(game).to_vec();
+        let ranking = ranking(game);
+        let idx = table
+            .iter()
+            .position(|e| ranking.beats(entry.score, e.score))
+            .unwrap_or(table.len());
+        table.insert(idx, entry);
+        table.truncate(MAX_ENTRIES);
+        self.prefs.set(game, table);
+        if self.persist {
+            if let Err(e) = self.prefs.save() {
+                error!("Unable to save high scores: {e:?}");
+            }
+        }
+        Some(idx)
+    }
+}
+
+/// Asks the host to open the initials entry screen, it's only opened if `score` makes the table
+/// for `game` otherwise the game is resumed
+pub fn submit_score(game: &'static str, score: usize) -> GameUpdateResult {
+    if score > 0 {
+        Push(Screen::EnterInitials { game, score })
+    } else {
+        Nothing
+    }
+}
+
+/// Ranking of the table `game`, unknown tables are ranked by points
+fn ranking(game: &str) -> Ranking {
+    find_score_table(game)
+        .map(|table| table.ranking)
+        .unwrap_or(Ranking::Points)
+}
+
+/// Current UTC date as YYYY-MM-DD
+fn today() -> String {
+    let (y, m, d) = civil_from_days(days_since_epoch());
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::minesweeper;
+    use crate::games::pong;
+
+    fn entry(score: usize) -> ScoreEntry {
+        ScoreEntry::new(score, String::from("AAA"))
+    }
+
+    #[test]
+    fn orders_points_highest_first() {
+        let mut scores = HighScores::in_memory();
+        assert_eq!(scores.insert(pong::ID, entry(5)), Some(0));
+        assert_eq!(scores.insert(pong::ID, entry(9)), Some(0));
+        assert_eq!(scores.insert(pong::ID, entry(7)), Some(1));
+        //ties go below the existing entry
+        assert_eq!(scores.insert(pong::ID, entry(7)), Some(2));
+        let table: Vec<usize> = scores.table(pong::ID).iter().map(|e| e.score).collect();
+        assert_eq!(table, [9, 7, 7, 5]);
+        assert!(scores.table(minesweeper::EASY_ID).is_empty());
+    }
+
+    #[test]
+    fn keeps_top_ten() {
+        let mut scores = HighScores::in_memory();
+        for score in 1..=MAX_ENTRIES {
+            scores.insert(pong::ID, entry(score * 10));
+        }
+        assert!(scores.qualifies(pong::ID, 11));
+        assert!(!scores.qualifies(pong::ID, 10));
+        assert!(!scores.qualifies(pong::ID, 0));
+        assert_eq!(scores.insert(pong::ID, entry(5)), None);
+
+        assert_eq!(scores.insert(pong::ID, entry(55)), Some(5));
+        let table = scores.table(pong::ID);
+        assert_eq!(table.len(), MAX_ENTRIES);
+        assert_eq!(table[5].score, 55);
+        assert_eq!(table.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn orders_times_fastest_first() {
+        let mut scores = HighScores::in_memory();
+        for time in [90, 30, 60] {
+            scores.insert(minesweeper::EASY_ID, entry(time));
+        }
+        let table: Vec<usize> = scores
+            .table(minesweeper::EASY_ID)
+            .iter()
+            .map(|e| e.score)
+            .collect();
+        assert_eq!(table, [30, 60, 90]);
+
+        for time in 100..100 + MAX_ENTRIES {
+            scores.insert(minesweeper::EASY_ID, entry(time));
+        }
+        assert!(scores.qualifies(minesweeper::EASY_ID, 105));
+        assert!(!scores.qualifies(minesweeper::EASY_ID, 106));
+        assert_eq!(scores.insert(minesweeper::EASY_ID, entry(45)), Some(1));
+        assert_eq!(
+            scores.table(minesweeper::EASY_ID).last().unwrap().score,
+            105
+        );
+    }
+
+    #[test]
+    fn submits_scores() {
+        assert_eq!(submit_score(pong::ID, 0), Nothing);
+        assert_eq!(
+            submit_score(pong::ID, 3),
+            Push(Screen::EnterInitials {
+                game: pong::ID,
+                score: 3
+            })
+        );
+    }
+
+    #[test]
+    fn times_rank_lowest_first() {
+        assert!(Ranking::Points.beats(20, 10));
+        assert!(!Ranking::Points.beats(10, 10));
+        assert!(Ranking::Time.beats(10, 20));
+        assert!(!Ranking::Time.beats(10, 10));
+        assert_eq!(Ranking::Points.format(125), "125");
+        assert_eq!(Ranking::Time.format(125), "2:05");
+    }
+}
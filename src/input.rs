@@ -0,0 +1,745 @@
+use crate::{PREFS_NAME, PREFS_ORG, PREFS_QUALIFIER};
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+const FILE_NAME: &str = "bindings.prefs";
+const KEY: &str = "bindings";
+
+pub const KEY_SLOTS: usize = 2;
+
+static BINDINGS: RwLock<Bindings> = RwLock::new(DEFAULT_BINDINGS);
+
+/// What games respond to, the host converts held keys and controller buttons into these
+/// using the current [Bindings]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Pause,
+    /// Second face button, for games that need more than [Action::Confirm]
+    Secondary,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Back,
+        Action::Pause,
+        Action::Secondary,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "UP",
+            Action::Down => "DOWN",
+            Action::Left => "LEFT",
+            Action::Right => "RIGHT",
+            Action::Confirm => "CONFIRM",
+            Action::Back => "BACK",
+            Action::Pause => "PAUSE",
+            Action::Secondary => "SECONDARY",
+        }
+    }
+
+    #[inline]
+    fn mask(&self) -> u16 {
+        1 << (*self as u16)
+    }
+}
+
+/// Actions the second player can use, they share indexes with [Action::ALL]
+pub const P2_ACTIONS: [Action; 5] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Confirm,
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// Controller buttons that [GameController] reports
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum PadButton {
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    South,
+    East,
+    North,
+    West,
+    Start,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 9] = [
+        PadButton::DPadUp,
+        PadButton::DPadDown,
+        PadButton::DPadLeft,
+        PadButton::DPadRight,
+        PadButton::South,
+        PadButton::East,
+        PadButton::North,
+        PadButton::West,
+        PadButton::Start,
+    ];
+
+    pub fn mask(&self) -> u16 {
+        match self {
+            PadButton::DPadUp => CNTR_MASK_UP,
+            PadButton::DPadDown => CNTR_MASK_DOWN,
+            PadButton::DPadLeft => CNTR_MASK_LEFT,
+            PadButton::DPadRight => CNTR_MASK_RIGHT,
+            PadButton::South => CNTR_MASK_SOUTH,
+            PadButton::East => CNTR_MASK_EAST,
+            PadButton::North => CNTR_MASK_NORTH,
+            PadButton::West => CNTR_MASK_WEST,
+            PadButton::Start => CNTR_MASK_START,
+        }
+    }
+
+    /// Short (3 chars max) name for the button using the face button names for `controller`
+    pub fn label(&self, controller: Option<Controller>) -> &'static str {
+        match (self, controller) {
+            (PadButton::DPadUp, _) => "UP",
+            (PadButton::DPadDown, _) => "DWN",
+            (PadButton::DPadLeft, _) => "LFT",
+            (PadButton::DPadRight, _) => "RGT",
+            (PadButton::Start, _) => "STA",
+            (PadButton::South, Some(Controller::Playstation)) => "X",
+            (PadButton::East, Some(Controller::Playstation)) => "O",
+            (PadButton::North, Some(Controller::Playstation)) => "TRI",
+            (PadButton::West, Some(Controller::Playstation)) => "SQR",
+            (PadButton::South, Some(Controller::Switch)) => "B",
+            (PadButton::East, Some(Controller::Switch)) => "A",
+            (PadButton::North, Some(Controller::Switch)) => "X",
+            (PadButton::West, Some(Controller::Switch)) => "Y",
+            (PadButton::South, _) => "A",
+            (PadButton::East, _) => "B",
+            (PadButton::North, _) => "Y",
+            (PadButton::West, _) => "X",
+        }
+    }
+}
+
+/// Short (3 chars max) name for `key`
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    let label = match key {
+        KeyCode::ArrowUp => "UP",
+        KeyCode::ArrowDown => "DWN",
+        KeyCode::ArrowLeft => "LFT",
+        KeyCode::ArrowRight => "RGT",
+        KeyCode::Space => "SPC",
+        KeyCode::Enter => "ENT",
+        KeyCode::Backspace => "BSP",
+        KeyCode::Escape => "ESC",
+        KeyCode::PageUp => "PGU",
+        KeyCode::PageDown => "PGD",
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => "SHF",
+        KeyCode::ControlLeft | KeyCode::ControlRight => "CTL",
+        KeyCode::AltLeft | KeyCode::AltRight => "ALT",
+        KeyCode::SuperLeft | KeyCode::SuperRight => "SUP",
+        KeyCode::Minus => "-",
+        KeyCode::Equal => "=",
+        KeyCode::Semicolon => ";",
+        KeyCode::BracketLeft => "[",
+        KeyCode::BracketRight => "]",
+        KeyCode::Comma => ",",
+        KeyCode::Period => ".",
+        KeyCode::Slash => "/",
+        KeyCode::Quote => "'",
+        KeyCode::Backslash => "\\",
+        KeyCode::NumpadAdd => "N+",
+        KeyCode::NumpadSubtract => "N-",
+        KeyCode::NumpadDecimal => "N.",
+        KeyCode::NumpadDivide => "N/",
+        KeyCode::NumpadStar => "N*",
+        KeyCode::NumpadEqual => "N=",
+        _ => {
+            return if let Some(letter) = name.strip_prefix("Key") {
+                letter.to_string()
+            } else if let Some(digit) = name.strip_prefix("Digit") {
+                digit.to_string()
+            } else if let Some(digit) = name.strip_prefix("Numpad") {
+                format!("N{digit}")
+            } else {
+                name.chars().take(3).collect::<String>().to_uppercase()
+            }
+        }
+    };
+    label.to_string()
+}
+
+/// Keys (primary and alternative) and controller button for every [Action]
+///
+/// The second player only has keys for [P2_ACTIONS], their controller uses the same buttons
+/// as the first player
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Bindings {
+    keys: [[Option<KeyCode>; KEY_SLOTS]; Action::ALL.len()],
+    p2_keys: [[Option<KeyCode>; KEY_SLOTS]; P2_ACTIONS.len()],
+    pad: [Option<PadButton>; Action::ALL.len()],
+}
+
+pub const DEFAULT_BINDINGS: Bindings = Bindings {
+    keys: [
+        [Some(KeyCode::ArrowUp), Some(KeyCode::KeyW)],
+        [Some(KeyCode::ArrowDown), Some(KeyCode::KeyS)],
+        [Some(KeyCode::ArrowLeft), Some(KeyCode::KeyA)],
+        [Some(KeyCode::ArrowRight), Some(KeyCode::KeyD)],
+        [Some(KeyCode::Space), Some(KeyCode::Enter)],
+        [Some(KeyCode::Escape), Some(KeyCode::Backspace)],
+        [Some(KeyCode::KeyP), None],
+        [Some(KeyCode::KeyX), Some(KeyCode::ShiftLeft)],
+    ],
+    p2_keys: [
+        [Some(KeyCode::KeyW), None],
+        [Some(KeyCode::KeyS), None],
+        [Some(KeyCode::KeyA), None],
+        [Some(KeyCode::KeyD), None],
+        [Some(KeyCode::KeyE), None],
+    ],
+    pad: [
+        Some(PadButton::DPadUp),
+        Some(PadButton::DPadDown),
+        Some(PadButton::DPadLeft),
+        Some(PadButton::DPadRight),
+        Some(PadButton::South),
+        Some(PadButton::East),
+        Some(PadButton::Start),
+        Some(PadButton::West),
+    ],
+};
+
+impl Bindings {
+    fn player_keys(&self, player: Player) -> &[[Option<KeyCode>; KEY_SLOTS]] {
+        match player {
+            Player::One => &self.keys,
+            Player::Two => &self.p2_keys,
+        }
+    }
+
+    fn player_keys_mut(&mut self, player: Player) -> &mut [[Option<KeyCode>; KEY_SLOTS]] {
+        match player {
+            Player::One => &mut self.keys,
+            Player::Two => &mut self.p2_keys,
+        }
+    }
+
+    /// Returns None if `player` can't use `action`
+    pub fn key(&self, player: Player, action: Action, slot: usize) -> Option<KeyCode> {
+        self.player_keys(player)
+            .get(action as usize)
+            .and_then(|slots| slots[slot])
+    }
+
+    pub fn pad(&self, action: Action) -> Option<PadButton> {
+        self.pad[action as usize]
+    }
+
+    /// Action `key` is bound to for `player`, if any
+    pub fn action_for_key(&self, player: Player, key: KeyCode) -> Option<Action> {
+        self.player_keys(player)
+            .iter()
+            .position(|slots| slots.contains(&Some(key)))
+            .map(|idx| Action::ALL[idx])
+    }
+
+    /// Player and action a press of `key` is for, with the same rules as [Input::new]
+    pub fn press_for_key(&self, players: usize, key: KeyCode) -> Option<(Player, Action)> {
+        if players > 1 {
+            if let Some(action) = self.action_for_key(Player::Two, key) {
+                return Some((Player::Two, action));
+            }
+        }
+        self.action_for_key(Player::One, key)
+            .map(|action| (Player::One, action))
+    }
+
+    /// First key bound to `action` for `player`
+    pub fn primary_key(&self, player: Player, action: Action) -> Option<KeyCode> {
+        self.player_keys(player)
+            .get(action as usize)
+            .and_then(|slots| slots.iter().flatten().next().copied())
+    }
+
+    /// Bind `key` to `action` for `player`, if `key` was bound to another of the player's
+    /// actions that slot gets the key it replaces
+    ///
+    /// Players can share keys, only the second player's are used in two player games
+    pub fn set_key(&mut self, player: Player, action: Action, slot: usize, key: KeyCode) {
+        let keys = self.player_keys_mut(player);
+        let Some(replaced) = keys.get(action as usize).map(|slots| slots[slot]) else {
+            return;
+        };
+        for slots in keys.iter_mut() {
+            for bound in slots.iter_mut() {
+                if *bound == Some(key) {
+                    *bound = replaced;
+                }
+            }
+        }
+        keys[action as usize][slot] = Some(key);
+    }
+
+    /// Bind `button` to `action`, if `button` was bound elsewhere that action gets the button it replaces
+    pub fn set_pad(&mut self, action: Action, button: PadButton) {
+        let replaced = self.pad[action as usize];
+        for bound in self.pad.iter_mut() {
+            if *bound == Some(button) {
+                *bound = replaced;
+            }
+        }
+        self.pad[action as usize] = Some(button);
+    }
+
+    /// Bitmask of the actions `player` is holding via `keys` or `pad_mask`
+    ///
+    /// Keys in `ignored` are skipped
+    fn resolve(
+        &self,
+        player: Player,
+        keys: &HashSet<KeyCode>,
+        ignored: &HashSet<KeyCode>,
+        pad_mask: u16,
+    ) -> u16 {
+        let player_keys = self.player_keys(player);
+        Action::ALL
+            .iter()
+            .filter(|action| {
+                let i = **action as usize;
+                let key_held = player_keys
+                    .get(i)
+                    .map(|slots| {
+                        slots
+                            .iter()
+                            .flatten()
+                            .any(|key| keys.contains(key) && !ignored.contains(key))
+                    })
+                    .unwrap_or(false);
+                key_held
+                    || self.pad[i]
+                        .map(|button| pad_mask & button.mask() != 0)
+                        .unwrap_or(false)
+            })
+            .fold(0, |mask, action| mask | action.mask())
+    }
+
+    fn p2_key_set(&self) -> HashSet<KeyCode> {
+        self.p2_keys.iter().flatten().flatten().copied().collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedBinding {
+    action: Action,
+    keys: Vec<Option<String>>,
+    #[serde(default)]
+    p2_keys: Vec<Option<String>>,
+    pad: Option<PadButton>,
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    ALL_KEYS
+        .iter()
+        .find(|key| format!("{key:?}") == name)
+        .copied()
+}
+
+fn prefs() -> Option<Preferences<Vec<SavedBinding>>> {
+    match get_pref_dir(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME) {
+        Ok(dir) => Some(Preferences::new(dir, FILE_NAME)),
+        Err(e) => {
+            error!("Unable to find bindings dir: {e:?}");
+            None
+        }
+    }
+}
+
+/// Current bindings
+pub fn bindings() -> Bindings {
+    *BINDINGS.read().unwrap()
+}
+
+/// Replace the current bindings and save them
+pub fn set_bindings(bindings: Bindings) {
+    *BINDINGS.write().unwrap() = bindings;
+    if let Some(mut prefs) = prefs() {
+        save_bindings(&mut prefs, &bindings);
+    }
+}
+
+/// Replace the current bindings with any that were saved
+pub fn load_bindings() {
+    let Some(mut prefs) = prefs() else {
+        return;
+    };
+    if let Some(bindings) = read_bindings(&mut prefs) {
+        *BINDINGS.write().unwrap() = bindings;
+    }
+}
+
+fn save_bindings(prefs: &mut Preferences<Vec<SavedBinding>>, bindings: &Bindings) {
+    let saved = Action::ALL
+        .iter()
+        .map(|action| SavedBinding {
+            action: *action,
+            keys: (0..KEY_SLOTS)
+                .map(|slot| {
+                    bindings
+                        .key(Player::One, *action, slot)
+                        .map(|key| format!("{key:?}"))
+                })
+                .collect(),
+            p2_keys: (0..KEY_SLOTS)
+                .map(|slot| {
+                    bindings
+                        .key(Player::Two, *action, slot)
+                        .map(|key| format!("{key:?}"))
+                })
+                .collect(),
+            pad: bindings.pad(*action),
+        })
+        .collect();
+    prefs.set(KEY, saved);
+    if let Err(e) = prefs.save() {
+        error!("Unable to save bindings: {e:?}");
+    }
+}
+
+/// Bindings saved in `prefs`, actions that weren't saved use [DEFAULT_BINDINGS]
+fn read_bindings(prefs: &mut Preferences<Vec<SavedBinding>>) -> Option<Bindings> {
+    if let Err(e) = prefs.load() {
+        error!("Unable to load bindings: {e:?}");
+        return None;
+    }
+    let saved = prefs.get(KEY)?;
+    let mut bindings = DEFAULT_BINDINGS;
+    for binding in saved {
+        let i = binding.action as usize;
+        for slot in 0..KEY_SLOTS {
+            bindings.keys[i][slot] = binding
+                .keys
+                .get(slot)
+                .cloned()
+                .flatten()
+                .and_then(|name| parse_key(&name));
+            if let Some(p2_slots) = bindings.p2_keys.get_mut(i) {
+                if !binding.p2_keys.is_empty() {
+                    p2_slots[slot] = binding
+                        .p2_keys
+                        .get(slot)
+                        .cloned()
+                        .flatten()
+                        .and_then(|name| parse_key(&name));
+                }
+            }
+        }
+        bindings.pad[i] = binding.pad;
+    }
+    Some(bindings)
+}
+
+/// Keys and controller buttons held during an update, before being converted by [Bindings]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RawInput {
+    pub keys: HashSet<KeyCode>,
+    /// Buttons held on each controller, see [PadButton::mask]
+    pub pads: Vec<u16>,
+}
+
+impl RawInput {
+    /// Buttons for `player`, with one player every controller is used
+    fn pad_mask(&self, player: Player, players: usize) -> u16 {
+        match (player, players) {
+            (Player::One, 1) => self.pads.iter().fold(0, |mask, pad| mask | pad),
+            (Player::One, _) => self.pads.first().copied().unwrap_or_default(),
+            (Player::Two, 1) => 0,
+            (Player::Two, _) => self.pads.get(1).copied().unwrap_or_default(),
+        }
+    }
+
+    fn all_pads(&self) -> u16 {
+        self.pads.iter().fold(0, |mask, pad| mask | pad)
+    }
+}
+
+/// Actions for a single update
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Input {
+    held: [u16; 2],
+    previous: [u16; 2],
+    pad_pressed: Option<PadButton>,
+}
+
+impl Input {
+    /// `previous` is from the last update and is used to work out which actions were just pressed
+    ///
+    /// When `players` is more than one keys bound to the second player are ignored for the
+    /// first player and each player only uses their own controller
+    pub fn new(bindings: &Bindings, players: usize, raw: &RawInput, previous: &RawInput) -> Self {
+        let ignored = if players > 1 {
+            bindings.p2_key_set()
+        } else {
+            HashSet::new()
+        };
+        let none = HashSet::new();
+        let resolve = |raw: &RawInput| {
+            let one = raw.pad_mask(Player::One, players);
+            let two = raw.pad_mask(Player::Two, players);
+            [
+                bindings.resolve(Player::One, &raw.keys, &ignored, one),
+                if players > 1 {
+                    bindings.resolve(Player::Two, &raw.keys, &none, two)
+                } else {
+                    0
+                },
+            ]
+        };
+        let pads = raw.all_pads();
+        let previous_pads = previous.all_pads();
+        let pad_pressed = PadButton::ALL
+            .iter()
+            .find(|button| pads & button.mask() != 0 && previous_pads & button.mask() == 0)
+            .copied();
+        Self {
+            held: resolve(raw),
+            previous: resolve(previous),
+            pad_pressed,
+        }
+    }
+
+    /// Rebuild input from [Input::masks], used for replays
+    ///
+    /// Controller presses for rebinding aren't included as games don't use them
+    pub fn from_masks(held: [u16; 2], previous: [u16; 2]) -> Self {
+        Self {
+            held,
+            previous,
+            pad_pressed: None,
+        }
+    }
+
+    /// Actions held this update and last update for each player
+    pub fn masks(&self) -> ([u16; 2], [u16; 2]) {
+        (self.held, self.previous)
+    }
+
+    /// True if either player is holding any action
+    #[inline]
+    pub fn any_held(&self) -> bool {
+        self.held.iter().any(|mask| *mask != 0)
+    }
+
+    /// True if the first player is holding `action`
+    #[inline]
+    pub fn held(&self, action: Action) -> bool {
+        self.held_by(Player::One, action)
+    }
+
+    /// True if the first player is holding `action` now but wasn't last update
+    #[inline]
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed_by(Player::One, action)
+    }
+
+    #[inline]
+    pub fn held_by(&self, player: Player, action: Action) -> bool {
+        self.held[player as usize] & action.mask() != 0
+    }
+
+    #[inline]
+    pub fn pressed_by(&self, player: Player, action: Action) -> bool {
+        self.held_by(player, action) && self.previous[player as usize] & action.mask() == 0
+    }
+
+    /// Controller button that was just pressed, used when rebinding
+    pub fn pad_pressed(&self) -> Option<PadButton> {
+        self.pad_pressed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::temp_dir;
+
+    fn raw(keys: &[KeyCode]) -> RawInput {
+        RawInput {
+            keys: keys.iter().copied().collect(),
+            pads: vec![],
+        }
+    }
+
+    #[test]
+    fn set_key_swaps_replaced_key() {
+        let mut bindings = DEFAULT_BINDINGS;
+        bindings.set_key(Player::One, Action::Up, 0, KeyCode::KeyS);
+        assert_eq!(
+            bindings.key(Player::One, Action::Up, 0),
+            Some(KeyCode::KeyS)
+        );
+        assert_eq!(
+            bindings.key(Player::One, Action::Down, 1),
+            Some(KeyCode::ArrowUp)
+        );
+        //the second player's keys are separate
+        assert_eq!(
+            bindings.key(Player::Two, Action::Down, 0),
+            Some(KeyCode::KeyS)
+        );
+    }
+
+    #[test]
+    fn set_key_ignores_missing_actions() {
+        let mut bindings = DEFAULT_BINDINGS;
+        bindings.set_key(Player::Two, Action::Pause, 0, KeyCode::KeyQ);
+        assert_eq!(bindings, DEFAULT_BINDINGS);
+        assert_eq!(bindings.key(Player::Two, Action::Pause, 0), None);
+    }
+
+    #[test]
+    fn set_pad_swaps_replaced_button() {
+        let mut bindings = DEFAULT_BINDINGS;
+        bindings.set_pad(Action::Confirm, PadButton::East);
+        assert_eq!(bindings.pad(Action::Confirm), Some(PadButton::East));
+        assert_eq!(bindings.pad(Action::Back), Some(PadButton::South));
+        bindings.set_pad(Action::Secondary, PadButton::North);
+        assert_eq!(bindings.pad(Action::Secondary), Some(PadButton::North));
+        assert!(Action::ALL
+            .iter()
+            .all(|action| bindings.pad(*action) != Some(PadButton::West)));
+    }
+
+    #[test]
+    fn finds_actions_for_keys() {
+        let bindings = DEFAULT_BINDINGS;
+        assert_eq!(
+            bindings.action_for_key(Player::One, KeyCode::KeyA),
+            Some(Action::Left)
+        );
+        assert_eq!(
+            bindings.action_for_key(Player::Two, KeyCode::KeyE),
+            Some(Action::Confirm)
+        );
+        assert_eq!(bindings.action_for_key(Player::Two, KeyCode::ArrowUp), None);
+        assert_eq!(
+            bindings.primary_key(Player::One, Action::Pause),
+            Some(KeyCode::KeyP)
+        );
+        assert_eq!(
+            bindings.press_for_key(1, KeyCode::KeyW),
+            Some((Player::One, Action::Up))
+        );
+        assert_eq!(
+            bindings.press_for_key(2, KeyCode::KeyW),
+            Some((Player::Two, Action::Up))
+        );
+        assert_eq!(
+            bindings.press_for_key(2, KeyCode::ArrowUp),
+            Some((Player::One, Action::Up))
+        );
+        assert_eq!(bindings.press_for_key(2, KeyCode::KeyQ), None);
+    }
+
+    #[test]
+    fn resolves_held_and_pressed() {
+        let input = Input::new(
+            &DEFAULT_BINDINGS,
+            1,
+            &raw(&[KeyCode::ArrowLeft, KeyCode::Space]),
+            &raw(&[KeyCode::Space]),
+        );
+        assert!(input.held(Action::Left));
+        assert!(input.pressed(Action::Left));
+        assert!(input.held(Action::Confirm));
+        assert!(!input.pressed(Action::Confirm));
+        assert!(!input.held(Action::Right));
+        assert!(!input.held_by(Player::Two, Action::Left));
+    }
+
+    #[test]
+    fn resolves_controllers() {
+        let pads = RawInput {
+            keys: HashSet::new(),
+            pads: vec![CNTR_MASK_UP, CNTR_MASK_SOUTH],
+        };
+        let one = Input::new(&DEFAULT_BINDINGS, 1, &pads, &RawInput::default());
+        assert!(one.held(Action::Up) && one.held(Action::Confirm));
+        assert_eq!(one.pad_pressed(), Some(PadButton::DPadUp));
+
+        let two = Input::new(&DEFAULT_BINDINGS, 2, &pads, &RawInput::default());
+        assert!(two.held(Action::Up) && !two.held(Action::Confirm));
+        assert!(two.held_by(Player::Two, Action::Confirm));
+    }
+
+    #[test]
+    fn two_players_split_keys() {
+        let keys = raw(&[KeyCode::KeyW, KeyCode::ArrowDown]);
+        let one = Input::new(&DEFAULT_BINDINGS, 1, &keys, &RawInput::default());
+        assert!(one.held(Action::Up));
+        assert!(one.held(Action::Down));
+
+        let two = Input::new(&DEFAULT_BINDINGS, 2, &keys, &RawInput::default());
+        assert!(!two.held(Action::Up));
+        assert!(two.held(Action::Down));
+        assert!(two.held_by(Player::Two, Action::Up));
+        assert!(!two.held_by(Player::Two, Action::Down));
+    }
+
+    #[test]
+    fn masks_round_trip() {
+        let input = Input::new(
+            &DEFAULT_BINDINGS,
+            2,
+            &raw(&[KeyCode::KeyD, KeyCode::Escape]),
+            &raw(&[KeyCode::Escape]),
+        );
+        let (held, previous) = input.masks();
+        let rebuilt = Input::from_masks(held, previous);
+        assert_eq!(rebuilt, input);
+        assert!(rebuilt.pressed_by(Player::Two, Action::Right));
+        assert!(rebuilt.held(Action::Back) && !rebuilt.pressed(Action::Back));
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let file = format!("bindings_test_{}.prefs", std::process::id());
+        let mut bindings = DEFAULT_BINDINGS;
+        bindings.set_key(Player::One, Action::Confirm, 1, KeyCode::KeyQ);
+        bindings.set_key(Player::Two, Action::Confirm, 0, KeyCode::Tab);
+        bindings.set_pad(Action::Pause, PadButton::North);
+        save_bindings(&mut Preferences::new(temp_dir(), &file), &bindings);
+
+        let mut prefs = Preferences::new(temp_dir(), &file);
+        let loaded = read_bindings(&mut prefs);
+        prefs.delete_file();
+        assert_eq!(loaded, Some(bindings));
+    }
+
+    #[test]
+    fn loads_nothing_if_unsaved() {
+        let file = format!("bindings_missing_{}.prefs", std::process::id());
+        assert_eq!(
+            read_bindings(&mut Preferences::new(temp_dir(), &file)),
+            None
+        );
+    }
+}
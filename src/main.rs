@@ -1,13 +1,38 @@
 #![windows_subsystem = "windows"]
 
+mod audio;
 mod button_bar;
+mod date;
+mod game_over;
+mod gamepads;
 mod games;
+#[cfg(test)]
+mod harness;
+mod high_scores;
+mod input;
+mod palette;
+mod replay;
+mod settings;
+mod sprite;
 
+use crate::audio::Audio;
+use crate::gamepads::Gamepads;
+use crate::games::attract::AttractMode;
+use crate::games::controls::Controls;
+use crate::games::enter_initials::EnterInitials;
+use crate::games::high_score_table::HighScoreTable;
 use crate::games::menu::GameMenu;
-use crate::games::pong::Pong;
-use crate::games::snake::Snake;
+use crate::games::pause::PauseMenu;
+use crate::games::replay::ReplayPlayer;
+use crate::games::settings::SettingsMenu;
+use crate::games::{find_game, random_seed, GameDescriptor};
+use crate::high_scores::HighScores;
+use crate::input::{bindings, load_bindings, Action, Input, Player, RawInput};
+use crate::palette::{load_palettes, palette, recolor};
+use crate::replay::{load_last_replay, load_replay, save_last_replay, Replay};
+use crate::settings::{load_settings, settings};
 use color_eyre::Result;
-use log::LevelFilter;
+use log::{error, LevelFilter};
 use pixels_graphics_lib::buffer_graphics_lib::prelude::*;
 use pixels_graphics_lib::buffer_graphics_lib::text::format::Positioning::LeftBottom;
 use pixels_graphics_lib::buffer_graphics_lib::text::pos::TextPos;
@@ -15,11 +40,16 @@ use pixels_graphics_lib::buffer_graphics_lib::Graphics;
 use pixels_graphics_lib::prelude::PixelFont::Standard4x5;
 use pixels_graphics_lib::prelude::*;
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 const SCREEN_WIDTH: usize = 160;
 const SCREEN_HEIGHT: usize = 166;
+const UPDATES_PER_SECOND: usize = 60;
 
-const INPUT_DELAY: f64 = 0.2;
+const PREFS_QUALIFIER: &str = "app";
+const PREFS_ORG: &str = "emmabritton";
+const PREFS_NAME: &str = "retro_games";
+const WINDOW_PREFS_VERSION: usize = 3;
 
 const CLR_3: Color = GB_3;
 const CLR_2: Color = GB_2;
@@ -36,86 +66,312 @@ fn main() -> Result<()> {
         .format_level(false)
         .init();
 
-    let system = Box::new(GameHost::new());
+    load_bindings();
+    load_settings();
+    load_palettes();
+    let settings = settings();
+    let args = parse_args();
+    let mut host = GameHost::new(args.seed);
+    if let Some(id) = args.game {
+        match find_game(&id) {
+            Some(game) => host.game_stack.push(Layer::game(game, args.seed)),
+            None => error!("Unknown game: {id}"),
+        }
+    }
+    if let Some(replay) = args.replay.as_deref().and_then(load_replay) {
+        host.play(replay);
+    }
+    let system = Box::new(host);
     run(
         SCREEN_WIDTH,
         SCREEN_HEIGHT,
         "Retro Games",
         system,
         Options {
-            ups: 60,
-            vsync: true,
+            ups: UPDATES_PER_SECOND,
+            vsync: settings.vsync,
+            scaling: settings.scale.scaling(),
             ..Options::default()
         },
     )?;
     Ok(())
 }
 
+#[derive(Debug, Default)]
+struct Args {
+    /// Used for every game started
+    seed: Option<u64>,
+    /// Start this game instead of showing the menu
+    game: Option<String>,
+    /// Play this replay file instead of showing the menu
+    replay: Option<PathBuf>,
+}
+
+/// Supports `--seed <number>`, `--game <id>` and `--replay <file>`
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--seed" => match iter.next().map(|value| value.parse::<u64>()) {
+                Some(Ok(seed)) => args.seed = Some(seed),
+                _ => error!("--seed needs a number"),
+            },
+            "--game" => match iter.next() {
+                Some(id) => args.game = Some(id),
+                None => error!("--game needs a game id"),
+            },
+            "--replay" => match iter.next() {
+                Some(path) => args.replay = Some(PathBuf::from(path)),
+                None => error!("--replay needs a file"),
+            },
+            _ => error!("Unknown argument: {arg}"),
+        }
+    }
+    args
+}
+
 struct GameHost {
-    game_stack: Vec<Box<dyn Game>>,
+    game_stack: Vec<Layer>,
     held_keys: HashSet<KeyCode>,
-    controller: GameController,
+    gamepads: Gamepads,
+    /// Input from the last update, used to find newly pressed actions
+    last_input: RawInput,
     keyboard: bool,
+    /// From the command line, used instead of random seeds
+    seed: Option<u64>,
+    audio: Audio,
+}
+
+struct Layer {
+    game: Box<dyn Game>,
+    /// Set for games from the registry, these can be paused and restarted
+    descriptor: Option<&'static GameDescriptor>,
+    /// Drawn over the (dimmed) layer beneath it
+    overlay: bool,
+    /// Set if the game was started with a chosen seed, restarting it will reuse the seed
+    fixed_seed: Option<u64>,
+    /// Input recorded for games, saved as the last replay once the game is closed
+    replay: Option<Replay>,
+}
+
+impl Layer {
+    fn screen(game: Box<dyn Game>) -> Self {
+        Self {
+            game,
+            descriptor: None,
+            overlay: false,
+            fixed_seed: None,
+            replay: None,
+        }
+    }
+
+    /// If `seed` is None a random one is used
+    fn game(descriptor: &'static GameDescriptor, seed: Option<u64>) -> Self {
+        let chosen = seed.unwrap_or_else(random_seed);
+        Self {
+            game: (descriptor.constructor)(chosen),
+            descriptor: Some(descriptor),
+            overlay: false,
+            fixed_seed: seed,
+            replay: Some(Replay::new(descriptor.id, chosen)),
+        }
+    }
+
+    fn overlay(game: Box<dyn Game>) -> Self {
+        Self {
+            game,
+            descriptor: None,
+            overlay: true,
+            fixed_seed: None,
+            replay: None,
+        }
+    }
 }
 
 impl GameHost {
-    pub fn new() -> Self {
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut audio = Audio::new();
+        settings().apply_audio(&mut audio);
         Self {
-            game_stack: vec![Box::new(GameMenu::new())],
+            seed,
+            game_stack: vec![Layer::screen(Box::new(GameMenu::new()))],
             held_keys: HashSet::new(),
-            controller: GameController::new_unchecked(),
+            gamepads: Gamepads::new(),
+            last_input: RawInput::default(),
             keyboard: false,
+            audio,
         }
     }
 }
 
-impl System for GameHost {
-    fn keys_used(&self) -> &[KeyCode] {
-        &[
-            KeyCode::ArrowUp,
-            KeyCode::ArrowDown,
-            KeyCode::ArrowLeft,
-            KeyCode::ArrowRight,
-            KeyCode::Escape,
-            KeyCode::Space,
-        ]
+impl GameHost {
+    fn pop(&mut self) -> Option<Layer> {
+        let layer = self.game_stack.pop();
+        if let Some(replay) = layer.as_ref().and_then(|layer| layer.replay.as_ref()) {
+            save_last_replay(replay);
+        }
+        if let Some(layer) = self.game_stack.last_mut() {
+            layer.game.resuming();
+            if let Some(replay) = &mut layer.replay {
+                replay.resumed();
+            }
+        }
+        layer
+    }
+
+    fn play(&mut self, replay: Replay) {
+        match find_game(replay.game) {
+            Some(game) => self
+                .game_stack
+                .push(Layer::screen(ReplayPlayer::new(game, replay))),
+            None => error!("Unknown game: {}", replay.game),
+        }
+    }
+
+    /// Opens the pause menu if pause or back was just pressed and a game is being played
+    fn check_pause(&mut self, input: &Input) -> bool {
+        let requested = input.pressed(Action::Pause) || input.pressed(Action::Back);
+        let playing = self
+            .game_stack
+            .last()
+            .map(|layer| layer.descriptor.is_some())
+            .unwrap_or(false);
+        if requested && playing {
+            self.game_stack.push(Layer::overlay(PauseMenu::new()));
+        }
+        requested && playing
     }
+}
 
+/// Darken every pixel by one shade
+fn dim(graphics: &mut Graphics) {
+    for y in 0..graphics.height() as isize {
+        for x in 0..graphics.width() as isize {
+            let color = match graphics.get_pixel(x, y, false) {
+                Some(CLR_3) => CLR_2,
+                Some(CLR_2) => CLR_1,
+                _ => CLR_0,
+            };
+            graphics.set_pixel(x, y, color);
+        }
+    }
+}
+
+impl System for GameHost {
     fn window_prefs(&mut self) -> Option<WindowPreferences> {
-        Some(WindowPreferences::new("app", "emmabritton", "retro_games", 3).unwrap())
+        //each scale has its own file, otherwise the saved window size would override a new scale
+        let version = WINDOW_PREFS_VERSION * 10 + settings().scale as usize;
+        Some(WindowPreferences::new(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME, version).unwrap())
     }
 
     fn update(&mut self, timing: &Timing, _: &Window) {
-        self.controller.update();
-        if self.controller.mask() != 0 {
+        self.gamepads.update();
+        self.audio.update(timing);
+        let raw = RawInput {
+            keys: self.held_keys.clone(),
+            pads: self.gamepads.masks(),
+        };
+        if raw.pads.iter().any(|pad| *pad != 0) {
             self.keyboard = false;
         }
-        if let Some(game) = self.game_stack.last_mut() {
-            match game.update(timing, &self.held_keys.iter().collect(), &self.controller) {
+        let players = self
+            .game_stack
+            .last()
+            .map(|layer| layer.game.players())
+            .unwrap_or(1);
+        let input = Input::new(&bindings(), players, &raw, &self.last_input);
+        self.last_input = raw;
+        if self.check_pause(&input) {
+            return;
+        }
+        if let Some(layer) = self.game_stack.last_mut() {
+            if let Some(replay) = &mut layer.replay {
+                replay.record(&input);
+            }
+            match layer.game.update(timing, &input, &mut self.audio) {
                 GameUpdateResult::Nothing => {}
-                GameUpdateResult::Push(new_game) => match new_game {
-                    GameName::Pong => self.game_stack.push(Pong::new()),
-                    GameName::Snake => self.game_stack.push(Snake::new()),
+                GameUpdateResult::Push(screen) => match screen {
+                    Screen::Game { id, seed } => match find_game(id) {
+                        Some(game) => self.game_stack.push(Layer::game(game, seed.or(self.seed))),
+                        None => error!("Unknown game: {id}"),
+                    },
+                    Screen::HighScores => {
+                        self.game_stack.push(Layer::screen(HighScoreTable::new()))
+                    }
+                    Screen::Controls => self.game_stack.push(Layer::screen(Controls::new())),
+                    Screen::Settings => self.game_stack.push(Layer::screen(SettingsMenu::new())),
+                    Screen::Attract { id } => match find_game(id).and_then(|game| game.demo) {
+                        Some(demo) => self
+                            .game_stack
+                            .push(Layer::screen(AttractMode::new(demo(random_seed())))),
+                        None => error!("No demo for game: {id}"),
+                    },
+                    Screen::Replay => {
+                        if let Some(replay) = load_last_replay() {
+                            self.play(replay);
+                        }
+                    }
+                    Screen::EnterInitials { game, score } => {
+                        let scores = HighScores::load();
+                        if scores.qualifies(game, score) {
+                            self.game_stack
+                                .push(Layer::screen(EnterInitials::new(game, score, scores)));
+                        } else {
+                            //as if the screen had opened and closed, so the game stops asking
+                            layer.game.resuming();
+                            if let Some(replay) = &mut layer.replay {
+                                replay.resumed();
+                            }
+                        }
+                    }
                 },
                 GameUpdateResult::Pop => {
-                    self.game_stack.remove(self.game_stack.len() - 1);
-                    if let Some(game) = self.game_stack.last_mut() {
-                        game.resuming();
+                    self.pop();
+                }
+                GameUpdateResult::Restart => {
+                    self.game_stack.pop();
+                    match self.game_stack.pop() {
+                        Some(Layer {
+                            descriptor: Some(descriptor),
+                            fixed_seed,
+                            replay,
+                            ..
+                        }) => {
+                            if let Some(replay) = replay {
+                                save_last_replay(&replay);
+                            }
+                            self.game_stack.push(Layer::game(descriptor, fixed_seed))
+                        }
+                        Some(layer) => {
+                            error!("Restart requested over a non game screen");
+                            self.game_stack.push(layer);
+                        }
+                        None => {}
                     }
                 }
+                GameUpdateResult::Quit => {
+                    self.game_stack.pop();
+                    self.pop();
+                }
             }
         }
     }
 
     fn render(&mut self, graphics: &mut Graphics) {
         graphics.clear(CLR_0);
-        if let Some(game) = self.game_stack.last() {
-            let controller = if self.keyboard {
-                None
-            } else {
-                self.controller.get_controller_type()
-            };
-            game.render(graphics, controller);
+        let controller = if self.keyboard {
+            None
+        } else {
+            self.gamepads.controller_type()
+        };
+        if let Some(layer) = self.game_stack.last() {
+            if layer.overlay && self.game_stack.len() > 1 {
+                let beneath = &self.game_stack[self.game_stack.len() - 2];
+                beneath.game.render(graphics, controller);
+                dim(graphics);
+            }
+            layer.game.render(graphics, controller);
         }
         if cfg!(debug_assertions) {
             graphics.draw_text(
@@ -124,10 +380,23 @@ impl System for GameHost {
                 (CLR_1, Standard4x5, LeftBottom),
             );
         }
+        recolor(graphics, &palette(settings().palette));
     }
 
     fn on_key_down(&mut self, keys: Vec<KeyCode>) {
         self.keyboard = true;
+        if let Some(layer) = self.game_stack.last_mut() {
+            let players = layer.game.players();
+            for key in &keys {
+                layer.game.on_key_press(*key);
+                if let Some((player, action)) = bindings().press_for_key(players, *key) {
+                    if let Some(replay) = &mut layer.replay {
+                        replay.press(player, action);
+                    }
+                    layer.game.on_press(player, action);
+                }
+            }
+        }
         for key in keys {
             self.held_keys.insert(key);
         }
@@ -137,11 +406,6 @@ impl System for GameHost {
         for key in &keys {
             self.held_keys.remove(key);
         }
-        if let Some(game) = self.game_stack.last_mut() {
-            for key in &keys {
-                game.on_key_press(*key)
-            }
-        }
     }
 
     fn should_exit(&mut self) -> bool {
@@ -151,26 +415,52 @@ impl System for GameHost {
 
 trait Game {
     fn render(&self, graphics: &mut Graphics, controller: Option<Controller>);
+    /// Called when `key` is first pressed, for anything that needs the raw key
     fn on_key_press(&mut self, key: KeyCode);
-    #[allow(clippy::ptr_arg)] //breaks other code if changed
-    fn update(
-        &mut self,
-        timing: &Timing,
-        held_keys: &Vec<&KeyCode>,
-        controller: &GameController,
-    ) -> GameUpdateResult;
+    /// Called when a key bound to `action` is pressed, before the next update
+    ///
+    /// Unlike [Input::pressed] this sees every press in order, even ones released before the
+    /// next update, they're recorded in replays
+    fn on_press(&mut self, _: Player, _: Action) {}
+    fn update(&mut self, timing: &Timing, input: &Input, audio: &mut Audio) -> GameUpdateResult;
     fn resuming(&mut self);
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum GameName {
-    Pong,
-    Snake,
+    /// Number of people playing, with two the second player gets their own keys and controller
+    fn players(&self) -> usize {
+        1
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum GameUpdateResult {
     Nothing,
-    Push(GameName),
+    Push(Screen),
     Pop,
+    /// Close this screen and restart the game beneath it
+    Restart,
+    /// Close this screen and the game beneath it
+    Quit,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Screen {
+    /// Start the game with this [id][games::GameDescriptor::id], with a random seed if `seed` is None
+    Game {
+        id: &'static str,
+        seed: Option<u64>,
+    },
+    HighScores,
+    /// Key and controller bindings
+    Controls,
+    Settings,
+    /// Play back the last game played
+    Replay,
+    /// Play the [demo][games::GameDescriptor::demo] of the game with this id until there's any input
+    Attract {
+        id: &'static str,
+    },
+    /// Ask for initials for a new entry in the high score table for `game`
+    EnterInitials {
+        game: &'static str,
+        score: usize,
+    },
 }
@@ -0,0 +1,197 @@
+use crate::{CLR_0, CLR_1, CLR_2, CLR_3, PREFS_NAME, PREFS_ORG, PREFS_QUALIFIER};
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use std::borrow::Cow;
+use std::fs;
+use std::sync::RwLock;
+
+const CUSTOM_FILE: &str = "palettes.txt";
+
+static CUSTOM_PALETTES: RwLock<Vec<Palette>> = RwLock::new(Vec::new());
+
+/// Four colours that replace [CLR_0] to [CLR_3] (lightest to darkest) when the screen is drawn
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Palette {
+    pub name: Cow<'static, str>,
+    pub colors: [Color; 4],
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::new(r, g, b, 255)
+}
+
+pub const PALETTES: [Palette; 5] = [
+    Palette {
+        name: Cow::Borrowed("GREEN"),
+        colors: [CLR_0, CLR_1, CLR_2, CLR_3],
+    },
+    Palette {
+        name: Cow::Borrowed("POCKET"),
+        colors: [
+            rgb(196, 207, 161),
+            rgb(139, 149, 109),
+            rgb(77, 83, 60),
+            rgb(31, 31, 31),
+        ],
+    },
+    Palette {
+        name: Cow::Borrowed("AMBER"),
+        colors: [
+            rgb(255, 176, 0),
+            rgb(191, 120, 0),
+            rgb(122, 68, 0),
+            rgb(40, 20, 0),
+        ],
+    },
+    Palette {
+        name: Cow::Borrowed("CGA"),
+        colors: [
+            rgb(255, 255, 255),
+            rgb(85, 255, 255),
+            rgb(255, 85, 255),
+            rgb(0, 0, 0),
+        ],
+    },
+    Palette {
+        name: Cow::Borrowed("HIGH CONTRAST"),
+        colors: [
+            rgb(255, 255, 255),
+            rgb(170, 170, 170),
+            rgb(85, 85, 85),
+            rgb(0, 0, 0),
+        ],
+    },
+];
+
+/// Built in palettes followed by any custom ones, [Settings::palette][crate::settings::Settings::palette] is an index into this
+pub fn palettes() -> Vec<Palette> {
+    let mut list = PALETTES.to_vec();
+    list.extend(CUSTOM_PALETTES.read().unwrap().iter().cloned());
+    list
+}
+
+/// Palette at `idx` in [palettes], or [PALETTES] (GREEN) if there isn't one (e.g. the custom file was edited)
+pub fn palette(idx: usize) -> Palette {
+    palettes()
+        .get(idx)
+        .cloned()
+        .unwrap_or_else(|| PALETTES[0].clone())
+}
+
+/// Parse custom palettes, one per line as `NAME RRGGBB RRGGBB RRGGBB RRGGBB` (lightest to darkest)
+///
+/// Colours can start with `#`, blank lines and lines starting with `#` are ignored,
+/// invalid lines are logged and skipped
+pub fn parse_palettes(text: &str) -> Vec<Palette> {
+    let mut list = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 {
+            error!(
+                "Invalid palette on line {}: needs a name and 4 colours",
+                i + 1
+            );
+            continue;
+        }
+        let (name, hex) = parts.split_at(parts.len() - 4);
+        let colors: Option<Vec<Color>> = hex.iter().map(|hex| parse_color(hex)).collect();
+        match colors {
+            Some(colors) => list.push(Palette {
+                name: Cow::Owned(name.join(" ").to_uppercase()),
+                colors: colors.try_into().unwrap(),
+            }),
+            None => error!("Invalid palette on line {}: colours must be RRGGBB", i + 1),
+        }
+    }
+    list
+}
+
+fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+/// Replace the custom palettes with those in `palettes.txt` in the preferences folder, if it exists
+pub fn load_palettes() {
+    let dir = match get_pref_dir(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME) {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Unable to find palettes dir: {e:?}");
+            return;
+        }
+    };
+    let path = dir.join(CUSTOM_FILE);
+    if !path.exists() {
+        return;
+    }
+    match fs::read_to_string(&path) {
+        Ok(text) => *CUSTOM_PALETTES.write().unwrap() = parse_palettes(&text),
+        Err(e) => error!("Unable to load palettes: {e:?}"),
+    }
+}
+
+/// Replace every [CLR_0] to [CLR_3] pixel with the matching colour from `palette`
+pub fn recolor(graphics: &mut Graphics, palette: &Palette) {
+    if palette.colors == PALETTES[0].colors {
+        return;
+    }
+    for y in 0..graphics.height() as isize {
+        for x in 0..graphics.width() as isize {
+            let color = match graphics.get_pixel(x, y, false) {
+                Some(CLR_0) => palette.colors[0],
+                Some(CLR_1) => palette.colors[1],
+                Some(CLR_2) => palette.colors[2],
+                Some(CLR_3) => palette.colors[3],
+                _ => continue,
+            };
+            graphics.set_pixel(x, y, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_custom_palettes() {
+        let text = "
+# comment
+ice #E0F8FF 88C0D0 4C566A 2E3440
+too few 112233
+bad colours 11223G 112233 112233 112233
+";
+        let list = parse_palettes(text);
+        assert_eq!(
+            list,
+            vec![Palette {
+                name: Cow::Owned(String::from("ICE")),
+                colors: [
+                    rgb(0xE0, 0xF8, 0xFF),
+                    rgb(0x88, 0xC0, 0xD0),
+                    rgb(0x4C, 0x56, 0x6A),
+                    rgb(0x2E, 0x34, 0x40)
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn names_can_have_spaces() {
+        let list = parse_palettes("deep sea 000000 000000 000000 000000");
+        assert_eq!(list[0].name, "DEEP SEA");
+    }
+
+    #[test]
+    fn missing_palette_falls_back_to_green() {
+        assert_eq!(palette(usize::MAX), PALETTES[0]);
+    }
+}
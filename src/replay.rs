@@ -0,0 +1,380 @@
+use crate::audio::Audio;
+use crate::games::find_game;
+use crate::input::{Action, Input, Player};
+use crate::{Game, PREFS_NAME, PREFS_ORG, PREFS_QUALIFIER};
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"RGRP";
+//version 1 files are read as well, they're the same without presses
+const VERSION: u8 = 2;
+const LAST_REPLAY: &str = "last.replay";
+const TAG_INPUT: u8 = 0;
+const TAG_RESUMED: u8 = 1;
+const TAG_PRESS: u8 = 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReplayEvent {
+    /// The same input for `ticks` updates in a row
+    Input {
+        held: [u16; 2],
+        previous: [u16; 2],
+        ticks: u32,
+    },
+    /// The game was resumed after a screen over it was closed
+    Resumed,
+    /// A key was pressed between updates, see [Game::on_press]
+    Press { player: Player, action: Action },
+}
+
+/// Everything needed to play a game back exactly: the game, its seed and the input for every update
+///
+/// Saved as the magic bytes, version, seed, game id and then each event,
+/// runs of identical input are stored once with a count
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Replay {
+    pub game: &'static str,
+    pub seed: u64,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new(game: &'static str, seed: u64) -> Self {
+        Self {
+            game,
+            seed,
+            events: vec![],
+        }
+    }
+}
+
+impl Replay {
+    /// Add the input for one update
+    pub fn record(&mut self, input: &Input) {
+        let (held, previous) = input.masks();
+        if let Some(ReplayEvent::Input {
+            held: last_held,
+            previous: last_previous,
+            ticks,
+        }) = self.events.last_mut()
+        {
+            if *last_held == held && *last_previous == previous && *ticks < u32::MAX {
+                *ticks += 1;
+                return;
+            }
+        }
+        self.events.push(ReplayEvent::Input {
+            held,
+            previous,
+            ticks: 1,
+        });
+    }
+
+    pub fn resumed(&mut self) {
+        self.events.push(ReplayEvent::Resumed);
+    }
+
+    pub fn press(&mut self, player: Player, action: Action) {
+        self.events.push(ReplayEvent::Press { player, action });
+    }
+
+    /// Number of updates recorded
+    pub fn ticks(&self) -> u64 {
+        self.events
+            .iter()
+            .map(|event| match event {
+                ReplayEvent::Input { ticks, .. } => *ticks as u64,
+                ReplayEvent::Resumed | ReplayEvent::Press { .. } => 0,
+            })
+            .sum()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(self.game.len() as u8);
+        bytes.extend_from_slice(self.game.as_bytes());
+        for event in &self.events {
+            match event {
+                ReplayEvent::Input {
+                    held,
+                    previous,
+                    ticks,
+                } => {
+                    bytes.push(TAG_INPUT);
+                    for mask in held.iter().chain(previous) {
+                        bytes.extend_from_slice(&mask.to_le_bytes());
+                    }
+                    bytes.extend_from_slice(&ticks.to_le_bytes());
+                }
+                ReplayEvent::Resumed => bytes.push(TAG_RESUMED),
+                ReplayEvent::Press { player, action } => {
+                    bytes.push(TAG_PRESS);
+                    bytes.push(*player as u8);
+                    bytes.push(*action as u8);
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, String> {
+        if take::<4>(&mut bytes)? != *MAGIC {
+            return Err(String::from("Not a replay file"));
+        }
+        let version = take::<1>(&mut bytes)?[0];
+        if !(1..=VERSION).contains(&version) {
+            return Err(format!("Unsupported replay version {version}"));
+        }
+        let seed = u64::from_le_bytes(take(&mut bytes)?);
+        let len = take::<1>(&mut bytes)?[0] as usize;
+        if bytes.len() < len {
+            return Err(String::from("Replay is truncated"));
+        }
+        let (id, rest) = bytes.split_at(len);
+        bytes = rest;
+        let id = String::from_utf8_lossy(id);
+        let game = find_game(&id)
+            .ok_or_else(|| format!("Unknown game: {id}"))?
+            .id;
+        let mut events = vec![];
+        while !bytes.is_empty() {
+            match take::<1>(&mut bytes)?[0] {
+                TAG_INPUT => {
+                    let mut masks = [0; 4];
+                    for mask in &mut masks {
+                        *mask = u16::from_le_bytes(take(&mut bytes)?);
+                    }
+                    events.push(ReplayEvent::Input {
+                        held: [masks[0], masks[1]],
+                        previous: [masks[2], masks[3]],
+                        ticks: u32::from_le_bytes(take(&mut bytes)?),
+                    });
+                }
+                TAG_RESUMED => events.push(ReplayEvent::Resumed),
+                TAG_PRESS => {
+                    let [player, action] = take(&mut bytes)?;
+                    let player = match player {
+                        0 => Player::One,
+                        1 => Player::Two,
+                        _ => return Err(format!("Invalid replay player {player}")),
+                    };
+                    let action = *Action::ALL
+                        .get(action as usize)
+                        .ok_or_else(|| format!("Invalid replay action {action}"))?;
+                    events.push(ReplayEvent::Press { player, action });
+                }
+                tag => return Err(format!("Invalid replay event {tag}")),
+            }
+        }
+        Ok(Self { game, seed, events })
+    }
+}
+
+/// Position in a [Replay] being played back
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Playback {
+    /// Index of the next event to play
+    event: usize,
+    /// Updates already played from the current event
+    tick: u32,
+}
+
+impl Playback {
+    pub fn finished(&self, replay: &Replay) -> bool {
+        self.event >= replay.events.len()
+    }
+
+    /// Play events from `replay` into `game` up to and including the next update, returns false
+    /// if the replay has finished
+    pub fn step(
+        &mut self,
+        replay: &Replay,
+        game: &mut dyn Game,
+        timing: &Timing,
+        audio: &mut Audio,
+    ) -> bool {
+        while let Some(event) = replay.events.get(self.event) {
+            match *event {
+                ReplayEvent::Resumed => {
+                    game.resuming();
+                    self.event += 1;
+                }
+                ReplayEvent::Press { player, action } => {
+                    game.on_press(player, action);
+                    self.event += 1;
+                }
+                ReplayEvent::Input {
+                    held,
+                    previous,
+                    ticks,
+                } => {
+                    game.update(timing, &Input::from_masks(held, previous), audio);
+                    self.tick += 1;
+                    if self.tick >= ticks {
+                        self.tick = 0;
+                        self.event += 1;
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Remove the first `N` bytes from `bytes`
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], String> {
+    if bytes.len() < N {
+        return Err(String::from("Replay is truncated"));
+    }
+    let (start, rest) = bytes.split_at(N);
+    *bytes = rest;
+    Ok(start.try_into().unwrap())
+}
+
+fn last_replay_path() -> Option<PathBuf> {
+    match get_pref_dir(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME) {
+        Ok(dir) => Some(dir.join(LAST_REPLAY)),
+        Err(e) => {
+            error!("Unable to find replay dir: {e:?}");
+            None
+        }
+    }
+}
+
+/// Save `replay` as the last game played, empty replays are ignored
+pub fn save_last_replay(replay: &Replay) {
+    if replay.ticks() == 0 {
+        return;
+    }
+    let Some(path) = last_replay_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            error!("Unable to create replay dir: {e:?}");
+            return;
+        }
+    }
+    if let Err(e) = fs::write(&path, replay.to_bytes()) {
+        error!("Unable to save replay: {e:?}");
+    }
+}
+
+pub fn load_last_replay() -> Option<Replay> {
+    load_replay(&last_replay_path()?)
+}
+
+pub fn load_replay(path: &Path) -> Option<Replay> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Unable to read replay {path:?}: {e:?}");
+            return None;
+        }
+    };
+    match Replay::from_bytes(&bytes) {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            error!("Unable to load replay {path:?}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::games::pong;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(pong::ID, 42);
+        replay.record(&Input::from_masks([0, 0], [0, 0]));
+        replay.record(&Input::from_masks([0, 0], [0, 0]));
+        replay.press(Player::Two, Action::Confirm);
+        replay.record(&Input::from_masks([1, 16], [0, 0]));
+        replay.resumed();
+        replay.record(&Input::from_masks([1, 16], [1, 16]));
+        replay
+    }
+
+    #[test]
+    fn records_runs_of_input() {
+        let replay = replay();
+        assert_eq!(replay.events.len(), 5);
+        assert_eq!(
+            replay.events[0],
+            ReplayEvent::Input {
+                held: [0, 0],
+                previous: [0, 0],
+                ticks: 2
+            }
+        );
+        assert_eq!(replay.ticks(), 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+    }
+
+    #[test]
+    fn reads_version_1() {
+        let mut replay = Replay::new(pong::ID, 42);
+        replay.record(&Input::from_masks([4, 0], [0, 0]));
+        replay.resumed();
+        let mut bytes = replay.to_bytes();
+        bytes[MAGIC.len()] = 1;
+        assert_eq!(Replay::from_bytes(&bytes), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut bytes = replay().to_bytes();
+        bytes[0] = b'X';
+        assert_eq!(
+            Replay::from_bytes(&bytes),
+            Err(String::from("Not a replay file"))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_version() {
+        let mut bytes = replay().to_bytes();
+        for version in [0, VERSION + 1] {
+            bytes[MAGIC.len()] = version;
+            assert_eq!(
+                Replay::from_bytes(&bytes),
+                Err(format!("Unsupported replay version {version}"))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let bytes = replay().to_bytes();
+        //inside the seed, the game id and the last event
+        for len in [2, 8, 15, bytes.len() - 1] {
+            assert_eq!(
+                Replay::from_bytes(&bytes[..len]),
+                Err(String::from("Replay is truncated")),
+                "{len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_events() {
+        let mut bytes = replay().to_bytes();
+        bytes.push(9);
+        assert_eq!(
+            Replay::from_bytes(&bytes),
+            Err(String::from("Invalid replay event 9"))
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::audio::{Audio, Channel};
+use crate::{PREFS_NAME, PREFS_ORG, PREFS_QUALIFIER};
+use log::error;
+use pixels_graphics_lib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+const FILE_NAME: &str = "settings.prefs";
+const KEY: &str = "settings";
+
+/// Volumes go from 0 to this
+pub const VOLUME_STEPS: u8 = 10;
+/// Seconds, choices for [Settings::input_delay]
+pub const INPUT_DELAYS: [f64; 5] = [0.1, 0.15, 0.2, 0.25, 0.3];
+
+static SETTINGS: RwLock<Settings> = RwLock::new(DEFAULT_SETTINGS);
+
+/// Size of the window, see [WindowScaling]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Scale {
+    Native,
+    Double,
+    Quad,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 3] = [Scale::Native, Scale::Double, Scale::Quad];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scale::Native => "NATIVE",
+            Scale::Double => "DOUBLE",
+            Scale::Quad => "QUAD",
+        }
+    }
+
+    pub fn scaling(&self) -> WindowScaling {
+        match self {
+            Scale::Native => WindowScaling::Native,
+            Scale::Double => WindowScaling::Double,
+            Scale::Quad => WindowScaling::Quad,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// 0 to [VOLUME_STEPS], indexed by [Channel]
+    pub volumes: [u8; Channel::ALL.len()],
+    pub muted: bool,
+    /// Only applied at startup
+    pub scale: Scale,
+    /// Index into [palettes][crate::palette::palettes]
+    pub palette: usize,
+    /// Only applied at startup
+    pub vsync: bool,
+    /// Seconds between repeats when holding a direction in menus
+    pub input_delay: f64,
+}
+
+pub const DEFAULT_SETTINGS: Settings = Settings {
+    volumes: [VOLUME_STEPS; Channel::ALL.len()],
+    muted: false,
+    scale: Scale::Double,
+    palette: 0,
+    vsync: true,
+    input_delay: 0.2,
+};
+
+impl Default for Settings {
+    fn default() -> Self {
+        DEFAULT_SETTINGS
+    }
+}
+
+impl Settings {
+    pub fn volume(&self, channel: Channel) -> u8 {
+        self.volumes[channel as usize]
+    }
+
+    pub fn apply_audio(&self, audio: &mut Audio) {
+        for channel in Channel::ALL {
+            audio.set_volume(channel, self.volume(channel) as f32 / VOLUME_STEPS as f32);
+        }
+        audio.set_muted(self.muted);
+    }
+}
+
+fn prefs() -> Option<Preferences<Settings>> {
+    match get_pref_dir(PREFS_QUALIFIER, PREFS_ORG, PREFS_NAME) {
+        Ok(dir) => Some(Preferences::new(dir, FILE_NAME)),
+        Err(e) => {
+            error!("Unable to find settings dir: {e:?}");
+            None
+        }
+    }
+}
+
+/// Current settings
+pub fn settings() -> Settings {
+    *SETTINGS.read().unwrap()
+}
+
+/// Seconds between repeats when holding a direction in menus
+pub fn input_delay() -> f64 {
+    settings().input_delay
+}
+
+/// Replace the current settings and save them
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.write().unwrap() = settings;
+    if let Some(mut prefs) = prefs() {
+        save_settings(&mut prefs, settings);
+    }
+}
+
+/// Replace the current settings with any that were saved
+pub fn load_settings() {
+    let Some(mut prefs) = prefs() else {
+        return;
+    };
+    if let Some(saved) = read_settings(&mut prefs) {
+        *SETTINGS.write().unwrap() = saved;
+    }
+}
+
+fn save_settings(prefs: &mut Preferences<Settings>, settings: Settings) {
+    prefs.set(KEY, settings);
+    if let Err(e) = prefs.save() {
+        error!("Unable to save settings: {e:?}");
+    }
+}
+
+fn read_settings(prefs: &mut Preferences<Settings>) -> Option<Settings> {
+    if let Err(e) = prefs.load() {
+        error!("Unable to load settings: {e:?}");
+        return None;
+    }
+    prefs.get(KEY).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn saves_and_loads() {
+        let file = format!("settings_test_{}.prefs", std::process::id());
+        let settings = Settings {
+            volumes: [3, 0, VOLUME_STEPS],
+            muted: true,
+            scale: Scale::Quad,
+            palette: 2,
+            vsync: false,
+            input_delay: INPUT_DELAYS[4],
+        };
+        save_settings(&mut Preferences::new(temp_dir(), &file), settings);
+
+        let mut prefs = Preferences::new(temp_dir(), &file);
+        let loaded = read_settings(&mut prefs);
+        prefs.delete_file();
+        assert_eq!(loaded, Some(settings));
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let file = format!("settings_partial_{}.prefs", std::process::id());
+        let path = temp_dir().join(&file);
+        fs::write(&path, r#"{"settings":{"muted":true,"scale":"Native"}}"#).unwrap();
+
+        let mut prefs = Preferences::new(temp_dir(), &file);
+        let loaded = read_settings(&mut prefs);
+        prefs.delete_file();
+        assert_eq!(
+            loaded,
+            Some(Settings {
+                muted: true,
+                scale: Scale::Native,
+                ..DEFAULT_SETTINGS
+            })
+        );
+    }
+
+    #[test]
+    fn loads_nothing_if_unsaved() {
+        let file = format!("settings_missing_{}.prefs", std::process::id());
+        assert_eq!(
+            read_settings(&mut Preferences::new(temp_dir(), &file)),
+            None
+        );
+    }
+}
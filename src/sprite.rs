@@ -0,0 +1,19 @@
+use pixels_graphics_lib::prelude::*;
+
+/// Draw a 1 bit sprite, each row is read from the most significant bit (of `width`) down
+pub fn draw_sprite<T: Into<u32> + Copy>(
+    graphics: &mut Graphics,
+    pos: Coord,
+    rows: &[T],
+    width: usize,
+    color: Color,
+) {
+    for (y, row) in rows.iter().enumerate() {
+        let row: u32 = (*row).into();
+        for x in 0..width {
+            if (row >> (width - 1 - x)) & 1 == 1 {
+                graphics.set_pixel(pos.x + x as isize, pos.y + y as isize, color);
+            }
+        }
+    }
+}